@@ -0,0 +1,322 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures_core::Stream;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<Budget>>> = const { RefCell::new(None) };
+}
+
+/// Limits on the database work of a request, applied with [`with_budget`].
+///
+/// No limit is set by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueryBudget {
+    max_statements: Option<u64>,
+    max_duration: Option<Duration>,
+    enforce: bool,
+}
+
+impl QueryBudget {
+    /// Returns a budget without limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the number of statements.
+    pub fn with_max_statements(mut self, statements: u64) -> Self {
+        self.max_statements = Some(statements);
+        self
+    }
+
+    /// Limit the cumulative time spent running statements.
+    pub fn with_max_duration(mut self, duration: Duration) -> Self {
+        self.max_duration = Some(duration);
+        self
+    }
+
+    /// Enable or disable failing the statements started once the budget is
+    /// exhausted with a [`QueryBudgetExceeded`] error, instead of only
+    /// emitting a warning event.
+    ///
+    /// Disabled by default.
+    pub fn with_enforcement(mut self, enforce: bool) -> Self {
+        self.enforce = enforce;
+        self
+    }
+}
+
+/// Runs `future` with every database operation started within it charged to
+/// `budget`.
+///
+/// This is a guard-rail against runaway request handlers, e.g. an N+1 query
+/// loop, meant to be set by request middleware. Every statement counts
+/// against the budget, along with the time it spent running, and records
+/// what was left of the budget when it started as
+/// `db.query.budget.remaining_statements` and `db.query.budget.remaining_ms`.
+///
+/// The first statement started once the budget is exhausted emits a
+/// `sqlx.query_budget.exhausted` warning event. With
+/// [`with_enforcement`](QueryBudget::with_enforcement), that statement and
+/// the following ones fail without reaching the database, with a
+/// [`QueryBudgetExceeded`] error. Statements within nested budgets are
+/// charged to the enclosing ones as well.
+///
+/// Like [`with_deadline`](crate::with_deadline), the budget follows the
+/// future across threads, as it is installed each time the future is polled,
+/// but not into tasks spawned from it.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let budget = sqlx_tracing::QueryBudget::new()
+///     .with_max_statements(50)
+///     .with_max_duration(Duration::from_millis(200));
+/// sqlx_tracing::with_budget(budget, handle(request)).await
+/// ```
+pub fn with_budget<F: Future>(budget: QueryBudget, future: F) -> WithBudget<F> {
+    WithBudget {
+        inner: future,
+        budget: Arc::new(Budget {
+            limits: budget,
+            statements: AtomicU64::new(0),
+            elapsed_us: AtomicU64::new(0),
+            warned: AtomicBool::new(false),
+            parent: current(),
+        }),
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// Future returned by [`with_budget`].
+    #[derive(Debug)]
+    pub struct WithBudget<F> {
+        #[pin]
+        inner: F,
+        budget: Arc<Budget>,
+    }
+}
+
+impl<F: Future> Future for WithBudget<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _scope = crate::scoped::install(&CURRENT, Some(this.budget.clone()));
+        this.inner.poll(cx)
+    }
+}
+
+fn current() -> Option<Arc<Budget>> {
+    crate::scoped::current(&CURRENT)
+}
+
+/// Usage of a [`QueryBudget`], shared by the operations of a request.
+#[derive(Debug)]
+struct Budget {
+    limits: QueryBudget,
+    statements: AtomicU64,
+    elapsed_us: AtomicU64,
+    warned: AtomicBool,
+    parent: Option<Arc<Budget>>,
+}
+
+impl Budget {
+    /// Returns this budget and the enclosing ones.
+    fn chain(self: &Arc<Self>) -> impl Iterator<Item = &Arc<Self>> {
+        std::iter::successors(Some(self), |budget| budget.parent.as_ref())
+    }
+
+    fn elapsed(&self) -> Duration {
+        Duration::from_micros(self.elapsed_us.load(Ordering::Relaxed))
+    }
+
+    /// Counts a statement, returning the statements and time left before it
+    /// started, or `None` for unlimited ones.
+    fn charge(&self) -> (Option<u64>, Option<Duration>) {
+        let used = self.statements.fetch_add(1, Ordering::Relaxed);
+        let statements = self
+            .limits
+            .max_statements
+            .map(|max| max.saturating_sub(used));
+        let duration = self
+            .limits
+            .max_duration
+            .map(|max| max.saturating_sub(self.elapsed()));
+        (statements, duration)
+    }
+}
+
+/// Error returned by database operations started once the budget set with
+/// [`with_budget`] is exhausted, when enforced.
+///
+/// It is surfaced as an [`sqlx::Error::Io`] of kind
+/// [`QuotaExceeded`](std::io::ErrorKind::QuotaExceeded) wrapping this type,
+/// which [`QueryBudgetExceeded::is`] detects.
+#[derive(Debug)]
+pub struct QueryBudgetExceeded;
+
+impl QueryBudgetExceeded {
+    /// Returns whether `err` was caused by an exhausted query budget.
+    pub fn is(err: &sqlx::Error) -> bool {
+        match err {
+            sqlx::Error::Io(err) => err.get_ref().is_some_and(|inner| inner.is::<Self>()),
+            _ => false,
+        }
+    }
+
+    fn error() -> sqlx::Error {
+        sqlx::Error::Io(std::io::Error::new(std::io::ErrorKind::QuotaExceeded, Self))
+    }
+}
+
+impl std::fmt::Display for QueryBudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("query budget exhausted before the database operation started")
+    }
+}
+
+impl std::error::Error for QueryBudgetExceeded {}
+
+/// Time spent by a statement, charged to its budgets when dropped.
+#[derive(Debug)]
+pub(crate) struct Charge {
+    budget: Arc<Budget>,
+    elapsed: Duration,
+}
+
+impl Charge {
+    fn add(&mut self, elapsed: Duration) {
+        self.elapsed += elapsed;
+    }
+}
+
+impl Drop for Charge {
+    fn drop(&mut self) {
+        let elapsed = self.elapsed.as_micros() as u64;
+        for budget in self.budget.chain() {
+            budget.elapsed_us.fetch_add(elapsed, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Charges a statement starting now to the current budget, if any,
+/// recording what was left of it on the current span.
+///
+/// Emits a `sqlx.query_budget.exhausted` event for each budget exhausted for
+/// the first time, and fails when one of them is enforced.
+pub(crate) fn start() -> Result<Option<Charge>, sqlx::Error> {
+    let Some(budget) = current() else {
+        return Ok(None);
+    };
+    let mut remaining_statements = None::<u64>;
+    let mut remaining = None::<Duration>;
+    let mut enforced = false;
+    for budget in budget.chain() {
+        let (statements, duration) = budget.charge();
+        remaining_statements = min(remaining_statements, statements);
+        remaining = min(remaining, duration);
+        if statements == Some(0) || duration == Some(Duration::ZERO) {
+            enforced |= budget.limits.enforce;
+            if !budget.warned.swap(true, Ordering::Relaxed) {
+                tracing::event!(
+                    name: crate::names::QUERY_BUDGET_EXHAUSTED,
+                    tracing::Level::WARN,
+                    {
+                        "db.query.budget.statements" = budget.statements.load(Ordering::Relaxed),
+                        "db.query.budget.elapsed_ms" = budget.elapsed().as_millis() as u64,
+                    },
+                    "query budget exhausted"
+                );
+            }
+        }
+    }
+    let span = tracing::Span::current();
+    if let Some(statements) = remaining_statements {
+        span.record("db.query.budget.remaining_statements", statements);
+    }
+    if let Some(remaining) = remaining {
+        span.record("db.query.budget.remaining_ms", remaining.as_millis() as u64);
+    }
+    if enforced {
+        return Err(QueryBudgetExceeded::error());
+    }
+    Ok(Some(Charge {
+        budget,
+        elapsed: Duration::ZERO,
+    }))
+}
+
+/// Returns the lowest of two optional limits.
+fn min<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Runs `fut` once charged by `charge`, charging the time it took.
+pub(crate) async fn charged<F, T>(
+    charge: Result<Option<Charge>, sqlx::Error>,
+    fut: F,
+) -> Result<T, sqlx::Error>
+where
+    F: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut charge = charge?;
+    let start = Instant::now();
+    let res = fut.await;
+    if let Some(charge) = &mut charge {
+        charge.add(start.elapsed());
+    }
+    res
+}
+
+/// Stream failing without being polled when its budget is exhausted, and
+/// charging the time spent polling it otherwise.
+pub(crate) struct Charged<S> {
+    inner: Option<S>,
+    charge: Result<Option<Charge>, Option<sqlx::Error>>,
+}
+
+impl<S> Charged<S> {
+    pub(crate) fn new(charge: Result<Option<Charge>, sqlx::Error>, stream: S) -> Self {
+        Self {
+            inner: Some(stream),
+            charge: charge.map_err(Some),
+        }
+    }
+}
+
+impl<S, T> Stream for Charged<S>
+where
+    S: Stream<Item = Result<T, sqlx::Error>> + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let charge = match &mut this.charge {
+            Ok(charge) => charge,
+            Err(err) => {
+                // Dropping the stream cancels the operation before it starts.
+                this.inner = None;
+                return Poll::Ready(err.take().map(Err));
+            }
+        };
+        let Some(inner) = this.inner.as_mut() else {
+            return Poll::Ready(None);
+        };
+        let start = Instant::now();
+        let item = Pin::new(inner).poll_next(cx);
+        if let Some(charge) = charge {
+            charge.add(start.elapsed());
+        }
+        item
+    }
+}
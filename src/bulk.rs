@@ -0,0 +1,175 @@
+use sqlx::query_builder::Separated;
+use tracing::Instrument;
+
+impl<DB> crate::Pool<DB>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+    for<'a> &'a mut DB::Connection: sqlx::Executor<'a, Database = DB>,
+{
+    /// Inserts `rows` into `table` using multi-row `INSERT ... VALUES` statements.
+    ///
+    /// Rows are split into chunks so that each statement stays within the
+    /// database's bind parameter limit, and all chunks run on a single pooled
+    /// connection. See [`Transaction::insert_many`](crate::Transaction::insert_many)
+    /// to insert atomically.
+    ///
+    /// The operation is wrapped in a `sqlx.insert_many` span recording the
+    /// total number of rows (`db.bulk.rows`) and chunks (`db.bulk.chunks`),
+    /// with one `sqlx.execute` child span per chunk.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let users = vec![("Alice", 30), ("Bob", 42)];
+    /// pool.insert_many("users", &["name", "age"], users, |mut row, (name, age)| {
+    ///     row.push_bind(name).push_bind(age);
+    /// })
+    /// .await?;
+    /// ```
+    pub async fn insert_many<T, F>(
+        &self,
+        table: &str,
+        columns: &[&str],
+        rows: impl IntoIterator<Item = T>,
+        push: F,
+    ) -> Result<DB::QueryResult, sqlx::Error>
+    where
+        F: for<'b, 'args> FnMut(Separated<'b, 'args, DB, &'static str>, T),
+    {
+        let mut conn = self.acquire().await?;
+        conn.insert_many(table, columns, rows, push).await
+    }
+}
+
+impl<DB> crate::PoolConnection<DB>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+    for<'a> &'a mut DB::Connection: sqlx::Executor<'a, Database = DB>,
+{
+    /// Inserts `rows` into `table` using multi-row `INSERT ... VALUES` statements.
+    ///
+    /// See [`Pool::insert_many`](crate::Pool::insert_many).
+    pub async fn insert_many<T, F>(
+        &mut self,
+        table: &str,
+        columns: &[&str],
+        rows: impl IntoIterator<Item = T>,
+        push: F,
+    ) -> Result<DB::QueryResult, sqlx::Error>
+    where
+        F: for<'b, 'args> FnMut(Separated<'b, 'args, DB, &'static str>, T),
+    {
+        let attributes = self.attributes.clone();
+        let lease = self.lease.clone();
+        insert_many::<DB, _, _>(self.inner.as_mut(), &attributes, table, columns, rows, push)
+            .instrument(lease)
+            .await
+    }
+}
+
+impl<'c, DB> crate::Transaction<'c, DB>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+    for<'a> &'a mut DB::Connection: sqlx::Executor<'a, Database = DB>,
+{
+    /// Inserts `rows` into `table` using multi-row `INSERT ... VALUES` statements
+    /// within this transaction.
+    ///
+    /// See [`Pool::insert_many`](crate::Pool::insert_many).
+    pub async fn insert_many<T, F>(
+        &mut self,
+        table: &str,
+        columns: &[&str],
+        rows: impl IntoIterator<Item = T>,
+        push: F,
+    ) -> Result<DB::QueryResult, sqlx::Error>
+    where
+        F: for<'b, 'args> FnMut(Separated<'b, 'args, DB, &'static str>, T),
+    {
+        let attributes = self.attributes.clone();
+        let idle = crate::idle::IdleTracker::resume(Some(&self.idle));
+        let res =
+            insert_many::<DB, _, _>(&mut *self.inner, &attributes, table, columns, rows, push)
+                .await;
+        if let Some(idle) = idle {
+            idle.pause();
+        }
+        res
+    }
+}
+
+/// Wraps the chunked inserts in a `sqlx.insert_many` span.
+async fn insert_many<DB, T, F>(
+    conn: &mut DB::Connection,
+    attrs: &crate::Attributes,
+    table: &str,
+    columns: &[&str],
+    rows: impl IntoIterator<Item = T>,
+    push: F,
+) -> Result<DB::QueryResult, sqlx::Error>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+    for<'a> &'a mut DB::Connection: sqlx::Executor<'a, Database = DB>,
+    F: for<'b, 'args> FnMut(Separated<'b, 'args, DB, &'static str>, T),
+{
+    let record_details = attrs.config().error_details();
+    let span = crate::instrument_op!(
+        crate::Operation::InsertMany,
+        attrs,
+        "db.sql.table" = table,
+        "db.bulk.rows" = ::tracing::field::Empty,
+        "db.bulk.chunks" = ::tracing::field::Empty,
+    );
+    async {
+        insert_chunks::<DB, _, _>(conn, attrs, table, columns, rows, push)
+            .await
+            .inspect_err(|e| crate::span::record_error(e, &record_details))
+    }
+    .instrument(span)
+    .await
+}
+
+/// Runs one multi-row `INSERT` per chunk of rows on a single connection.
+async fn insert_chunks<DB, T, F>(
+    conn: &mut DB::Connection,
+    attrs: &crate::Attributes,
+    table: &str,
+    columns: &[&str],
+    rows: impl IntoIterator<Item = T>,
+    mut push: F,
+) -> Result<DB::QueryResult, sqlx::Error>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+    for<'a> &'a mut DB::Connection: sqlx::Executor<'a, Database = DB>,
+    F: for<'b, 'args> FnMut(Separated<'b, 'args, DB, &'static str>, T),
+{
+    if columns.is_empty() {
+        return Err(sqlx::Error::InvalidArgument(
+            "insert_many requires at least one column".into(),
+        ));
+    }
+    let chunk_size = (DB::MAX_BIND_PARAMETERS / columns.len()).max(1);
+    let mut rows = rows.into_iter().peekable();
+    let mut result = DB::QueryResult::default();
+    let (mut total, mut chunks) = (0usize, 0usize);
+    while rows.peek().is_some() {
+        let mut builder =
+            sqlx::QueryBuilder::<DB>::new(format!("INSERT INTO {table} ({}) ", columns.join(", ")));
+        let mut count = 0usize;
+        builder.push_values(rows.by_ref().take(chunk_size), |row, value| {
+            count += 1;
+            push(row, value);
+        });
+        let sql = builder.sql().to_owned();
+        let fut = DB::execute_builder(&mut *conn, &mut builder);
+        result.extend(Some(
+            crate::exec_fut_result!(sql.as_str(), attrs, fut).await?,
+        ));
+        total += count;
+        chunks += 1;
+    }
+    let span = tracing::Span::current();
+    span.record("db.bulk.rows", total);
+    span.record("db.bulk.chunks", chunks);
+    Ok(result)
+}
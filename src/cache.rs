@@ -0,0 +1,198 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::Instrument;
+
+/// Read-through cache of query results in front of a [`Pool`](crate::Pool).
+///
+/// Results are keyed by the fingerprint of their SQL, the hash of their bind
+/// parameters and their type, and kept for a fixed time to live. Every
+/// lookup is traced with a `sqlx.cache.fetch` span recording its key
+/// (`db.cache.key`), whether it was a hit (`db.cache.hit`) and, on hits, the
+/// latency of the fetch that populated the entry (`db.cache.saved_ms`).
+/// Misses run the fetch within that span, so its statement spans stay
+/// children of the lookup.
+///
+/// Errors are never cached. Concurrent misses for the same key each run the
+/// fetch, the last one populating the entry.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let cached = sqlx_tracing::CachedPool::new(traced_pool, Duration::from_secs(30));
+/// let sql = "SELECT name FROM users WHERE id = $1";
+/// let name: String = cached
+///     .fetch(sql, id, |pool| sqlx::query_scalar(sql).bind(id).fetch_one(pool))
+///     .await?;
+/// ```
+pub struct CachedPool<DB>
+where
+    DB: sqlx::Database,
+{
+    pool: crate::Pool<DB>,
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<Key, Entry>>>,
+}
+
+impl<DB: sqlx::Database> Clone for CachedPool<DB> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            ttl: self.ttl,
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+impl<DB: sqlx::Database> std::fmt::Debug for CachedPool<DB> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachedPool")
+            .field("pool", &self.pool)
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Cache key: the fingerprint of the SQL, the hash of the parameters and the
+/// type of the result.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Key {
+    fingerprint: String,
+    parameters: u64,
+    result: TypeId,
+}
+
+impl Key {
+    fn new<T: 'static>(sql: &str, parameters: impl Hash) -> Self {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        parameters.hash(&mut hasher);
+        Self {
+            fingerprint: crate::span::fingerprint(sql),
+            parameters: hasher.finish(),
+            result: TypeId::of::<T>(),
+        }
+    }
+
+    /// Returns the value of the `db.cache.key` field.
+    fn field(&self) -> String {
+        format!("{}:{:016x}", self.fingerprint, self.parameters)
+    }
+}
+
+/// Cached result, along with how long it took to fetch.
+struct Entry {
+    value: Arc<dyn Any + Send + Sync>,
+    expires: Instant,
+    latency: Duration,
+}
+
+impl<DB> CachedPool<DB>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+{
+    /// Wraps `pool`, keeping results for `ttl`.
+    pub fn new(pool: crate::Pool<DB>, ttl: Duration) -> Self {
+        Self {
+            pool,
+            ttl,
+            entries: Default::default(),
+        }
+    }
+
+    /// Returns the wrapped pool, to run uncached statements.
+    pub fn pool(&self) -> &crate::Pool<DB> {
+        &self.pool
+    }
+
+    /// Returns the result of `sql` with `parameters` if cached, or runs
+    /// `fetch` with the wrapped pool and caches its result otherwise.
+    ///
+    /// `parameters` must hash the values bound by `fetch`, e.g. as a tuple,
+    /// so that different parameters are cached separately.
+    pub async fn fetch<'p, T, P, F, Fut>(
+        &'p self,
+        sql: &str,
+        parameters: P,
+        fetch: F,
+    ) -> Result<T, sqlx::Error>
+    where
+        T: Clone + Send + Sync + 'static,
+        P: Hash,
+        F: FnOnce(&'p crate::Pool<DB>) -> Fut,
+        Fut: Future<Output = Result<T, sqlx::Error>>,
+    {
+        let attrs = &self.pool.attributes;
+        let key = Key::new::<T>(sql, parameters);
+        let span = crate::instrument_op!(
+            crate::Operation::CacheFetch,
+            attrs,
+            "db.cache.key" = key.field(),
+            "db.cache.hit" = ::tracing::field::Empty,
+            "db.cache.saved_ms" = ::tracing::field::Empty,
+        );
+        if let Some((value, latency)) = self.get::<T>(&key) {
+            span.record("db.cache.hit", true);
+            span.record("db.cache.saved_ms", latency.as_millis() as u64);
+            return Ok(value);
+        }
+        span.record("db.cache.hit", false);
+        async {
+            let start = Instant::now();
+            let res = fetch(&self.pool).await;
+            match &res {
+                Ok(value) => self.insert(key, value.clone(), start.elapsed()),
+                Err(err) => crate::span::record_error(err, &attrs.config().error_details()),
+            }
+            res
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Removes the cached result of `sql` with `parameters`, for any result
+    /// type.
+    pub fn invalidate<P: Hash>(&self, sql: &str, parameters: P) {
+        let key = Key::new::<()>(sql, parameters);
+        self.lock().retain(|existing, _| {
+            existing.fingerprint != key.fingerprint || existing.parameters != key.parameters
+        });
+    }
+
+    /// Removes every cached result.
+    pub fn invalidate_all(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Key, Entry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the unexpired result cached for `key`, and how long it took
+    /// to fetch.
+    fn get<T: Clone + 'static>(&self, key: &Key) -> Option<(T, Duration)> {
+        let entries = self.lock();
+        let entry = entries
+            .get(key)
+            .filter(|entry| entry.expires > Instant::now())?;
+        let value = entry.value.downcast_ref::<T>()?;
+        Some((value.clone(), entry.latency))
+    }
+
+    /// Caches `value` for `key`, dropping expired entries.
+    fn insert<T: Send + Sync + 'static>(&self, key: Key, value: T, latency: Duration) {
+        let now = Instant::now();
+        let mut entries = self.lock();
+        entries.retain(|_, entry| entry.expires > now);
+        entries.insert(
+            key,
+            Entry {
+                value: Arc::new(value),
+                expires: now + self.ttl,
+                latency,
+            },
+        );
+    }
+}
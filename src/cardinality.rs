@@ -0,0 +1,71 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+/// Value recorded in place of the values of a field beyond its limit.
+pub(crate) const OTHER: &str = "(other)";
+
+/// Limits the distinct values recorded for label-like span fields, set with
+/// [`PoolBuilder::with_cardinality_limit`](crate::PoolBuilder::with_cardinality_limit).
+#[derive(Debug)]
+pub(crate) struct CardinalityGuard {
+    max_values: usize,
+    max_length: usize,
+    fields: Mutex<HashMap<&'static str, Field>>,
+}
+
+/// Values seen for a field.
+#[derive(Debug, Default)]
+struct Field {
+    values: HashSet<String>,
+    warned: bool,
+}
+
+impl CardinalityGuard {
+    pub(crate) fn new(max_values: usize, max_length: usize) -> Self {
+        Self {
+            max_values,
+            max_length,
+            fields: Mutex::default(),
+        }
+    }
+
+    /// Returns the value to record for `field`: `value` itself, its hash when
+    /// longer than the maximum length, or [`OTHER`] once the field reached
+    /// its maximum number of distinct values.
+    ///
+    /// Emits a `sqlx.cardinality.limited` event the first time a value of
+    /// `field` is replaced.
+    pub(crate) fn value<'a>(&self, field: &'static str, value: &'a str) -> Cow<'a, str> {
+        let (value, reason) = if value.len() > self.max_length {
+            (Cow::Owned(crate::span::fingerprint(value)), "length")
+        } else {
+            (Cow::Borrowed(value), "values")
+        };
+        let mut fields = self.fields.lock().unwrap_or_else(|e| e.into_inner());
+        let seen = fields.entry(field).or_default();
+        let value = if seen.values.contains(value.as_ref()) {
+            value
+        } else if seen.values.len() < self.max_values {
+            seen.values.insert(value.clone().into_owned());
+            value
+        } else {
+            Cow::Borrowed(OTHER)
+        };
+        let replaced = reason == "length" || value == OTHER;
+        if replaced && !std::mem::replace(&mut seen.warned, true) {
+            tracing::event!(
+                name: crate::names::CARDINALITY_LIMITED,
+                tracing::Level::WARN,
+                {
+                    "db.cardinality.field" = field,
+                    "db.cardinality.reason" = reason,
+                    "db.cardinality.max_values" = self.max_values,
+                    "db.cardinality.max_length" = self.max_length,
+                },
+                "high-cardinality span field limited"
+            );
+        }
+        value
+    }
+}
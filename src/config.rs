@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+/// Recording options and runtime state of a pool, set on the
+/// [`PoolBuilder`](crate::PoolBuilder) and shared by the
+/// [`Attributes`](crate::Attributes) of the built pool.
+#[derive(Clone, Debug)]
+pub(crate) struct Config {
+    pub(crate) record_query_text: bool,
+    pub(crate) record_comment_tags: bool,
+    pub(crate) query_text_operations: Option<Vec<String>>,
+    pub(crate) record_schema: bool,
+    pub(crate) record_result_metadata: bool,
+    pub(crate) record_payload_size: bool,
+    pub(crate) record_error_details: bool,
+    pub(crate) record_error_message_hash: bool,
+    pub(crate) error_throttle: Option<Arc<crate::throttle::ErrorThrottle>>,
+    pub(crate) error_status: Option<crate::status::ErrorStatusMapper>,
+    pub(crate) transaction_idle_threshold: Option<std::time::Duration>,
+    pub(crate) statement_timeout: Option<crate::StatementTimeout>,
+    pub(crate) acquire_slow_threshold: Option<std::time::Duration>,
+    pub(crate) stream_progress_interval: Option<std::num::NonZeroU64>,
+    pub(crate) connection_lease_spans: bool,
+    pub(crate) query_rewriter: Option<crate::rewrite::QueryRewriter>,
+    pub(crate) empty_statements: crate::EmptyStatements,
+    pub(crate) span_exporter: Option<crate::export::SpanExporter>,
+    pub(crate) statement_groups: Option<Arc<crate::repeat::StatementGroups>>,
+    pub(crate) ping_query: Option<String>,
+    pub(crate) ping_tracing: crate::PingTracing,
+    pub(crate) explain_sampling: Option<std::num::NonZeroU64>,
+    #[cfg(feature = "postgres")]
+    pub(crate) explain_analyze: bool,
+    pub(crate) explain_counter: Arc<std::sync::atomic::AtomicU64>,
+    #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+    pub(crate) metrics: Option<Arc<crate::metrics::PoolMetrics>>,
+    #[cfg(feature = "tokio-metrics")]
+    pub(crate) task_monitor: tokio_metrics::TaskMonitor,
+    #[cfg(feature = "opentelemetry")]
+    pub(crate) record_baggage: bool,
+    #[cfg(feature = "opentelemetry")]
+    pub(crate) baggage_keys: Option<Vec<String>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            record_query_text: true,
+            record_comment_tags: true,
+            query_text_operations: None,
+            record_schema: false,
+            record_result_metadata: false,
+            record_payload_size: false,
+            record_error_details: true,
+            record_error_message_hash: false,
+            error_throttle: None,
+            error_status: None,
+            transaction_idle_threshold: None,
+            statement_timeout: None,
+            acquire_slow_threshold: None,
+            stream_progress_interval: None,
+            connection_lease_spans: false,
+            query_rewriter: None,
+            empty_statements: crate::EmptyStatements::Flag,
+            span_exporter: None,
+            statement_groups: None,
+            ping_query: None,
+            ping_tracing: crate::PingTracing::Span,
+            explain_sampling: None,
+            #[cfg(feature = "postgres")]
+            explain_analyze: false,
+            explain_counter: Default::default(),
+            #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+            metrics: None,
+            #[cfg(feature = "tokio-metrics")]
+            task_monitor: tokio_metrics::TaskMonitor::new(),
+            #[cfg(feature = "opentelemetry")]
+            record_baggage: false,
+            #[cfg(feature = "opentelemetry")]
+            baggage_keys: None,
+        }
+    }
+}
+
+impl Config {
+    /// Returns whether error details are recorded, and how often.
+    pub(crate) fn error_details(&self) -> crate::throttle::ErrorDetails {
+        crate::throttle::ErrorDetails::new(
+            self.record_error_details,
+            self.record_error_message_hash,
+            self.error_throttle.clone(),
+            self.error_status.clone(),
+            #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+            self.metrics.clone(),
+        )
+    }
+
+    /// Returns the value of the `db.query.text` field for `statement`, given
+    /// its parsed `operation`.
+    pub(crate) fn query_text<'s>(
+        &self,
+        statement: &'s str,
+        operation: Option<&str>,
+    ) -> Option<&'s str> {
+        let allowed = match (&self.query_text_operations, operation) {
+            (None, _) => true,
+            (Some(allowed), Some(operation)) => allowed
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(operation)),
+            (Some(_), None) => false,
+        };
+        (self.record_query_text && allowed).then_some(statement)
+    }
+}
@@ -1,6 +1,95 @@
-use futures::{StreamExt, TryStreamExt};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use tracing::Instrument;
 
+/// Returns a new id for a traced connection handle, recorded as
+/// `db.client.connection.id`.
+pub(crate) fn next_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Returns a new `sqlx.connection.lease` span when enabled on the attributes,
+/// or a disabled span otherwise.
+pub(crate) fn lease_span<DB: crate::prelude::Database>(attrs: &crate::Attributes) -> tracing::Span {
+    if !attrs.config().connection_lease_spans {
+        return tracing::Span::none();
+    }
+    crate::instrument_op!(
+        crate::Operation::Lease,
+        attrs,
+        "db.client.connection.id" = ::tracing::field::Empty,
+    )
+}
+
+/// How [`PoolConnection::ping`](crate::PoolConnection::ping) is traced, set
+/// with [`PoolBuilder::with_ping_tracing`](crate::PoolBuilder::with_ping_tracing).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PingTracing {
+    /// Create a `sqlx.connection.ping` span.
+    #[default]
+    Span,
+    /// Emit a `sqlx.connection.ping` event carrying the ping duration as
+    /// `db.client.connection.ping_ms`, at `DEBUG` level or `WARN` on failure.
+    Event,
+    /// Emit neither spans nor events.
+    Off,
+}
+
+/// Pings `conn` by running `query`, or with the driver's native ping when
+/// unset.
+fn ping<'a, DB>(
+    conn: &'a mut DB::Connection,
+    query: Option<&'a str>,
+) -> futures_core::future::BoxFuture<'a, Result<(), sqlx::Error>>
+where
+    DB: sqlx::Database,
+    for<'e> &'e mut DB::Connection: sqlx::Executor<'e, Database = DB>,
+{
+    use sqlx::{Connection, Executor};
+
+    match query {
+        Some(query) => {
+            let fut = conn.execute(sqlx::raw_sql(query));
+            Box::pin(async move { fut.await.map(|_| ()) })
+        }
+        None => conn.ping(),
+    }
+}
+
+impl<DB> crate::PoolConnection<DB>
+where
+    DB: sqlx::Database,
+{
+    /// Wraps a pooled SQLx connection, recording its id on the current span
+    /// and on its lease span.
+    pub(crate) fn new(
+        inner: sqlx::pool::PoolConnection<DB>,
+        attributes: Arc<crate::Attributes>,
+        lease: tracing::Span,
+    ) -> Self {
+        let id = next_id();
+        tracing::Span::current().record("db.client.connection.id", id);
+        lease.record("db.client.connection.id", id);
+        Self {
+            _run: crate::repeat::RunGuard::new(&attributes, id),
+            inner,
+            attributes,
+            id,
+            lease,
+        }
+    }
+
+    /// Returns the `sqlx.connection.lease` span of this connection.
+    ///
+    /// The span is disabled unless lease spans were enabled with
+    /// [`PoolBuilder::with_connection_lease_spans`](crate::PoolBuilder::with_connection_lease_spans).
+    pub fn span(&self) -> &tracing::Span {
+        &self.lease
+    }
+}
+
 impl<DB> AsMut<<DB as sqlx::Database>::Connection> for crate::PoolConnection<DB>
 where
     DB: crate::prelude::Database + sqlx::Database,
@@ -10,6 +99,58 @@ where
     }
 }
 
+impl<DB> crate::PoolConnection<DB>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+{
+    /// Wraps a raw SQLx pooled connection with the attributes recorded on
+    /// its spans, e.g. to re-wrap a connection taken with
+    /// [`into_parts`](crate::PoolConnection::into_parts).
+    ///
+    /// The connection gets a new id and lease span.
+    pub fn from_parts(
+        inner: sqlx::pool::PoolConnection<DB>,
+        attributes: crate::Attributes,
+    ) -> Self {
+        let attributes = Arc::new(attributes);
+        let lease = lease_span::<DB>(&attributes);
+        let id = next_id();
+        lease.record("db.client.connection.id", id);
+        Self {
+            _run: crate::repeat::RunGuard::new(&attributes, id),
+            inner,
+            attributes,
+            id,
+            lease,
+        }
+    }
+
+    /// Unwraps the raw SQLx pooled connection, to temporarily use SQLx
+    /// directly for operations this crate does not support.
+    ///
+    /// This ends the lease span of the connection. Use
+    /// [`into_parts`](crate::PoolConnection::into_parts) to keep the
+    /// attributes for re-wrapping the connection later.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let (mut raw, attributes) = conn.into_parts();
+    /// sqlx::Connection::shrink_buffers(&mut *raw);
+    /// let conn = sqlx_tracing::PoolConnection::from_parts(raw, attributes);
+    /// ```
+    pub fn into_inner(self) -> sqlx::pool::PoolConnection<DB> {
+        self.inner
+    }
+
+    /// Unwraps the raw SQLx pooled connection along with the attributes
+    /// recorded on its spans, the inverse of
+    /// [`from_parts`](crate::PoolConnection::from_parts).
+    pub fn into_parts(self) -> (sqlx::pool::PoolConnection<DB>, crate::Attributes) {
+        (self.inner, Arc::unwrap_or_clone(self.attributes))
+    }
+}
+
 impl<DB> crate::PoolConnection<DB>
 where
     DB: crate::prelude::Database + sqlx::Database,
@@ -17,18 +158,92 @@ where
 {
     /// Pings the database to check if the connection is still valid.
     ///
-    /// The ping operation is instrumented with a `sqlx.connection.ping` tracing span.
+    /// The ping operation is instrumented with a `sqlx.connection.ping` tracing
+    /// span by default. The SQL used instead of the driver's native ping and
+    /// whether a span, an event or nothing is emitted are configured with
+    /// [`PoolBuilder::with_ping_query`](crate::PoolBuilder::with_ping_query)
+    /// and [`PoolBuilder::with_ping_tracing`](crate::PoolBuilder::with_ping_tracing).
     pub async fn ping(&mut self) -> Result<(), sqlx::Error> {
-        use sqlx::Connection;
         let attrs = &self.attributes;
-        let record_details = attrs.record_error_details;
-        let span = crate::instrument_op!("sqlx.connection.ping", attrs);
+        // Nothing is traced when instrumentation is compiled out.
+        let tracing = if cfg!(feature = "disabled") {
+            PingTracing::Off
+        } else {
+            attrs.config().ping_tracing
+        };
+        match tracing {
+            PingTracing::Span => {
+                let record_details = attrs.config().error_details();
+                let span = self
+                    .lease
+                    .in_scope(|| crate::instrument_op!(crate::Operation::Ping, attrs));
+                async {
+                    ping::<DB>(self.inner.as_mut(), attrs.config().ping_query.as_deref())
+                        .await
+                        .inspect_err(|e| {
+                            crate::span::record_error(e, &record_details);
+                            crate::span::record_broken(e, Some(self.id));
+                        })
+                }
+                .instrument(span)
+                .await
+            }
+            PingTracing::Event => {
+                let start = std::time::Instant::now();
+                let res =
+                    ping::<DB>(self.inner.as_mut(), attrs.config().ping_query.as_deref()).await;
+                let duration_ms = start.elapsed().as_millis() as u64;
+                match &res {
+                    Ok(()) => tracing::event!(
+                        name: crate::names::CONNECTION_PING,
+                        tracing::Level::DEBUG,
+                        {
+                            "db.client.connection.id" = self.id,
+                            "db.client.connection.ping_ms" = duration_ms,
+                        },
+                        "connection ping succeeded"
+                    ),
+                    Err(e) => {
+                        tracing::event!(
+                            name: crate::names::CONNECTION_PING,
+                            tracing::Level::WARN,
+                            {
+                                "db.client.connection.id" = self.id,
+                                "db.client.connection.ping_ms" = duration_ms,
+                                "error.message" = attrs.config().error_details().message(e),
+                            },
+                            "connection ping failed"
+                        );
+                        crate::span::record_broken(e, Some(self.id));
+                    }
+                }
+                res
+            }
+            PingTracing::Off => {
+                ping::<DB>(self.inner.as_mut(), attrs.config().ping_query.as_deref()).await
+            }
+        }
+    }
+
+    /// Closes this connection instead of returning it to the pool.
+    ///
+    /// The close operation is instrumented with a `sqlx.connection.close` tracing span.
+    /// The pool will open a new connection in its place if needed.
+    pub async fn close(self) -> Result<(), sqlx::Error> {
+        let attrs = &self.attributes;
+        let record_details = attrs.config().error_details();
+        let span = self.lease.in_scope(|| {
+            crate::instrument_op!(
+                crate::Operation::CloseConnection,
+                attrs,
+                "db.client.connection.close_reason" = "explicit",
+            )
+        });
         async {
             self.inner
-                .as_mut()
-                .ping()
+                .close()
                 .await
-                .inspect_err(|e| crate::span::record_error(e, record_details))
+                .inspect_err(|e| crate::span::record_error(e, &record_details))
         }
         .instrument(span)
         .await
@@ -40,24 +255,30 @@ where
     pub async fn begin(&mut self) -> Result<crate::Transaction<'_, DB>, sqlx::Error> {
         use sqlx::Connection;
         let attrs = &self.attributes;
-        let record_details = attrs.record_error_details;
-        let span = crate::instrument_op!("sqlx.transaction.begin", attrs);
-        async {
+        let record_details = attrs.config().error_details();
+        let span = self
+            .lease
+            .in_scope(|| crate::instrument_op!(crate::Operation::Begin, attrs));
+        let inner = async {
             self.inner
                 .as_mut()
                 .begin()
                 .await
-                .map(|inner| crate::Transaction {
-                    inner,
-                    attributes: self.attributes.clone(),
-                })
-                .inspect_err(|e| crate::span::record_error(e, record_details))
+                .inspect_err(|e| crate::span::record_error(e, &record_details))
         }
         .instrument(span)
-        .await
+        .await?;
+        self.lease
+            .in_scope(|| crate::Transaction::new(inner, self.attributes.clone(), self.id, None))
+            .apply_statement_timeout()
+            .await
     }
 }
 
+#[cfg(feature = "disabled")]
+crate::passthrough_executor!(impl<'c> for &'c mut crate::PoolConnection<DB>, |conn| &mut *conn.inner);
+
+#[cfg(not(feature = "disabled"))]
 impl<'c, DB> sqlx::Executor<'c> for &'c mut crate::PoolConnection<DB>
 where
     DB: crate::prelude::Database + sqlx::Database,
@@ -69,23 +290,27 @@ where
     fn describe<'e, 'q: 'e>(
         self,
         sql: &'q str,
-    ) -> futures::future::BoxFuture<'e, Result<sqlx::Describe<Self::Database>, sqlx::Error>>
+    ) -> futures_core::future::BoxFuture<'e, Result<sqlx::Describe<Self::Database>, sqlx::Error>>
     where
         'c: 'e,
     {
         let attrs = &self.attributes;
-        crate::exec_fut!(
-            "sqlx.describe",
-            sql,
-            attrs,
-            self.inner.as_mut().describe(sql)
-        )
+        self.lease.in_scope(|| {
+            crate::exec_fut!(
+                crate::Operation::Describe,
+                sql,
+                attrs,
+                self.inner.as_mut().describe(sql),
+                None,
+                Some(self.id)
+            )
+        })
     }
 
     fn execute<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<<Self::Database as sqlx::Database>::QueryResult, sqlx::Error>,
     >
@@ -93,15 +318,24 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_fut!("sqlx.execute", sql, attrs, self.inner.execute(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        self.lease.in_scope(|| {
+            crate::exec_fut_result!(
+                sql,
+                attrs,
+                query.execute(&mut *self.inner),
+                None,
+                Some(self.id)
+            )
+        })
     }
 
     fn execute_many<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::stream::BoxStream<
+    ) -> futures_core::stream::BoxStream<
         'e,
         Result<<Self::Database as sqlx::Database>::QueryResult, sqlx::Error>,
     >
@@ -109,33 +343,53 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_stream!(
-            "sqlx.execute_many",
-            sql,
-            attrs,
-            self.inner.execute_many(query)
-        )
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        self.lease.in_scope(|| {
+            crate::exec_stream!(
+                crate::Operation::ExecuteMany,
+                sql,
+                attrs,
+                query.execute_many(&mut *self.inner),
+                |_| false,
+                None,
+                Some(self.id)
+            )
+        })
     }
 
     fn fetch<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::stream::BoxStream<'e, Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>>
+    ) -> futures_core::stream::BoxStream<
+        'e,
+        Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>,
+    >
     where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_stream!("sqlx.fetch", sql, attrs, self.inner.fetch(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        self.lease.in_scope(|| {
+            crate::exec_stream!(
+                crate::Operation::Fetch,
+                sql,
+                attrs,
+                crate::stream::measure_rows::<DB, _>(query.fetch(&mut *self.inner), attrs),
+                |_| true,
+                None,
+                Some(self.id)
+            )
+        })
     }
 
     fn fetch_all<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<Vec<<Self::Database as sqlx::Database>::Row>, sqlx::Error>,
     >
@@ -143,15 +397,24 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_fut_rows!(sql, attrs, self.inner.fetch_all(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        self.lease.in_scope(|| {
+            crate::exec_fut_rows!(
+                sql,
+                attrs,
+                query.fetch_all(&mut *self.inner),
+                None,
+                Some(self.id)
+            )
+        })
     }
 
     fn fetch_many<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::stream::BoxStream<
+    ) -> futures_core::stream::BoxStream<
         'e,
         Result<
             sqlx::Either<
@@ -165,28 +428,51 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_stream!("sqlx.fetch_many", sql, attrs, self.inner.fetch_many(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        self.lease.in_scope(|| {
+            crate::exec_stream!(
+                crate::Operation::FetchMany,
+                sql,
+                attrs,
+                crate::stream::measure_results::<DB, _>(query.fetch_many(&mut *self.inner), attrs),
+                sqlx::Either::is_right,
+                None,
+                Some(self.id)
+            )
+        })
     }
 
     fn fetch_one<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<'e, Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>>
+    ) -> futures_core::future::BoxFuture<
+        'e,
+        Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>,
+    >
     where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_fut_one!(sql, attrs, self.inner.fetch_one(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        self.lease.in_scope(|| {
+            crate::exec_fut_one!(
+                sql,
+                attrs,
+                query.fetch_one(&mut *self.inner),
+                None,
+                Some(self.id)
+            )
+        })
     }
 
     fn fetch_optional<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<Option<<Self::Database as sqlx::Database>::Row>, sqlx::Error>,
     >
@@ -194,15 +480,24 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_fut_opt!(sql, attrs, self.inner.fetch_optional(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        self.lease.in_scope(|| {
+            crate::exec_fut_opt!(
+                sql,
+                attrs,
+                query.fetch_optional(&mut *self.inner),
+                None,
+                Some(self.id)
+            )
+        })
     }
 
     fn prepare<'e, 'q: 'e>(
         self,
         query: &'q str,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<<Self::Database as sqlx::Database>::Statement<'q>, sqlx::Error>,
     >
@@ -210,14 +505,23 @@ where
         'c: 'e,
     {
         let attrs = &self.attributes;
-        crate::exec_fut!("sqlx.prepare", query, attrs, self.inner.prepare(query))
+        self.lease.in_scope(|| {
+            crate::exec_fut!(
+                crate::Operation::Prepare,
+                query,
+                attrs,
+                crate::span::prepared(query, self.inner.prepare(query)),
+                None,
+                Some(self.id)
+            )
+        })
     }
 
     fn prepare_with<'e, 'q: 'e>(
         self,
         sql: &'q str,
         parameters: &'e [<Self::Database as sqlx::Database>::TypeInfo],
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<<Self::Database as sqlx::Database>::Statement<'q>, sqlx::Error>,
     >
@@ -225,16 +529,24 @@ where
         'c: 'e,
     {
         let attrs = &self.attributes;
-        crate::exec_fut!(
-            "sqlx.prepare_with",
-            sql,
-            attrs,
-            self.inner.prepare_with(sql, parameters)
-        )
+        self.lease.in_scope(|| {
+            crate::exec_fut!(
+                crate::Operation::PrepareWith,
+                sql,
+                attrs,
+                crate::span::prepared(sql, self.inner.prepare_with(sql, parameters)),
+                None,
+                Some(self.id)
+            )
+        })
     }
 }
 
-impl<'c, DB> sqlx::Executor<'c> for &'c mut crate::Connection<'c, DB>
+#[cfg(feature = "disabled")]
+crate::passthrough_executor!(impl<'c, 'b> for &'c mut crate::Connection<'b, DB>, |conn| &mut *conn.inner);
+
+#[cfg(not(feature = "disabled"))]
+impl<'c, 'b, DB> sqlx::Executor<'c> for &'c mut crate::Connection<'b, DB>
 where
     DB: crate::prelude::Database + sqlx::Database,
     for<'a> &'a mut DB::Connection: sqlx::Executor<'a, Database = DB>,
@@ -245,18 +557,25 @@ where
     fn describe<'e, 'q: 'e>(
         self,
         sql: &'q str,
-    ) -> futures::future::BoxFuture<'e, Result<sqlx::Describe<Self::Database>, sqlx::Error>>
+    ) -> futures_core::future::BoxFuture<'e, Result<sqlx::Describe<Self::Database>, sqlx::Error>>
     where
         'c: 'e,
     {
         let attrs = &self.attributes;
-        crate::exec_fut!("sqlx.describe", sql, attrs, self.inner.describe(sql))
+        crate::exec_fut!(
+            crate::Operation::Describe,
+            sql,
+            attrs,
+            self.inner.describe(sql),
+            self.idle.as_ref(),
+            Some(self.connection_id)
+        )
     }
 
     fn execute<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<<Self::Database as sqlx::Database>::QueryResult, sqlx::Error>,
     >
@@ -264,15 +583,22 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_fut!("sqlx.execute", sql, attrs, self.inner.execute(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        crate::exec_fut_result!(
+            sql,
+            attrs,
+            query.execute(&mut *self.inner),
+            self.idle.as_ref(),
+            Some(self.connection_id)
+        )
     }
 
     fn execute_many<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::stream::BoxStream<
+    ) -> futures_core::stream::BoxStream<
         'e,
         Result<<Self::Database as sqlx::Database>::QueryResult, sqlx::Error>,
     >
@@ -280,33 +606,49 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
         crate::exec_stream!(
-            "sqlx.execute_many",
+            crate::Operation::ExecuteMany,
             sql,
             attrs,
-            self.inner.execute_many(query)
+            query.execute_many(&mut *self.inner),
+            |_| false,
+            self.idle.as_ref(),
+            Some(self.connection_id)
         )
     }
 
     fn fetch<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::stream::BoxStream<'e, Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>>
+    ) -> futures_core::stream::BoxStream<
+        'e,
+        Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>,
+    >
     where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_stream!("sqlx.fetch", sql, attrs, self.inner.fetch(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        crate::exec_stream!(
+            crate::Operation::Fetch,
+            sql,
+            attrs,
+            crate::stream::measure_rows::<DB, _>(query.fetch(&mut *self.inner), attrs),
+            |_| true,
+            self.idle.as_ref(),
+            Some(self.connection_id)
+        )
     }
 
     fn fetch_all<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<Vec<<Self::Database as sqlx::Database>::Row>, sqlx::Error>,
     >
@@ -314,15 +656,22 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_fut_rows!(sql, attrs, self.inner.fetch_all(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        crate::exec_fut_rows!(
+            sql,
+            attrs,
+            query.fetch_all(&mut *self.inner),
+            self.idle.as_ref(),
+            Some(self.connection_id)
+        )
     }
 
     fn fetch_many<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::stream::BoxStream<
+    ) -> futures_core::stream::BoxStream<
         'e,
         Result<
             sqlx::Either<
@@ -336,28 +685,47 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_stream!("sqlx.fetch_many", sql, attrs, self.inner.fetch_many(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        crate::exec_stream!(
+            crate::Operation::FetchMany,
+            sql,
+            attrs,
+            crate::stream::measure_results::<DB, _>(query.fetch_many(&mut *self.inner), attrs),
+            sqlx::Either::is_right,
+            self.idle.as_ref(),
+            Some(self.connection_id)
+        )
     }
 
     fn fetch_one<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<'e, Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>>
+    ) -> futures_core::future::BoxFuture<
+        'e,
+        Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>,
+    >
     where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_fut_one!(sql, attrs, self.inner.fetch_one(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        crate::exec_fut_one!(
+            sql,
+            attrs,
+            query.fetch_one(&mut *self.inner),
+            self.idle.as_ref(),
+            Some(self.connection_id)
+        )
     }
 
     fn fetch_optional<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<Option<<Self::Database as sqlx::Database>::Row>, sqlx::Error>,
     >
@@ -365,15 +733,22 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_fut_opt!(sql, attrs, self.inner.fetch_optional(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        crate::exec_fut_opt!(
+            sql,
+            attrs,
+            query.fetch_optional(&mut *self.inner),
+            self.idle.as_ref(),
+            Some(self.connection_id)
+        )
     }
 
     fn prepare<'e, 'q: 'e>(
         self,
         query: &'q str,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<<Self::Database as sqlx::Database>::Statement<'q>, sqlx::Error>,
     >
@@ -381,14 +756,21 @@ where
         'c: 'e,
     {
         let attrs = &self.attributes;
-        crate::exec_fut!("sqlx.prepare", query, attrs, self.inner.prepare(query))
+        crate::exec_fut!(
+            crate::Operation::Prepare,
+            query,
+            attrs,
+            crate::span::prepared(query, self.inner.prepare(query)),
+            self.idle.as_ref(),
+            Some(self.connection_id)
+        )
     }
 
     fn prepare_with<'e, 'q: 'e>(
         self,
         sql: &'q str,
         parameters: &'e [<Self::Database as sqlx::Database>::TypeInfo],
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<<Self::Database as sqlx::Database>::Statement<'q>, sqlx::Error>,
     >
@@ -397,10 +779,12 @@ where
     {
         let attrs = &self.attributes;
         crate::exec_fut!(
-            "sqlx.prepare_with",
+            crate::Operation::PrepareWith,
             sql,
             attrs,
-            self.inner.prepare_with(sql, parameters)
+            crate::span::prepared(sql, self.inner.prepare_with(sql, parameters)),
+            self.idle.as_ref(),
+            Some(self.connection_id)
         )
     }
 }
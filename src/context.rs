@@ -0,0 +1,142 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Key/value pairs recorded on the database spans created while polling a
+/// [`WithContext`] future.
+type Values = Arc<Vec<(String, String)>>;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Values>> = const { RefCell::new(None) };
+}
+
+/// Runs `future` with `values` recorded on every database span created
+/// within it.
+///
+/// This tags all queries of a request, e.g. with a `tenant.id`, without
+/// passing wrappers around. The values are recorded as a `sqlx.context` field
+/// formatted as `key=value` pairs and, with the `opentelemetry` feature, as
+/// individual OpenTelemetry attributes. Nested contexts extend the values of
+/// the enclosing one.
+///
+/// The context follows the future across threads, as it is installed each
+/// time the future is polled, but not into tasks spawned from it.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// sqlx_tracing::with_context([("tenant.id", tenant_id)], async {
+///     sqlx::query("SELECT 1").execute(&pool).await
+/// })
+/// .await?;
+/// ```
+pub fn with_context<F, I, K, V>(values: I, future: F) -> WithContext<F>
+where
+    F: Future,
+    I: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: Into<String>,
+{
+    WithContext {
+        inner: future,
+        values: merged(values),
+    }
+}
+
+/// Runs `f` with `values` recorded on the database spans it creates, like
+/// [`with_context`] for synchronous code.
+pub(crate) fn in_scope<R, I, K, V>(values: I, f: impl FnOnce() -> R) -> R
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: Into<String>,
+{
+    let _scope = crate::scoped::install(&CURRENT, Some(merged(values)));
+    f()
+}
+
+/// Returns the current context values extended with `values`.
+fn merged<I, K, V>(values: I) -> Values
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: Into<String>,
+{
+    let mut merged = current()
+        .map(|values| (*values).clone())
+        .unwrap_or_default();
+    for (key, value) in values {
+        let key = key.into();
+        let value = value.into();
+        match merged.iter_mut().find(|(existing, _)| *existing == key) {
+            Some((_, existing)) => *existing = value,
+            None => merged.push((key, value)),
+        }
+    }
+    Arc::new(merged)
+}
+
+pin_project_lite::pin_project! {
+    /// Future returned by [`with_context`].
+    #[derive(Debug)]
+    pub struct WithContext<F> {
+        #[pin]
+        inner: F,
+        values: Values,
+    }
+}
+
+impl<F: Future> Future for WithContext<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _scope = crate::scoped::install(&CURRENT, Some(this.values.clone()));
+        this.inner.poll(cx)
+    }
+}
+
+fn current() -> Option<Values> {
+    crate::scoped::current(&CURRENT)
+}
+
+/// Records the extensions of the attributes and the current context values
+/// on `span`, later values taking precedence over earlier ones with the same
+/// key.
+pub(crate) fn record<'a>(
+    span: &tracing::Span,
+    extensions: impl Iterator<Item = (&'a str, &'a str)>,
+) {
+    let values = current();
+    let values = values.as_deref().map(Vec::as_slice).unwrap_or_default();
+    let mut pairs: Vec<(&str, &str)> = Vec::new();
+    let mut insert = |key, value| match pairs.iter_mut().find(|(existing, _)| *existing == key) {
+        Some((_, existing)) => *existing = value,
+        None => pairs.push((key, value)),
+    };
+    for (key, value) in extensions {
+        insert(key, value);
+    }
+    for (key, value) in values {
+        insert(key, value);
+    }
+    if pairs.is_empty() {
+        return;
+    }
+    let formatted = pairs
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(",");
+    span.record("sqlx.context", formatted);
+    #[cfg(feature = "opentelemetry")]
+    {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        for (key, value) in pairs {
+            span.set_attribute(key.to_owned(), value.to_owned());
+        }
+    }
+}
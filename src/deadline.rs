@@ -0,0 +1,173 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures_core::Stream;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Instant>> = const { RefCell::new(None) };
+}
+
+/// Runs `future` with every database operation started within it bounded by
+/// `deadline`.
+///
+/// This is meant to be set by request middleware so queries give up once the
+/// request's budget is spent. The time left when an operation starts is
+/// recorded as `db.query.deadline_ms` on its span. Operations starting after
+/// the deadline fail without reaching the database, and operations still
+/// running when it passes are cancelled, both with a [`DeadlineExceeded`]
+/// error. Nested deadlines can only shorten the enclosing one.
+///
+/// The deadline follows the future across threads, as it is installed each
+/// time the future is polled, but not into tasks spawned from it.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let deadline = Instant::now() + Duration::from_millis(250);
+/// sqlx_tracing::with_deadline(deadline, async {
+///     sqlx::query("SELECT 1").execute(&pool).await
+/// })
+/// .await?;
+/// ```
+pub fn with_deadline<F: Future>(deadline: Instant, future: F) -> WithDeadline<F> {
+    let deadline = current().map_or(deadline, |current| current.min(deadline));
+    WithDeadline {
+        inner: future,
+        deadline,
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// Future returned by [`with_deadline`].
+    #[derive(Debug)]
+    pub struct WithDeadline<F> {
+        #[pin]
+        inner: F,
+        deadline: Instant,
+    }
+}
+
+impl<F: Future> Future for WithDeadline<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _scope = crate::scoped::install(&CURRENT, Some(*this.deadline));
+        this.inner.poll(cx)
+    }
+}
+
+fn current() -> Option<Instant> {
+    crate::scoped::current(&CURRENT)
+}
+
+/// Error returned by database operations started after, or still running
+/// at, the deadline set with [`with_deadline`].
+///
+/// It is surfaced as an [`sqlx::Error::Io`] of kind
+/// [`TimedOut`](std::io::ErrorKind::TimedOut) wrapping this type, which
+/// [`DeadlineExceeded::is`] detects.
+#[derive(Debug)]
+pub struct DeadlineExceeded;
+
+impl DeadlineExceeded {
+    /// Returns whether `err` was caused by an exhausted deadline.
+    pub fn is(err: &sqlx::Error) -> bool {
+        match err {
+            sqlx::Error::Io(err) => err.get_ref().is_some_and(|inner| inner.is::<Self>()),
+            _ => false,
+        }
+    }
+
+    fn error() -> sqlx::Error {
+        sqlx::Error::Io(std::io::Error::new(std::io::ErrorKind::TimedOut, Self))
+    }
+}
+
+impl std::fmt::Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("deadline exceeded before the database operation completed")
+    }
+}
+
+impl std::error::Error for DeadlineExceeded {}
+
+/// Returns the current deadline, recording the time left before it as
+/// `db.query.deadline_ms` on the current span.
+pub(crate) fn start() -> Option<Instant> {
+    let deadline = current()?;
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    tracing::Span::current().record("db.query.deadline_ms", remaining.as_millis() as u64);
+    Some(deadline)
+}
+
+/// Returns the time left before the current deadline, if any.
+pub(crate) fn time_left() -> Option<Duration> {
+    current().map(|deadline| deadline.saturating_duration_since(Instant::now()))
+}
+
+/// Returns the time left before `deadline`, or `None` when it has passed.
+fn remaining(deadline: Instant) -> Option<Duration> {
+    Some(deadline.saturating_duration_since(Instant::now())).filter(|left| !left.is_zero())
+}
+
+/// Runs `fut`, failing with [`DeadlineExceeded`] once `deadline` passes.
+pub(crate) async fn bound<F, T>(deadline: Option<Instant>, fut: F) -> Result<T, sqlx::Error>
+where
+    F: Future<Output = Result<T, sqlx::Error>>,
+{
+    let Some(deadline) = deadline else {
+        return fut.await;
+    };
+    let Some(remaining) = remaining(deadline) else {
+        return Err(DeadlineExceeded::error());
+    };
+    let fut = std::pin::pin!(fut);
+    match futures_util::future::select(fut, futures_timer::Delay::new(remaining)).await {
+        futures_util::future::Either::Left((res, _)) => res,
+        futures_util::future::Either::Right(_) => Err(DeadlineExceeded::error()),
+    }
+}
+
+/// Stream failing with [`DeadlineExceeded`] and ending once its deadline
+/// passes.
+pub(crate) struct Bounded<S> {
+    inner: Option<S>,
+    delay: Option<futures_timer::Delay>,
+}
+
+impl<S> Bounded<S> {
+    pub(crate) fn new(deadline: Option<Instant>, stream: S) -> Self {
+        let delay = deadline
+            .map(|deadline| futures_timer::Delay::new(remaining(deadline).unwrap_or_default()));
+        Self {
+            inner: Some(stream),
+            delay,
+        }
+    }
+}
+
+impl<S, T> Stream for Bounded<S>
+where
+    S: Stream<Item = Result<T, sqlx::Error>> + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let Some(inner) = this.inner.as_mut() else {
+            return Poll::Ready(None);
+        };
+        if let Some(delay) = this.delay.as_mut()
+            && Pin::new(delay).poll(cx).is_ready()
+        {
+            // Dropping the stream cancels the operation.
+            this.inner = None;
+            return Poll::Ready(Some(Err(DeadlineExceeded::error())));
+        }
+        Pin::new(inner).poll_next(cx)
+    }
+}
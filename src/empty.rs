@@ -0,0 +1,41 @@
+/// How statements whose SQL is empty or only whitespace, once rewritten, are
+/// traced, set with
+/// [`PoolBuilder::with_empty_statements`](crate::PoolBuilder::with_empty_statements).
+///
+/// Such statements usually come from a templating or query rewriting bug.
+/// They are still sent to the database, which decides whether they fail.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmptyStatements {
+    /// Create the statement span and emit a `sqlx.statement.empty` event.
+    #[default]
+    Flag,
+    /// Emit a `sqlx.statement.empty` event instead of the statement span.
+    Skip,
+    /// Trace them like any other statement.
+    Trace,
+}
+
+/// Emits a `sqlx.statement.empty` event when `sql` is empty or only
+/// whitespace, as configured by the attributes, and returns whether the
+/// statement span should be created.
+pub(crate) fn check(attrs: &crate::Attributes, connection_id: Option<u64>, sql: &str) -> bool {
+    if !sql.trim().is_empty() || attrs.config().empty_statements == EmptyStatements::Trace {
+        return true;
+    }
+    tracing::event!(
+        name: crate::names::STATEMENT_EMPTY,
+        tracing::Level::WARN,
+        {
+            "db.client.pool.name" = attrs.pool_name.as_deref(),
+            "db.client.connection.id" = connection_id,
+        },
+        "empty SQL statement"
+    );
+    attrs.config().empty_statements == EmptyStatements::Flag
+}
+
+/// Returns whether the statement running `sql` is skipped, in which case it
+/// is not exported either.
+pub(crate) fn skipped(attrs: &crate::Attributes, sql: &str) -> bool {
+    attrs.config().empty_statements == EmptyStatements::Skip && sql.trim().is_empty()
+}
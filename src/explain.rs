@@ -0,0 +1,161 @@
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+use futures_core::future::BoxFuture;
+
+/// Arguments of a statement sampled for `EXPLAIN`, set aside before the
+/// statement runs.
+pub(crate) struct Sample<'q, DB: sqlx::Database> {
+    arguments: Option<DB::Arguments<'q>>,
+}
+
+/// Query wrapper handing out arguments taken from the wrapped query up front.
+pub(crate) struct Sampled<'q, DB: sqlx::Database, E> {
+    inner: E,
+    arguments: Option<Option<DB::Arguments<'q>>>,
+}
+
+impl<'q, DB, E> sqlx::Execute<'q, DB> for Sampled<'q, DB, E>
+where
+    DB: sqlx::Database,
+    E: sqlx::Execute<'q, DB>,
+{
+    fn sql(&self) -> &'q str {
+        self.inner.sql()
+    }
+
+    fn statement(&self) -> Option<&DB::Statement<'q>> {
+        self.inner.statement()
+    }
+
+    fn take_arguments(
+        &mut self,
+    ) -> Result<Option<<DB as sqlx::Database>::Arguments<'q>>, sqlx::error::BoxDynError> {
+        match self.arguments.take() {
+            Some(arguments) => Ok(arguments),
+            None => self.inner.take_arguments(),
+        }
+    }
+
+    fn persistent(&self) -> bool {
+        self.inner.persistent()
+    }
+}
+
+/// Returns whether a runtime is available to [`spawn`] the `EXPLAIN` of a
+/// sampled statement on: the current tokio runtime with the `runtime-tokio`
+/// feature, or async-std's global executor with the `runtime-async-std`
+/// feature.
+pub(crate) fn runtime_available() -> bool {
+    #[cfg(feature = "runtime-tokio")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return true;
+    }
+    cfg!(feature = "runtime-async-std")
+}
+
+/// Spawns `fut` on the runtime found by [`runtime_available`], dropping it
+/// when there is none.
+#[cfg(feature = "postgres")]
+pub(crate) fn spawn<F>(fut: F)
+where
+    F: std::future::Future<Output = ()> + Send + 'static,
+{
+    #[cfg(feature = "runtime-tokio")]
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        handle.spawn(fut);
+        return;
+    }
+    #[cfg(feature = "runtime-async-std")]
+    async_std::task::spawn(fut);
+    #[cfg(not(feature = "runtime-async-std"))]
+    drop(fut);
+}
+
+/// Decides whether `query` is sampled for `EXPLAIN`, keeping a copy of its
+/// arguments when it is.
+///
+/// Only `SELECT` statements are sampled, and counted towards the sampling
+/// rate, as `EXPLAIN ANALYZE` runs the explained statement a second time.
+/// Nothing is sampled without a runtime to explain statements on, see
+/// [`runtime_available`].
+pub(crate) fn sample<'q, DB, E>(
+    mut query: E,
+    attrs: &crate::Attributes,
+) -> (Sampled<'q, DB, E>, Option<Sample<'q, DB>>)
+where
+    DB: crate::prelude::Database + sqlx::Database,
+    E: sqlx::Execute<'q, DB>,
+{
+    let sampled = attrs.config().explain_sampling.is_some_and(|every| {
+        runtime_available()
+            && crate::span::operation(query.sql())
+                .is_some_and(|operation| operation.eq_ignore_ascii_case("select"))
+            && attrs
+                .config()
+                .explain_counter
+                .fetch_add(1, Ordering::Relaxed)
+                % every
+                == 0
+    });
+    if !sampled {
+        return (
+            Sampled {
+                inner: query,
+                arguments: None,
+            },
+            None,
+        );
+    }
+    let Ok(arguments) = query.take_arguments() else {
+        // Leave encoding errors to the statement itself.
+        return (
+            Sampled {
+                inner: query,
+                arguments: None,
+            },
+            None,
+        );
+    };
+    let copy = match &arguments {
+        Some(arguments) => DB::clone_arguments(arguments).map(Some),
+        None => Some(None),
+    };
+    let sample = copy.map(|arguments| Sample { arguments });
+    let query = Sampled {
+        inner: query,
+        arguments: Some(arguments),
+    };
+    (query, sample)
+}
+
+/// Runs `fut` and, when it succeeds and a sample was taken, explains the
+/// sampled statement on `pool` without waiting for the plan.
+///
+/// `sql` is the SQL that was sent, i.e. after the query rewriter ran, so that
+/// the plan is that of the statement that ran.
+pub(crate) fn after<'e, 'q: 'e, DB, T>(
+    pool: &sqlx::Pool<DB>,
+    attrs: &Arc<crate::Attributes>,
+    sample: Option<Sample<'q, DB>>,
+    sql: &str,
+    fut: BoxFuture<'e, Result<T, sqlx::Error>>,
+) -> BoxFuture<'e, Result<T, sqlx::Error>>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+    T: Send + 'e,
+{
+    let Some(sample) = sample else {
+        return fut;
+    };
+    let pool = pool.clone();
+    let attrs = attrs.clone();
+    let sql = sql.to_owned();
+    Box::pin(async move {
+        let res = fut.await;
+        if res.is_ok() {
+            DB::explain(&pool, &attrs, &sql, sample.arguments);
+        }
+        res
+    })
+}
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+type CompleteFn = dyn Fn(&SpanRecord) + Send + Sync;
+
+/// Summary of a completed statement, delivered to the callback set with
+/// [`PoolBuilder::on_span_complete`](crate::PoolBuilder::on_span_complete).
+///
+/// With the `serde` feature it implements `Serialize`, so it can be shipped
+/// as is to sinks like Kafka or ClickHouse.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct SpanRecord {
+    /// Operation of the statement.
+    pub operation: crate::Operation,
+    /// Name of the pool the statement went through.
+    pub pool_name: String,
+    /// Fingerprint of the statement text, the 64-bit FNV-1a hash also
+    /// recorded as `db.statement.id`.
+    pub fingerprint: String,
+    /// Time from the start of the statement until its result, or the end of
+    /// its row stream, in milliseconds.
+    pub duration_ms: f64,
+    /// Number of rows returned, or affected by `execute`, when known. For
+    /// row streams, this is the number of rows yielded, without the query
+    /// results of `fetch_many` and `execute_many`.
+    pub rows: Option<u64>,
+    /// Whether the statement failed.
+    pub failed: bool,
+    /// Database error code of a failed statement, such as a SQLSTATE.
+    pub error_code: Option<String>,
+}
+
+/// Callback receiving a [`SpanRecord`] for every completed statement.
+#[derive(Clone)]
+pub(crate) struct SpanExporter(Arc<CompleteFn>);
+
+impl SpanExporter {
+    pub(crate) fn new<F>(callback: F) -> Self
+    where
+        F: Fn(&SpanRecord) + Send + Sync + 'static,
+    {
+        Self(Arc::new(callback))
+    }
+}
+
+impl std::fmt::Debug for SpanExporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpanExporter").finish_non_exhaustive()
+    }
+}
+
+/// Statement in flight, reported to the exporter once it completes.
+pub(crate) struct Pending {
+    exporter: SpanExporter,
+    operation: crate::Operation,
+    pool_name: String,
+    fingerprint: String,
+    start: Instant,
+}
+
+impl Pending {
+    /// Starts timing a statement, or returns `None` without an exporter or
+    /// when the statement is skipped or untraced.
+    pub(crate) fn start(
+        attrs: &crate::Attributes,
+        operation: crate::Operation,
+        sql: &str,
+    ) -> Option<Self> {
+        let exporter = attrs.config().span_exporter.clone()?;
+        if crate::empty::skipped(attrs, sql) || crate::untraced::active() {
+            return None;
+        }
+        Some(Self {
+            exporter,
+            operation,
+            pool_name: attrs
+                .limited("db.client.pool.name", attrs.pool_name())
+                .into_owned(),
+            fingerprint: crate::span::statement_id(sql),
+            start: Instant::now(),
+        })
+    }
+
+    /// Reports the statement with its row count and error, if any.
+    pub(crate) fn finish(self, rows: Option<u64>, error: Option<&sqlx::Error>) {
+        let error_code = error
+            .and_then(sqlx::Error::as_database_error)
+            .and_then(|err| err.code())
+            .map(|code| code.into_owned());
+        (self.exporter.0)(&SpanRecord {
+            operation: self.operation,
+            pool_name: self.pool_name,
+            fingerprint: self.fingerprint,
+            duration_ms: self.start.elapsed().as_secs_f64() * 1000.0,
+            rows,
+            failed: error.is_some(),
+            error_code,
+        });
+    }
+}
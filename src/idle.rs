@@ -0,0 +1,160 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Returns a new id for a traced transaction, recorded as
+/// `db.transaction.id`.
+fn next_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Tracks the time a transaction spends waiting between statements.
+///
+/// The clock is resumed when a statement starts and paused when it produces
+/// a result, so the accumulated total only covers the gaps in between. The
+/// statements and the time spent running them are counted as well, for the
+/// [`TransactionReport`](crate::TransactionReport).
+///
+/// The tracker also identifies the transaction, so statement spans can
+/// record the transaction they belong to, along with its label. Savepoints
+/// get their own tracker, with their own id, sharing the clock of the
+/// enclosing transaction and inheriting its label.
+#[derive(Debug)]
+pub(crate) struct IdleTracker {
+    id: u64,
+    depth: usize,
+    threshold: Option<Duration>,
+    label: Mutex<Option<String>>,
+    state: Arc<Mutex<IdleState>>,
+}
+
+#[derive(Debug)]
+struct IdleState {
+    started: Instant,
+    last_activity: Instant,
+    total: Duration,
+    statements: u64,
+    busy: Duration,
+}
+
+impl IdleTracker {
+    pub(crate) fn new(threshold: Option<Duration>) -> Self {
+        Self {
+            id: next_id(),
+            depth: 0,
+            threshold,
+            label: Mutex::new(None),
+            state: Arc::new(Mutex::new(IdleState {
+                started: Instant::now(),
+                last_activity: Instant::now(),
+                total: Duration::ZERO,
+                statements: 0,
+                busy: Duration::ZERO,
+            })),
+        }
+    }
+
+    /// Returns a tracker for a savepoint nested in this transaction.
+    pub(crate) fn nested(&self) -> Self {
+        Self {
+            id: next_id(),
+            depth: self.depth + 1,
+            threshold: self.threshold,
+            label: Mutex::new(self.label()),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Returns the id of the transaction, recorded as `db.transaction.id`.
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the savepoint depth of the transaction, `0` for a top-level
+    /// transaction.
+    pub(crate) fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the label of the transaction, recorded as
+    /// `db.transaction.label`.
+    pub(crate) fn label(&self) -> Option<String> {
+        self.label.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub(crate) fn set_label(&self, label: String) {
+        *self.label.lock().unwrap_or_else(|e| e.into_inner()) = Some(label);
+    }
+
+    /// Accounts for the idle gap since the last activity when a statement
+    /// starts.
+    ///
+    /// Returns a handle to pause the clock once the statement completes.
+    pub(crate) fn resume(tracker: Option<&Arc<Self>>) -> Option<Arc<Self>> {
+        let tracker = tracker?;
+        tracker.mark();
+        tracker
+            .state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .statements += 1;
+        Some(tracker.clone())
+    }
+
+    /// Accounts for the final idle gap and returns the total idle time.
+    pub(crate) fn finish(&self) -> Duration {
+        self.mark();
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).total
+    }
+
+    /// Marks the end of a statement, restarting the idle clock.
+    pub(crate) fn pause(&self) {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        // The statement ran since it resumed the tracker.
+        let ran = now.saturating_duration_since(state.last_activity);
+        state.busy += ran;
+        state.last_activity = now;
+    }
+
+    /// Returns the number of statements run within the transaction so far.
+    pub(crate) fn statements(&self) -> u64 {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .statements
+    }
+
+    /// Returns the activity of the transaction so far, once
+    /// [`finish`](Self::finish)ed, with the `savepoints` it created.
+    pub(crate) fn report(&self, savepoints: usize) -> crate::TransactionReport {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        crate::TransactionReport {
+            statements: state.statements,
+            database_time: state.busy,
+            idle_time: state.total,
+            savepoints,
+            duration: state.started.elapsed(),
+        }
+    }
+
+    /// Adds the gap since the last activity to the total, warning when it
+    /// exceeds the configured threshold.
+    fn mark(&self) {
+        let now = Instant::now();
+        let gap = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            let gap = now.saturating_duration_since(state.last_activity);
+            state.total += gap;
+            state.last_activity = now;
+            gap
+        };
+        if !cfg!(feature = "disabled") && self.threshold.is_some_and(|threshold| gap > threshold) {
+            tracing::warn!(
+                "db.transaction.idle_ms" = gap.as_millis() as u64,
+                "transaction was idle between statements longer than the configured threshold"
+            );
+        }
+    }
+}
@@ -1,14 +1,59 @@
 #![doc = include_str!("../README.md")]
+#![deny(clippy::undocumented_unsafe_blocks)]
+// With the `disabled` feature, statements are handed straight to SQLx and the
+// instrumentation they would go through is left unused.
+#![cfg_attr(feature = "disabled", allow(dead_code, unused_imports))]
 
 use std::sync::Arc;
 
 use tracing::Instrument;
 
+mod budget;
+mod bulk;
+mod cache;
+mod cardinality;
+mod concurrent;
+mod config;
 mod connection;
+mod context;
+mod deadline;
+mod empty;
+mod explain;
+mod export;
+pub(crate) mod idle;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+mod maintenance;
+#[cfg(all(feature = "metrics", not(feature = "disabled")))]
+mod metrics;
+#[cfg(feature = "migrate")]
+mod migrate;
+pub mod names;
+mod operation;
+mod options;
+#[cfg(feature = "opentelemetry")]
+mod otel;
 mod pool;
 pub mod prelude;
+mod queue;
+pub mod record;
+mod repeat;
+mod rewrite;
+mod row;
+mod scoped;
+mod script;
 pub(crate) mod span;
+mod statement_cache;
+mod status;
+mod stream;
+#[cfg(all(feature = "testing", any(feature = "postgres", feature = "sqlite")))]
+pub mod testing;
+mod throttle;
 mod transaction;
+mod untraced;
+mod validate;
+
+#[cfg(feature = "mysql")]
+pub mod mysql;
 
 #[cfg(feature = "postgres")]
 pub mod postgres;
@@ -16,29 +61,329 @@ pub mod postgres;
 #[cfg(feature = "sqlite")]
 pub mod sqlite;
 
+pub use budget::{QueryBudget, QueryBudgetExceeded, WithBudget, with_budget};
+pub use cache::CachedPool;
+pub use connection::PingTracing;
+pub use context::{WithContext, with_context};
+pub use deadline::{DeadlineExceeded, WithDeadline, with_deadline};
+pub use empty::EmptyStatements;
+pub use export::SpanRecord;
+pub use operation::Operation;
+pub use pool::{AcquireErrorReason, AcquireOutcomes};
+pub use row::TracedRow;
+pub use status::ErrorStatus;
+pub use transaction::{StatementTimeout, TransactionReport};
+pub use untraced::{Untraced, untraced};
+pub use validate::BuildError;
+
+#[cfg(feature = "migrate")]
+pub use migrate::{create_database, database_exists, drop_database};
+#[cfg(feature = "opentelemetry")]
+pub use otel::{current_span_id, current_trace_id};
+
 /// Attributes describing the database connection and context.
 /// Used for span enrichment and attribute propagation.
-#[derive(Debug)]
-struct Attributes {
+///
+/// The identifying attributes can be built with [`Attributes::builder`] and
+/// applied with [`PoolBuilder::with_attributes`], which lets a set of
+/// attributes be shared between pools. The attributes of an existing pool are
+/// returned by [`Pool::attributes`], and refer to the recording options of that
+/// pool.
+#[derive(Clone, Debug, Default)]
+pub struct Attributes {
     name: Option<String>,
+    pool_name: Option<String>,
+    system_name: Option<String>,
+    upstream_address: Option<String>,
+    workload: Option<String>,
     host: Option<String>,
+    hosts: Vec<String>,
     port: Option<u16>,
     database: Option<String>,
-    record_query_text: bool,
-    record_error_details: bool,
+    user: Option<String>,
+    extensions: std::collections::BTreeMap<String, String>,
+    resource: std::collections::BTreeMap<String, String>,
+    ssl_mode: Option<&'static str>,
+    tls: Option<bool>,
+    connection_string: Option<String>,
+    cardinality: Option<Arc<cardinality::CardinalityGuard>>,
+    statement_cache: Option<Arc<statement_cache::StatementCacheWatch>>,
+    // Set when the pool is built, defaults apply to attributes built on their own.
+    config: Option<Arc<config::Config>>,
 }
 
-impl Default for Attributes {
-    fn default() -> Self {
-        Self {
-            name: None,
-            host: None,
-            port: None,
-            database: None,
-            record_query_text: true,
-            record_error_details: true,
+impl Attributes {
+    /// Returns a builder for the identifying attributes.
+    pub fn builder() -> AttributesBuilder {
+        AttributesBuilder::default()
+    }
+
+    /// Returns the name recorded as `peer.service`, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the pool name recorded as `db.client.pool.name`, empty unless
+    /// the attributes are those of a pool.
+    pub fn pool_name(&self) -> &str {
+        self.pool_name.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the database name, if any.
+    pub fn database(&self) -> Option<&str> {
+        self.database.as_deref()
+    }
+
+    /// Returns the host, if any.
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    /// Returns the candidate hosts of a multi-host setup, empty unless set.
+    pub fn hosts(&self) -> impl Iterator<Item = &str> {
+        self.hosts.iter().map(String::as_str)
+    }
+
+    /// Returns the recording options of the pool the attributes belong to,
+    /// or the default ones.
+    fn config(&self) -> &config::Config {
+        static DEFAULT: std::sync::LazyLock<config::Config> =
+            std::sync::LazyLock::new(Default::default);
+        self.config.as_deref().unwrap_or(&DEFAULT)
+    }
+
+    /// Returns the value to record for the label-like `field`, limited by the
+    /// guard set with [`PoolBuilder::with_cardinality_limit`].
+    fn limited<'a>(&self, field: &'static str, value: &'a str) -> std::borrow::Cow<'a, str> {
+        match &self.cardinality {
+            Some(guard) => guard.value(field, value),
+            None => std::borrow::Cow::Borrowed(value),
         }
     }
+
+    /// Returns the capacity of the statement cache of each connection, if
+    /// known, see [`AttributesBuilder::with_statement_cache_capacity`].
+    pub fn statement_cache_capacity(&self) -> Option<usize> {
+        self.statement_cache.as_ref().map(|watch| watch.capacity())
+    }
+
+    /// Returns the value of the `db.client.connection.candidate_hosts` field,
+    /// the comma-separated candidate hosts, if any.
+    fn candidate_hosts(&self) -> Option<String> {
+        (!self.hosts.is_empty()).then(|| self.hosts.join(","))
+    }
+
+    /// Returns the port, if any.
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// Returns the database user, if any.
+    pub fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    /// Returns the value of the extension `key`, if set.
+    pub fn extension(&self, key: &str) -> Option<&str> {
+        self.extensions.get(key).map(String::as_str)
+    }
+
+    /// Returns all extensions, ordered by key.
+    pub fn extensions(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.extensions
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Returns all resource attributes, ordered by key.
+    pub fn resource_attributes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.resource
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Returns the workload of the pool, set for partitions, if any.
+    pub fn workload(&self) -> Option<&str> {
+        self.workload.as_deref()
+    }
+
+    /// Returns the proxy endpoint as `server.address` and the database
+    /// behind it as `db.upstream.address`, when connecting through a proxy,
+    /// along with the workload of the pool as `db.workload`.
+    fn upstream(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.upstream_address
+            .as_deref()
+            .into_iter()
+            .flat_map(|upstream| {
+                let server = self.host.as_deref().map(|host| ("server.address", host));
+                server
+                    .into_iter()
+                    .chain([("db.upstream.address", upstream)])
+            })
+            .chain(
+                self.workload
+                    .as_deref()
+                    .map(|workload| ("db.workload", workload)),
+            )
+    }
+
+    /// Returns the value of the `db.system.name` field, overridden with
+    /// [`PoolBuilder::with_system_name`] or provided by the database.
+    fn system_name<DB: prelude::Database>(&self) -> &str {
+        self.system_name.as_deref().unwrap_or(DB::system_name())
+    }
+}
+
+/// Inserts the key/values of `attributes` into `map`.
+fn extend<I, K, V>(map: &mut std::collections::BTreeMap<String, String>, attributes: I)
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: Into<String>,
+{
+    map.extend(
+        attributes
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into())),
+    );
+}
+
+/// Builder for the identifying [`Attributes`] of a pool.
+///
+/// Extensions are arbitrary key/values, e.g. a deployment region, recorded on
+/// every span like the values set with [`with_context`].
+#[derive(Debug, Default)]
+pub struct AttributesBuilder {
+    attributes: Attributes,
+}
+
+impl AttributesBuilder {
+    /// Set a custom name for the pool (for peer.service attribute).
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.attributes.name = Some(name.into());
+        self
+    }
+
+    /// Set the database name attribute.
+    pub fn with_database(mut self, database: impl Into<String>) -> Self {
+        self.attributes.database = Some(database.into());
+        self
+    }
+
+    /// Set the host attribute.
+    pub fn with_host(mut self, host: impl Into<String>) -> Self {
+        self.attributes.host = Some(host.into());
+        self
+    }
+
+    /// Set the port attribute.
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.attributes.port = Some(port);
+        self
+    }
+
+    /// Set the candidate hosts of a multi-host or failover setup, recorded
+    /// as `db.client.connection.candidate_hosts` on lifecycle spans such as
+    /// `sqlx.pool.acquire`.
+    ///
+    /// For Postgres, a comma-separated list of hosts in the connect options
+    /// is split into the candidate hosts, the first of them being recorded
+    /// as the host. To know which candidate a connection actually reached,
+    /// see [`PoolOptions::with_server_address_recording`].
+    pub fn with_hosts<I>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.attributes.hosts = hosts.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the database user attribute.
+    pub fn with_user(mut self, user: impl Into<String>) -> Self {
+        self.attributes.user = Some(user.into());
+        self
+    }
+
+    /// Set the `db.ssl_mode` attribute, as named by the database.
+    pub fn with_ssl_mode(mut self, ssl_mode: &'static str) -> Self {
+        self.attributes.ssl_mode = Some(ssl_mode);
+        self
+    }
+
+    /// Set whether connections are known to use TLS, recorded as
+    /// `tls.established`.
+    pub fn with_tls(mut self, tls: bool) -> Self {
+        self.attributes.tls = Some(tls);
+        self
+    }
+
+    /// Set the extension `key` to `value`.
+    pub fn with_extension(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.extensions.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add OpenTelemetry resource-style attributes, such as `service.version`
+    /// or `host.name`, recorded on every span.
+    ///
+    /// They are meant for backends that don't enrich spans with the
+    /// attributes of the tracer's resource. Like
+    /// [extensions](AttributesBuilder::with_extension), they are recorded
+    /// within the `sqlx.context` field and, with the `opentelemetry` feature,
+    /// as individual attributes; extensions and context values with the same
+    /// key take precedence.
+    ///
+    /// None by default.
+    pub fn with_resource_attributes<I, K, V>(mut self, attributes: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        extend(&mut self.attributes.resource, attributes);
+        self
+    }
+
+    /// Limit the distinct values recorded for label-like span fields to
+    /// `max_values` per field, and their length to `max_length` bytes.
+    ///
+    /// This protects metrics backends deriving labels from spans against
+    /// unbounded values, such as a database or pool per tenant. The guarded
+    /// fields are `db.client.pool.name`, `db.name`, `db.namespace` and
+    /// `db.operation`, along with the pool name of exported
+    /// [`SpanRecord`]s. Longer values are replaced with their 64-bit FNV-1a
+    /// hash, and values beyond the limit with `(other)`. The first
+    /// replacement of each field emits a `sqlx.cardinality.limited` warning
+    /// event. Pools built with these attributes share the limit.
+    ///
+    /// Not limited by default.
+    pub fn with_cardinality_limit(mut self, max_values: usize, max_length: usize) -> Self {
+        self.attributes.cardinality = Some(Arc::new(cardinality::CardinalityGuard::new(
+            max_values, max_length,
+        )));
+        self
+    }
+
+    /// Set the capacity of the statement cache of each connection, recorded
+    /// as `db.client.statement_cache.capacity` on `sqlx.pool.acquire` spans.
+    ///
+    /// Once the pool ran more distinct persistent statements than the cache
+    /// holds, a `sqlx.statement_cache.exceeded` warning event is emitted, as
+    /// connections will then keep evicting and preparing statements again.
+    /// A capacity of 0, i.e. a disabled cache, is not watched.
+    ///
+    /// Set from the connect options for Postgres.
+    pub fn with_statement_cache_capacity(mut self, capacity: usize) -> Self {
+        self.attributes.statement_cache = (capacity > 0 && !cfg!(feature = "disabled"))
+            .then(|| Arc::new(statement_cache::StatementCacheWatch::new(capacity)));
+        self
+    }
+
+    /// Build the [`Attributes`].
+    pub fn build(self) -> Attributes {
+        self.attributes
+    }
 }
 
 /// Builder for constructing a [`Pool`] with custom attributes.
@@ -49,70 +394,219 @@ impl Default for Attributes {
 pub struct PoolBuilder<DB: sqlx::Database> {
     pool: sqlx::Pool<DB>,
     attributes: Attributes,
+    config: config::Config,
+    sqlx_logging: Option<bool>,
+    connection_string: Option<String>,
 }
 
-// this is required because `pool.connect_options().to_url_lossy()` panics with sqlite
-#[cfg(feature = "postgres")]
-impl From<sqlx::Pool<sqlx::Postgres>> for PoolBuilder<sqlx::Postgres> {
+impl<DB> From<sqlx::Pool<DB>> for PoolBuilder<DB>
+where
+    DB: prelude::Database + sqlx::Database,
+{
     /// Create a new builder from an existing SQLx pool.
-    fn from(pool: sqlx::Pool<sqlx::Postgres>) -> Self {
-        use sqlx::ConnectOptions;
+    ///
+    /// Attributes are extracted from the pool's connect options by
+    /// [`prelude::Database::connection_attributes`].
+    fn from(pool: sqlx::Pool<DB>) -> Self {
+        let attributes = connection_attributes::<DB>(&pool.connect_options());
+        Self::with_connection_attributes(pool, attributes)
+    }
+}
 
-        let url = pool.connect_options().to_url_lossy();
-        let attributes = Attributes {
-            host: url.host_str().map(String::from),
-            port: url.port(),
-            database: url
-                .path_segments()
-                .and_then(|mut segments| segments.next().map(String::from)),
-            ..Default::default()
-        };
-        Self { pool, attributes }
+impl<DB> PoolBuilder<DB>
+where
+    DB: prelude::Database + sqlx::Database,
+{
+    /// Create a new builder from an existing SQLx pool, with the
+    /// `attributes` extracted from its connect options.
+    pub(crate) fn with_connection_attributes(pool: sqlx::Pool<DB>, attributes: Attributes) -> Self {
+        let connection_string = DB::connection_string(&pool.connect_options());
+        Self {
+            pool,
+            attributes,
+            config: Default::default(),
+            sqlx_logging: None,
+            connection_string,
+        }
     }
 }
 
-// this is required because `pool.connect_options().to_url_lossy()` panics with sqlite
-#[cfg(feature = "sqlite")]
-impl From<sqlx::Pool<sqlx::Sqlite>> for PoolBuilder<sqlx::Sqlite> {
-    /// Create a new builder from an existing SQLx pool.
-    fn from(pool: sqlx::Pool<sqlx::Sqlite>) -> Self {
-        let attributes = Attributes {
-            host: pool
-                .connect_options()
-                .get_filename()
-                .to_str()
-                .map(String::from),
-            ..Default::default()
-        };
-        Self { pool, attributes }
+/// Returns the attributes extracted from the connect `options`, see
+/// [`prelude::Database::connection_attributes`].
+pub(crate) fn connection_attributes<DB>(
+    options: &<DB::Connection as sqlx::Connection>::Options,
+) -> Attributes
+where
+    DB: prelude::Database + sqlx::Database,
+{
+    let mut attributes = DB::connection_attributes(options).build();
+    attributes.port = attributes.port.or_else(DB::default_port);
+    attributes
+}
+
+impl<DB> PoolBuilder<DB>
+where
+    DB: sqlx::Database,
+    PoolBuilder<DB>: From<sqlx::Pool<DB>>,
+{
+    /// Create a new builder from connect options, without opening any
+    /// connection until one is first needed.
+    ///
+    /// Attributes are derived from the connect options themselves, which keeps
+    /// details such as the database user. Must be called from within an async
+    /// runtime, as for [`sqlx::pool::PoolOptions::connect_lazy_with`].
+    pub fn from_options(
+        options: <DB::Connection as sqlx::Connection>::Options,
+        pool_options: sqlx::pool::PoolOptions<DB>,
+    ) -> Self {
+        Self::from(pool_options.connect_lazy_with(options))
     }
 }
 
 impl<DB: sqlx::Database> PoolBuilder<DB> {
+    /// Sets identifying attributes through `f`, so that they are set the
+    /// same way as on an [`AttributesBuilder`].
+    fn map_attributes(self, f: impl FnOnce(AttributesBuilder) -> AttributesBuilder) -> Self {
+        let attributes = f(AttributesBuilder {
+            attributes: self.attributes,
+        })
+        .build();
+        Self { attributes, ..self }
+    }
+
     /// Set a custom name for the pool (for peer.service attribute).
-    pub fn with_name(mut self, name: impl Into<String>) -> Self {
-        self.attributes.name = Some(name.into());
+    pub fn with_name(self, name: impl Into<String>) -> Self {
+        self.map_attributes(|attributes| attributes.with_name(name))
+    }
+
+    /// Replace the identifying attributes, the name, database, host,
+    /// candidate hosts, port, user, SSL mode, TLS, extensions and resource
+    /// attributes, along with the cardinality limit and statement cache
+    /// capacity, with those of `attributes`.
+    ///
+    /// The pool name and recording options set on this builder are kept, so
+    /// that pools sharing attributes can still be told apart.
+    pub fn with_attributes(mut self, attributes: Attributes) -> Self {
+        let Attributes {
+            name,
+            host,
+            hosts,
+            port,
+            database,
+            user,
+            ssl_mode,
+            tls,
+            extensions,
+            resource,
+            cardinality,
+            statement_cache,
+            ..
+        } = attributes;
+        self.attributes.name = name;
+        self.attributes.host = host;
+        self.attributes.hosts = hosts;
+        self.attributes.port = port;
+        self.attributes.database = database;
+        self.attributes.user = user;
+        self.attributes.ssl_mode = ssl_mode;
+        self.attributes.tls = tls;
+        self.attributes.extensions = extensions;
+        self.attributes.resource = resource;
+        self.attributes.cardinality = cardinality;
+        self.attributes.statement_cache = statement_cache;
         self
     }
 
-    /// Set the database name attribute.
-    pub fn with_database(mut self, database: impl Into<String>) -> Self {
-        self.attributes.database = Some(database.into());
+    /// Add OpenTelemetry resource-style attributes, see
+    /// [`AttributesBuilder::with_resource_attributes`].
+    pub fn with_resource_attributes<I, K, V>(self, attributes: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.map_attributes(|builder| builder.with_resource_attributes(attributes))
+    }
+
+    /// Set the pool name recorded as `db.client.pool.name` on every span.
+    ///
+    /// This tells apart the traffic of several pools of a service connected
+    /// to the same database. Defaults to a short id unique within the
+    /// process, such as `pool-1`.
+    pub fn with_pool_name(mut self, name: impl Into<String>) -> Self {
+        self.attributes.pool_name = Some(name.into());
         self
     }
 
-    /// Set the host attribute.
-    pub fn with_host(mut self, host: impl Into<String>) -> Self {
-        self.attributes.host = Some(host.into());
+    /// Set the address of the database behind a proxy such as PgBouncer or
+    /// RDS Proxy, recorded as `db.upstream.address`.
+    ///
+    /// The host of the connect options is then the proxy endpoint, also
+    /// recorded as `server.address`. As spans have a limited number of
+    /// fields, both are recorded like [extensions](AttributesBuilder::with_extension):
+    /// within the `sqlx.context` field and, with the `opentelemetry` feature,
+    /// as individual attributes.
+    ///
+    /// Not set by default.
+    pub fn with_upstream_address(mut self, address: impl Into<String>) -> Self {
+        self.attributes.upstream_address = Some(address.into());
         self
     }
 
-    /// Set the port attribute.
-    pub fn with_port(mut self, port: u16) -> Self {
-        self.attributes.port = Some(port);
+    /// Set the workload served by the pool, e.g. `interactive` or `batch`,
+    /// recorded as `db.workload` on every span.
+    ///
+    /// Like the [upstream address](Self::with_upstream_address), it is
+    /// recorded like [extensions](AttributesBuilder::with_extension). It is
+    /// set on the partitions created with [`Pool::partition`].
+    ///
+    /// Not set by default.
+    pub fn with_workload(mut self, workload: impl Into<String>) -> Self {
+        self.attributes.workload = Some(workload.into());
+        self
+    }
+
+    /// Override the `db.system.name` recorded on every span.
+    ///
+    /// This attributes traffic to the actual backend of wire-compatible
+    /// systems, such as `timescaledb`, `redshift` or `neon` behind the
+    /// Postgres driver. Defaults to the name provided by the database, such
+    /// as `postgresql`.
+    pub fn with_system_name(mut self, name: impl Into<String>) -> Self {
+        self.attributes.system_name = Some(name.into());
         self
     }
 
+    /// Set the database name attribute.
+    pub fn with_database(self, database: impl Into<String>) -> Self {
+        self.map_attributes(|attributes| attributes.with_database(database))
+    }
+
+    /// Set the host attribute.
+    pub fn with_host(self, host: impl Into<String>) -> Self {
+        self.map_attributes(|attributes| attributes.with_host(host))
+    }
+
+    /// Set the port attribute.
+    pub fn with_port(self, port: u16) -> Self {
+        self.map_attributes(|attributes| attributes.with_port(port))
+    }
+
+    /// Set the candidate hosts of a multi-host or failover setup, see
+    /// [`AttributesBuilder::with_hosts`].
+    pub fn with_hosts<I>(self, hosts: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.map_attributes(|attributes| attributes.with_hosts(hosts))
+    }
+
+    /// Set the database user attribute.
+    pub fn with_user(self, user: impl Into<String>) -> Self {
+        self.map_attributes(|attributes| attributes.with_user(user))
+    }
+
     /// Enable or disable recording of SQL query text in spans.
     ///
     /// When disabled, the `db.query.text` span field will be empty.
@@ -121,7 +615,79 @@ impl<DB: sqlx::Database> PoolBuilder<DB> {
     ///
     /// Enabled by default.
     pub fn with_query_text_recording(mut self, enabled: bool) -> Self {
-        self.attributes.record_query_text = enabled;
+        self.config.record_query_text = enabled;
+        self
+    }
+
+    /// Enable or disable recording of the `key:value` tags of leading SQL
+    /// comments, such as `/* application:billing,controller:invoices */`.
+    ///
+    /// This follows the marginalia convention, so queries already tagged for
+    /// database logs carry the same tags in traces. Tags are recorded like
+    /// [extensions](AttributesBuilder::with_extension), within the
+    /// `sqlx.context` field and, with the `opentelemetry` feature, as
+    /// individual attributes.
+    ///
+    /// Enabled by default.
+    pub fn with_comment_tag_recording(mut self, enabled: bool) -> Self {
+        self.config.record_comment_tags = enabled;
+        self
+    }
+
+    /// Record query text only for statements whose operation, the first
+    /// keyword of the statement, is one of `operations`.
+    ///
+    /// This is a middle ground between recording every statement and none,
+    /// e.g. recording `SELECT` and DDL statements but never `INSERT` or
+    /// `UPDATE` statements, which tend to carry data values. Operations are
+    /// matched case-insensitively. Has no effect when query text recording
+    /// is disabled.
+    ///
+    /// All operations are recorded by default.
+    pub fn with_query_text_operations<I, S>(mut self, operations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.query_text_operations = Some(operations.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Enable or disable recording of the schema targeted by each statement
+    /// as the `db.namespace` field.
+    ///
+    /// The schema is parsed from the qualified name of the first table the
+    /// statement references, such as `aux` in `SELECT * FROM aux.users`.
+    /// This tells apart statements on SQLite databases added with `ATTACH`;
+    /// statements on unqualified tables leave the field empty.
+    ///
+    /// Disabled by default.
+    pub fn with_schema_recording(mut self, enabled: bool) -> Self {
+        self.config.record_schema = enabled;
+        self
+    }
+
+    /// Enable or disable recording of database-specific result metadata on
+    /// `sqlx.execute` spans, such as `db.sqlite.last_insert_rowid` for SQLite.
+    ///
+    /// Disabled by default.
+    pub fn with_result_metadata_recording(mut self, enabled: bool) -> Self {
+        self.config.record_result_metadata = enabled;
+        self
+    }
+
+    /// Enable or disable recording the approximate size of statements and of
+    /// the rows they return, as `db.request.bytes` and `db.response.bytes`.
+    ///
+    /// Drivers don't expose the bytes sent and received, so the request size
+    /// is the length of the statement text, without its bind parameters, and
+    /// the response size adds up the raw size of the column values of the
+    /// returned rows. This is enough to spot statements fetching far more
+    /// data than needed, e.g. with `SELECT *`.
+    ///
+    /// Disabled by default.
+    pub fn with_payload_size_recording(mut self, enabled: bool) -> Self {
+        self.config.record_payload_size = enabled;
         self
     }
 
@@ -133,17 +699,379 @@ impl<DB: sqlx::Database> PoolBuilder<DB> {
     /// contain sensitive information such as connection strings or internal
     /// database state.
     ///
+    /// Spans whose details were omitted record `error.details_redacted =
+    /// true`, so trace consumers can tell them apart from details that were
+    /// lost.
+    ///
     /// Enabled by default.
     pub fn with_error_detail_recording(mut self, enabled: bool) -> Self {
-        self.attributes.record_error_details = enabled;
+        self.config.record_error_details = enabled;
+        self
+    }
+
+    /// Enable or disable recording the hash of the error message as
+    /// `error.message_hash` when the error details are omitted, either
+    /// because [`with_error_detail_recording`](Self::with_error_detail_recording)
+    /// disabled them or the rate limit was reached.
+    ///
+    /// The hash is the 64-bit FNV-1a hash of the message, which lets
+    /// identical errors be grouped without exposing their message.
+    ///
+    /// Disabled by default.
+    pub fn with_error_message_hash(mut self, enabled: bool) -> Self {
+        self.config.record_error_message_hash = enabled;
+        self
+    }
+
+    /// Record the details of identical errors, i.e. with the same message, at
+    /// most `max` times per `period`.
+    ///
+    /// Beyond the limit, spans only record the error type, status and
+    /// database error code. This protects tracing pipelines during error
+    /// storms, such as every query failing with the same connection error.
+    /// Has no effect when error detail recording is disabled.
+    ///
+    /// Not limited by default.
+    pub fn with_error_detail_rate_limit(mut self, max: u32, period: std::time::Duration) -> Self {
+        self.config.error_throttle = Some(Arc::new(throttle::ErrorThrottle::new(max, period)));
+        self
+    }
+
+    /// Set the OpenTelemetry status of spans whose operation failed, per
+    /// error.
+    ///
+    /// Errors mapped to [`ErrorStatus::Ok`] are expected outcomes, such as
+    /// unique violations when inserting rows that may already exist: their
+    /// span records `otel.status_code = "ok"` without `error.type` or
+    /// `error.message`, and a `sqlx.error.expected` event records the error
+    /// instead. The violated constraint and database status code are still
+    /// recorded on the span, but with the `metrics` feature, the error is not
+    /// counted in `db.client.operation.errors`. The operation returns the
+    /// error either way.
+    ///
+    /// Every error marks its span as failed by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let traced_pool = sqlx_tracing::PoolBuilder::from(pool)
+    ///     .with_error_status(|err| match err.as_database_error() {
+    ///         Some(db_err) if db_err.is_unique_violation() => sqlx_tracing::ErrorStatus::Ok,
+    ///         _ => sqlx_tracing::ErrorStatus::Error,
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn with_error_status<F>(mut self, status: F) -> Self
+    where
+        F: Fn(&sqlx::Error) -> ErrorStatus + Send + Sync + 'static,
+    {
+        self.config.error_status = Some(status::ErrorStatusMapper::new(status));
+        self
+    }
+
+    /// Limit the distinct values of label-like span fields, see
+    /// [`AttributesBuilder::with_cardinality_limit`].
+    pub fn with_cardinality_limit(self, max_values: usize, max_length: usize) -> Self {
+        self.map_attributes(|attributes| attributes.with_cardinality_limit(max_values, max_length))
+    }
+
+    /// Enable or disable recording of the connection string as the
+    /// `db.connection_string` field on lifecycle spans.
+    ///
+    /// The username and password are stripped from the recorded URL, but
+    /// other connection parameters are kept, which helps to tell which
+    /// endpoint a service actually talked to.
+    ///
+    /// Disabled by default.
+    pub fn with_connection_string_recording(mut self, enabled: bool) -> Self {
+        self.attributes.connection_string =
+            self.connection_string.as_ref().filter(|_| enabled).cloned();
+        self
+    }
+
+    /// Emit a warning event when a transaction stays idle between two
+    /// statements for longer than `threshold`.
+    ///
+    /// The total idle time of a transaction is always recorded as
+    /// `db.transaction.idle_ms` on its commit and rollback spans.
+    ///
+    /// Disabled by default.
+    pub fn with_transaction_idle_threshold(mut self, threshold: std::time::Duration) -> Self {
+        self.config.transaction_idle_threshold = Some(threshold);
+        self
+    }
+
+    /// Bound the statements of every transaction on the server side, with a
+    /// `SET LOCAL statement_timeout` issued right after `BEGIN`.
+    ///
+    /// The timeout is the time left before the [`with_deadline`] deadline,
+    /// or a fixed budget shortened to it, see [`StatementTimeout`], and is
+    /// recorded as `db.transaction.statement_timeout_ms` on the
+    /// `sqlx.transaction` span. This way the database gives up on a statement
+    /// when the traced deadline passes, instead of running it to completion
+    /// for a caller that is gone.
+    ///
+    /// Only supported by Postgres, ignored by other databases. Disabled by
+    /// default.
+    pub fn with_statement_timeout(mut self, timeout: StatementTimeout) -> Self {
+        self.config.statement_timeout = Some(timeout);
+        self
+    }
+
+    /// Enable or disable grouping identical consecutive statements of a
+    /// connection into a single span.
+    ///
+    /// A statement with the same SQL as the one right before it, on the same
+    /// connection and transaction, reuses the span of the first statement of
+    /// the run instead of opening its own. The span records the number of
+    /// statements of the run as `db.statement.repetitions` once the run ends,
+    /// i.e. when a different statement runs or the connection or transaction
+    /// handle is dropped. This keeps N+1 query loops from flooding traces,
+    /// while still making them obvious.
+    ///
+    /// Every statement of the run records its results on the shared span, so
+    /// attributes like `db.response.returned_rows` are those of the last one
+    /// rather than totals.
+    ///
+    /// Disabled by default.
+    pub fn with_statement_grouping(mut self, enabled: bool) -> Self {
+        self.config.statement_groups = enabled.then(Default::default);
+        self
+    }
+
+    /// Emit a `sqlx.pool.acquire_slow` warning event when [`Pool::acquire`]
+    /// waits longer than `threshold` for a connection.
+    ///
+    /// The event carries the wait (`db.client.connection.wait_ms`), the
+    /// pool's size (`pool.size`), idle connections (`pool.idle`), maximum
+    /// size (`pool.max_connections`) and the other acquisitions still
+    /// waiting (`pool.pending_acquires`), which is usually enough to
+    /// diagnose pool starvation from a single log line.
+    ///
+    /// Disabled by default.
+    pub fn with_acquire_slow_threshold(mut self, threshold: std::time::Duration) -> Self {
+        self.config.acquire_slow_threshold = Some(threshold);
+        self
+    }
+
+    /// Emit a `sqlx.stream.progress` event every `rows` rows returned by
+    /// `fetch` and `fetch_many` streams. The query results of `fetch_many`
+    /// are not counted as rows.
+    ///
+    /// Each event carries the running count as `db.response.returned_rows`,
+    /// which makes the progress of long exports visible before the stream
+    /// ends. An interval of `0` disables the events.
+    ///
+    /// Disabled by default.
+    pub fn with_stream_progress_interval(mut self, rows: u64) -> Self {
+        self.config.stream_progress_interval = std::num::NonZeroU64::new(rows);
+        self
+    }
+
+    /// Rewrite the SQL of every statement before it is sent to the database.
+    ///
+    /// This is the place to add sqlcommenter-style comments, optimizer hints
+    /// or tenant schema prefixes. The rewriter applies to statements executed
+    /// through the pool, its connections and transactions, and spans record
+    /// the rewritten SQL. Returning [`Cow::Borrowed`](std::borrow::Cow) leaves
+    /// a statement unchanged.
+    ///
+    /// Prepared statements, as well as `prepare` and `describe` calls, are
+    /// not rewritten, since the prepared statement borrows its SQL. Neither
+    /// are statements with arguments on databases other than PostgreSQL,
+    /// MySQL and SQLite.
+    ///
+    /// No rewriting by default.
+    pub fn with_query_rewriter<F>(mut self, rewriter: F) -> Self
+    where
+        F: Fn(&str) -> std::borrow::Cow<'_, str> + Send + Sync + 'static,
+    {
+        self.config.query_rewriter = Some(rewrite::QueryRewriter::new(rewriter));
+        self
+    }
+
+    /// Set how statements whose SQL is empty or only whitespace, after the
+    /// [query rewriter](Self::with_query_rewriter) ran, are traced.
+    ///
+    /// They are usually caused by templating bugs; a `sqlx.statement.empty`
+    /// warning event makes them visible, and [`EmptyStatements::Skip`] keeps
+    /// their spans out of traces.
+    ///
+    /// [`EmptyStatements::Flag`] by default.
+    pub fn with_empty_statements(mut self, behavior: EmptyStatements) -> Self {
+        self.config.empty_statements = behavior;
         self
     }
 
+    /// Call `callback` with a [`SpanRecord`] whenever a statement completes.
+    ///
+    /// The record summarizes the statement span (operation, fingerprint,
+    /// duration, rows and error code) so database telemetry can be shipped
+    /// to custom sinks, such as Kafka or ClickHouse, without parsing tracing
+    /// output. With the `serde` feature, [`SpanRecord`] is serializable.
+    ///
+    /// Row streams are reported when they end, are dropped or fail. The
+    /// callback runs on the task executing the statement, so it should hand
+    /// records off rather than block.
+    ///
+    /// No callback by default.
+    pub fn on_span_complete<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&SpanRecord) + Send + Sync + 'static,
+    {
+        self.config.span_exporter = Some(export::SpanExporter::new(callback));
+        self
+    }
+
+    /// Run `query` in [`PoolConnection::ping`] instead of the driver's native
+    /// ping.
+    ///
+    /// A statement like `SELECT 1` also checks that the server executes
+    /// queries, at the cost of a round trip through the query engine.
+    ///
+    /// The driver's native ping by default.
+    pub fn with_ping_query(mut self, query: impl Into<String>) -> Self {
+        self.config.ping_query = Some(query.into());
+        self
+    }
+
+    /// Set whether [`PoolConnection::ping`] creates a span, emits an event or
+    /// is not traced at all.
+    ///
+    /// Frequent liveness checks can drown the interesting spans of a trace;
+    /// [`PingTracing::Event`] keeps their failures visible at a lower cost.
+    ///
+    /// [`PingTracing::Span`] by default.
+    pub fn with_ping_tracing(mut self, tracing: PingTracing) -> Self {
+        self.config.ping_tracing = tracing;
+        self
+    }
+
+    /// Enable or disable `sqlx.connection.lease` spans covering the lifetime
+    /// of each connection acquired from the pool.
+    ///
+    /// The lease span starts with the `sqlx.pool.acquire` span and ends when
+    /// the [`PoolConnection`] is dropped. It parents the acquisition and every
+    /// operation executed on the connection, so traces show how long a
+    /// connection was held and what ran on it.
+    ///
+    /// Disabled by default.
+    pub fn with_connection_lease_spans(mut self, enabled: bool) -> Self {
+        self.config.connection_lease_spans = enabled;
+        self
+    }
+
+    /// Enable or disable recording of the W3C Baggage entries of the current
+    /// OpenTelemetry context as attributes of database spans.
+    ///
+    /// Identifiers propagated through baggage, such as a tenant or request
+    /// id, then land on database telemetry without any further code. Use
+    /// [`PoolBuilder::with_baggage_keys`] to restrict the recorded entries.
+    ///
+    /// Disabled by default.
+    #[cfg(feature = "opentelemetry")]
+    pub fn with_baggage_recording(mut self, enabled: bool) -> Self {
+        self.config.record_baggage = enabled;
+        self
+    }
+
+    /// Record only the baggage entries whose key is one of `keys`.
+    ///
+    /// Baggage may carry values that should not reach the observability
+    /// backend, so an allowlist is recommended when recording it. Has no
+    /// effect when baggage recording is disabled.
+    ///
+    /// All entries are recorded by default.
+    #[cfg(feature = "opentelemetry")]
+    pub fn with_baggage_keys<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.baggage_keys = Some(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Enable or disable SQLx's built-in statement logging.
+    ///
+    /// SQLx logs every executed statement through the `log` crate, which
+    /// duplicates the tracing spans emitted by this crate. When disabled,
+    /// statement and slow statement logging are turned off; when enabled,
+    /// SQLx's default logging levels are restored.
+    ///
+    /// The setting only applies to new connections, so the idle connections
+    /// of the pool are closed when it is built, and opened again when needed.
+    /// Connections checked out at that time keep logging as before until
+    /// they are closed. Pools created through [`PoolOptions`] have statement
+    /// logging disabled from the start.
+    ///
+    /// Left as configured on the SQLx pool by default.
+    pub fn with_sqlx_logging(mut self, enabled: bool) -> Self {
+        self.sqlx_logging = Some(enabled);
+        self
+    }
+
+    /// Build the [`Pool`] after checking the configuration, returning a
+    /// [`BuildError`] describing the first invalid or conflicting option,
+    /// such as an empty name or query text operations set while query text
+    /// recording is disabled.
+    pub fn try_build(self) -> Result<Pool<DB>, BuildError>
+    where
+        DB: prelude::Database,
+    {
+        validate::validate(&self.attributes, &self.config)?;
+        Ok(self.build())
+    }
+
     /// Build the [`Pool`] with the configured attributes.
-    pub fn build(self) -> Pool<DB> {
+    ///
+    /// The configuration is not checked, see [`PoolBuilder::try_build`].
+    pub fn build(self) -> Pool<DB>
+    where
+        DB: prelude::Database,
+    {
+        if let Some(enabled) = self.sqlx_logging {
+            use sqlx::ConnectOptions;
+
+            let options = (*self.pool.connect_options()).clone();
+            let options = if enabled {
+                options
+                    .log_statements(log::LevelFilter::Debug)
+                    .log_slow_statements(log::LevelFilter::Warn, std::time::Duration::from_secs(1))
+            } else {
+                options.disable_statement_logging()
+            };
+            self.pool.set_connect_options(options);
+            // Idle connections were opened with the previous settings.
+            let idle = std::iter::from_fn(|| self.pool.try_acquire()).collect::<Vec<_>>();
+            for mut conn in idle {
+                conn.close_on_drop();
+            }
+        }
+        let pool_name = self.attributes.pool_name.unwrap_or_else(pool::next_name);
+        #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+        let metrics = Arc::new(metrics::PoolMetrics::new(&pool_name));
+        // Shared with the connections and transactions, to count their errors.
+        let config = config::Config {
+            #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+            metrics: Some(metrics.clone()),
+            ..self.config
+        };
+        let attributes = Attributes {
+            pool_name: Some(pool_name),
+            config: Some(Arc::new(config)),
+            ..self.attributes
+        };
+        let writer_queue = queue::WriterQueue::new(&self.pool);
+        let attributes = Arc::new(attributes);
         Pool {
-            inner: self.pool,
-            attributes: Arc::new(self.attributes),
+            inner: Arc::new(pool::SharedPool::new(self.pool, attributes.clone())),
+            attributes,
+            pending_acquires: Default::default(),
+            acquire_outcomes: Default::default(),
+            writer_queue,
+            #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+            metrics,
         }
     }
 }
@@ -151,18 +1079,96 @@ impl<DB: sqlx::Database> PoolBuilder<DB> {
 /// An asynchronous pool of SQLx database connections with tracing instrumentation.
 ///
 /// Wraps a SQLx [`Pool`] and propagates tracing attributes to all acquired connections.
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct Pool<DB>
 where
     DB: sqlx::Database,
 {
-    inner: sqlx::Pool<DB>,
+    // Shared by the clones of the pool, so that `WeakPool` handles don't
+    // keep it open.
+    inner: Arc<pool::SharedPool<DB>>,
     attributes: Arc<Attributes>,
+    pending_acquires: Arc<std::sync::atomic::AtomicUsize>,
+    acquire_outcomes: Arc<pool::AcquireCounters>,
+    writer_queue: Option<queue::WriterQueue>,
+    #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+    metrics: Arc<metrics::PoolMetrics>,
 }
 
-impl<DB> From<sqlx::Pool<DB>> for Pool<DB>
+// Implemented manually, as deriving would require `DB: Clone`.
+impl<DB: sqlx::Database> Clone for Pool<DB> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            attributes: self.attributes.clone(),
+            pending_acquires: self.pending_acquires.clone(),
+            acquire_outcomes: self.acquire_outcomes.clone(),
+            writer_queue: self.writer_queue.clone(),
+            #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+/// A weak handle to a [`Pool`], returned by [`Pool::downgrade`].
+///
+/// It does not keep the pool open: once every [`Pool`] handle is dropped, the
+/// underlying SQLx pool is too, closing its connections, and
+/// [`WeakPool::upgrade`] returns `None`. This lets background tasks refer to
+/// a pool without extending its lifetime.
+#[derive(Debug)]
+pub struct WeakPool<DB>
 where
     DB: sqlx::Database,
+{
+    inner: std::sync::Weak<pool::SharedPool<DB>>,
+    attributes: Arc<Attributes>,
+    pending_acquires: Arc<std::sync::atomic::AtomicUsize>,
+    acquire_outcomes: Arc<pool::AcquireCounters>,
+    writer_queue: Option<queue::WriterQueue>,
+    #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+    metrics: Arc<metrics::PoolMetrics>,
+}
+
+// Implemented manually, as deriving would require `DB: Clone`.
+impl<DB: sqlx::Database> Clone for WeakPool<DB> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            attributes: self.attributes.clone(),
+            pending_acquires: self.pending_acquires.clone(),
+            acquire_outcomes: self.acquire_outcomes.clone(),
+            writer_queue: self.writer_queue.clone(),
+            #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+impl<DB: sqlx::Database> WeakPool<DB> {
+    /// Returns a [`Pool`] handle if the pool is still alive, with the same
+    /// attributes, counters and metrics as the pool it was downgraded from.
+    pub fn upgrade(&self) -> Option<Pool<DB>> {
+        Some(Pool {
+            inner: self.inner.upgrade()?,
+            attributes: self.attributes.clone(),
+            pending_acquires: self.pending_acquires.clone(),
+            acquire_outcomes: self.acquire_outcomes.clone(),
+            writer_queue: self.writer_queue.clone(),
+            #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+            metrics: self.metrics.clone(),
+        })
+    }
+
+    /// Returns the attributes recorded on the spans of the pool.
+    pub fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+}
+
+impl<DB> From<sqlx::Pool<DB>> for Pool<DB>
+where
+    DB: prelude::Database + sqlx::Database,
     PoolBuilder<DB>: From<sqlx::Pool<DB>>,
 {
     /// Convert a SQLx [`Pool`] into a tracing-instrumented [`Pool`].
@@ -171,6 +1177,26 @@ where
     }
 }
 
+impl<DB> Pool<DB>
+where
+    DB: sqlx::Database,
+    PoolBuilder<DB>: From<sqlx::Pool<DB>>,
+{
+    /// Create a tracing-instrumented [`Pool`] from connect options, without
+    /// opening any connection until one is first needed.
+    ///
+    /// See [`PoolBuilder::from_options`].
+    pub fn from_options(
+        options: <DB::Connection as sqlx::Connection>::Options,
+        pool_options: sqlx::pool::PoolOptions<DB>,
+    ) -> Self
+    where
+        DB: prelude::Database,
+    {
+        PoolBuilder::from_options(options, pool_options).build()
+    }
+}
+
 impl<DB> AsRef<sqlx::Pool<DB>> for Pool<DB>
 where
     DB: sqlx::Database,
@@ -187,8 +1213,8 @@ where
     /// Returns a reference to the underlying [`sqlx::Pool`].
     ///
     /// This allows bypassing the tracing instrumentation for operations
-    /// not yet supported by this crate (e.g. `COPY`, `LISTEN/NOTIFY`,
-    /// or other database-specific features).
+    /// not yet supported by this crate (e.g. `COPY TO`, or other
+    /// database-specific features).
     ///
     /// # Example
     ///
@@ -202,11 +1228,45 @@ where
         &self.inner
     }
 
+    /// Returns a [`WeakPool`] handle, which does not keep the pool open.
+    ///
+    /// The pool is dropped, closing its connections, along with its last
+    /// [`Pool`] handle, unless the SQLx pool returned by
+    /// [`inner`](Self::inner) was cloned. Dropping it without calling
+    /// [`close`](Self::close) while connections are open emits a
+    /// `sqlx.pool.dropped` warning event.
+    pub fn downgrade(&self) -> WeakPool<DB> {
+        WeakPool {
+            inner: Arc::downgrade(&self.inner),
+            attributes: self.attributes.clone(),
+            pending_acquires: self.pending_acquires.clone(),
+            acquire_outcomes: self.acquire_outcomes.clone(),
+            writer_queue: self.writer_queue.clone(),
+            #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+            metrics: self.metrics.clone(),
+        }
+    }
+
     /// Returns the number of connections currently active (including idle).
     pub fn size(&self) -> u32 {
         self.inner.size()
     }
 
+    /// Returns the attributes recorded on the spans of this pool.
+    pub fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    /// Returns the number of [`Pool::acquire`], [`Pool::try_acquire`] and
+    /// [`Pool::try_begin`] calls of this pool per outcome since it was built,
+    /// shared by its clones.
+    ///
+    /// Connections acquired internally by the pool's executor methods are
+    /// not counted.
+    pub fn acquire_outcomes(&self) -> AcquireOutcomes {
+        self.acquire_outcomes.snapshot()
+    }
+
     /// Returns the number of idle connections (not currently in use).
     pub fn num_idle(&self) -> usize {
         self.inner.num_idle()
@@ -216,47 +1276,191 @@ where
     pub fn is_closed(&self) -> bool {
         self.inner.is_closed()
     }
+
+    /// Returns the tokio task monitor of this pool's statement futures.
+    ///
+    /// The futures of `execute`, `fetch_all`, `fetch_one`, `fetch_optional`
+    /// and `prepare` calls are instrumented, so their poll counts and
+    /// scheduling delays tell a slow database from a starved runtime. Row
+    /// streams are not instrumented.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let metrics = pool.task_monitor().cumulative();
+    /// println!("mean scheduling delay: {:?}", metrics.mean_scheduled_duration());
+    /// ```
+    #[cfg(feature = "tokio-metrics")]
+    pub fn task_monitor(&self) -> &tokio_metrics::TaskMonitor {
+        &self.attributes.config().task_monitor
+    }
 }
 
 impl<DB> Pool<DB>
 where
     DB: sqlx::Database + crate::prelude::Database,
 {
+    /// Returns a builder for a partition of this pool, a separate pool
+    /// serving `workload` with at most `max_connections` connections.
+    ///
+    /// Splitting the traffic of a database into partitions, e.g.
+    /// `interactive` and `batch`, keeps batch jobs from starving interactive
+    /// requests of connections. The partition shares the connect options and
+    /// pool options of this pool, including the hooks installed by
+    /// [`PoolOptions`], and its attributes and recording options. Its spans
+    /// record `workload` as `db.workload`, and its pool name is this pool's
+    /// name followed by `.` and `workload`, e.g. `pool-1.batch`, which keeps
+    /// the metrics of the partitions apart.
+    ///
+    /// The partition opens no connection until one is first needed, and must
+    /// be created from within an async runtime, as for
+    /// [`sqlx::pool::PoolOptions::connect_lazy_with`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let interactive = pool.partition("interactive", 16).build();
+    /// let batch = pool.partition("batch", 4).build();
+    /// ```
+    pub fn partition(&self, workload: impl Into<String>, max_connections: u32) -> PoolBuilder<DB> {
+        let workload = workload.into();
+        let options = self.inner.options().clone();
+        let min_connections = options.get_min_connections().min(max_connections);
+        let connect_options = self.inner.connect_options();
+        let pool = options
+            .max_connections(max_connections)
+            .min_connections(min_connections)
+            .connect_lazy_with((*connect_options).clone());
+        let attributes = Attributes {
+            pool_name: Some(format!("{}.{workload}", self.attributes.pool_name())),
+            workload: Some(workload),
+            config: None,
+            ..(*self.attributes).clone()
+        };
+        PoolBuilder {
+            pool,
+            attributes,
+            config: self.attributes.config().clone(),
+            sqlx_logging: None,
+            connection_string: DB::connection_string(&connect_options),
+        }
+    }
+
     /// Retrieves a connection and immediately begins a new transaction.
     ///
     /// The returned [`Transaction`] is instrumented for tracing.
     pub async fn begin<'c>(&'c self) -> Result<Transaction<'c, DB>, sqlx::Error> {
         let attrs = &self.attributes;
-        let record_details = attrs.record_error_details;
-        let span = crate::instrument_op!("sqlx.transaction.begin", attrs);
-        async {
+        let record_details = attrs.config().error_details();
+        let pending = pool::PendingAcquire::new(&self.pending_acquires);
+        let slot = self.writer_queue.as_ref().map(queue::WriterQueue::join);
+        let position = slot.as_ref().map(queue::QueueSlot::position);
+        let span = queue::in_scope(position, || {
+            crate::instrument_op!(
+                crate::Operation::Begin,
+                attrs,
+                "pool.pending_acquires" = pending.waiting(),
+            )
+        });
+        let inner = async {
             self.inner
                 .begin()
                 .await
-                .map(|inner| Transaction {
-                    inner,
-                    attributes: self.attributes.clone(),
-                })
-                .inspect_err(|e| crate::span::record_error(e, record_details))
+                .inspect_err(|e| crate::span::record_error(e, &record_details))
         }
         .instrument(span)
+        .await?;
+        queue::in_scope(position, || {
+            Transaction::new(inner, self.attributes.clone(), connection::next_id(), slot)
+        })
+        .apply_statement_timeout()
         .await
     }
 
     /// Acquires a pooled connection, instrumented for tracing.
+    ///
+    /// When the pool was created through [`PoolOptions`], the span also records
+    /// whether the connection was newly established or reused
+    /// (`db.client.connection.state`) and how long it sat idle
+    /// (`db.client.connection.idle_ms`).
+    ///
+    /// The number of other tasks already waiting for a connection of this
+    /// pool when the acquisition began is recorded as `pool.pending_acquires`,
+    /// and whether it was handed out immediately, as an idle connection was
+    /// available, after waiting, or failed because of a timeout or a closed
+    /// pool as
+    /// `db.client.connection.acquire_outcome`. The outcomes are also counted,
+    /// see [`Pool::acquire_outcomes`]. Failures record their
+    /// [`AcquireErrorReason`] as `pool.acquire.error_reason`.
+    ///
+    /// When waiting for the connection takes longer than the threshold set
+    /// with [`PoolBuilder::with_acquire_slow_threshold`], a
+    /// `sqlx.pool.acquire_slow` event reports the pool's state.
+    ///
+    /// With the `metrics` feature, the time spent waiting is also recorded in
+    /// the `db.client.connection.wait_time` OpenTelemetry histogram, in
+    /// seconds, of the meter provider installed globally when the pool was
+    /// built.
     pub async fn acquire(&self) -> Result<PoolConnection<DB>, sqlx::Error> {
         let attrs = &self.attributes;
-        let record_details = attrs.record_error_details;
-        let span = crate::instrument_op!("sqlx.pool.acquire", attrs);
+        let record_details = attrs.config().error_details();
+        let pending = pool::PendingAcquire::new(&self.pending_acquires);
+        // Connections wait for the writer, but only their writes join the queue.
+        let (position, _) = queue::enter(self.writer_queue.as_ref(), false);
+        let lease = queue::in_scope(position, || connection::lease_span::<DB>(attrs));
+        let span = lease.in_scope(|| {
+            queue::in_scope(position, || {
+                crate::instrument_op!(
+                    crate::Operation::Acquire,
+                    attrs,
+                    // Other tasks waiting for a connection when the acquisition began
+                    "pool.pending_acquires" = pending.waiting(),
+                    // Connection state and idle time (filled by `PoolOptions` hooks)
+                    "db.client.connection.state" = ::tracing::field::Empty,
+                    "db.client.connection.idle_ms" = ::tracing::field::Empty,
+                    // Server reached by a new connection (filled by `PoolOptions` hooks)
+                    "db.client.connection.server_address" = ::tracing::field::Empty,
+                    // Capacity of the statement cache of each connection (if known)
+                    "db.client.statement_cache.capacity" = attrs.statement_cache_capacity(),
+                    // Settings of a new SQLite connection (filled by `PoolOptions` hooks)
+                    "db.sqlite.journal_mode" = ::tracing::field::Empty,
+                    "db.sqlite.synchronous" = ::tracing::field::Empty,
+                    "db.sqlite.cache_size" = ::tracing::field::Empty,
+                    // Id of the traced connection handle
+                    "db.client.connection.id" = ::tracing::field::Empty,
+                    // Time left before the `with_deadline` deadline
+                    "db.query.deadline_ms" = ::tracing::field::Empty,
+                    // Immediate, waited, timed out, pool closed or failed
+                    "db.client.connection.acquire_outcome" = ::tracing::field::Empty,
+                    // Why the acquisition failed
+                    "pool.acquire.error_reason" = ::tracing::field::Empty,
+                )
+            })
+        });
+        let deadline = span.in_scope(deadline::start);
+        let waited = self.inner.num_idle() == 0;
+        let start = std::time::Instant::now();
         async {
-            self.inner
-                .acquire()
-                .await
-                .map(|inner| PoolConnection {
-                    attributes: self.attributes.clone(),
-                    inner,
+            let res = deadline::bound(deadline, self.inner.acquire()).await;
+            let wait = start.elapsed();
+            #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+            self.metrics.record_wait(wait);
+            pool::report_slow_acquire(
+                &self.inner,
+                attrs.config().acquire_slow_threshold,
+                wait,
+                &self.pending_acquires,
+            );
+            let outcome = self.acquire_outcomes.record(&res, waited);
+            tracing::Span::current().record("db.client.connection.acquire_outcome", outcome);
+            res.map(|inner| PoolConnection::new(inner, self.attributes.clone(), lease))
+                .inspect_err(|e| {
+                    tracing::Span::current().record(
+                        "pool.acquire.error_reason",
+                        AcquireErrorReason::of(e).as_str(),
+                    );
+                    crate::span::record_error(e, &record_details)
                 })
-                .inspect_err(|e| crate::span::record_error(e, record_details))
         }
         .instrument(span)
         .await
@@ -266,14 +1470,76 @@ where
     ///
     /// Returns `None` immediately if no idle connections are available
     /// and the pool is at its connection limit.
+    ///
+    /// The outcome (`acquired` or `unavailable`) is recorded as
+    /// `db.client.connection.acquire_outcome` on the `sqlx.pool.acquire` span,
+    /// and counted, see [`Pool::acquire_outcomes`].
     pub fn try_acquire(&self) -> Option<PoolConnection<DB>> {
         let attrs = &self.attributes;
-        let span = crate::instrument_op!("sqlx.pool.acquire", attrs);
+        let lease = connection::lease_span::<DB>(attrs);
+        let span = lease.in_scope(|| {
+            crate::instrument_op!(
+                crate::Operation::Acquire,
+                attrs,
+                "db.client.connection.acquire_outcome" = ::tracing::field::Empty,
+                "db.client.connection.id" = ::tracing::field::Empty,
+            )
+        });
         let _enter = span.enter();
-        self.inner.try_acquire().map(|inner| PoolConnection {
-            attributes: self.attributes.clone(),
-            inner,
+        let conn = self.inner.try_acquire();
+        span.record(
+            "db.client.connection.acquire_outcome",
+            self.acquire_outcomes.record_try(conn.is_some()),
+        );
+        conn.map(|inner| PoolConnection::new(inner, self.attributes.clone(), lease))
+    }
+
+    /// Attempts to acquire a connection without waiting and, if successful,
+    /// immediately begins a new transaction.
+    ///
+    /// Returns `Ok(None)` immediately if no connection is available, which is
+    /// useful for best-effort background writes. The `sqlx.transaction.begin`
+    /// span records `pool.pending_acquires` like [`Pool::begin`], and the
+    /// outcome (`acquired` or `unavailable`) as
+    /// `db.client.connection.acquire_outcome`, counted like
+    /// [`Pool::try_acquire`].
+    pub async fn try_begin<'c>(&'c self) -> Result<Option<Transaction<'c, DB>>, sqlx::Error> {
+        let attrs = &self.attributes;
+        let record_details = attrs.config().error_details();
+        let pending = pool::PendingAcquire::new(&self.pending_acquires);
+        let slot = self.writer_queue.as_ref().map(queue::WriterQueue::join);
+        let position = slot.as_ref().map(queue::QueueSlot::position);
+        let span = queue::in_scope(position, || {
+            crate::instrument_op!(
+                crate::Operation::Begin,
+                attrs,
+                "pool.pending_acquires" = pending.waiting(),
+                "db.client.connection.acquire_outcome" = ::tracing::field::Empty,
+            )
+        });
+        let tx = async {
+            self.inner
+                .try_begin()
+                .await
+                .inspect(|tx| {
+                    tracing::Span::current().record(
+                        "db.client.connection.acquire_outcome",
+                        self.acquire_outcomes.record_try(tx.is_some()),
+                    );
+                })
+                .inspect_err(|e| crate::span::record_error(e, &record_details))
+        }
+        .instrument(span)
+        .await?;
+        let Some(inner) = tx else {
+            return Ok(None);
+        };
+        queue::in_scope(position, || {
+            Transaction::new(inner, self.attributes.clone(), connection::next_id(), slot)
         })
+        .apply_statement_timeout()
+        .await
+        .map(Some)
     }
 
     /// Ends the use of a connection pool.
@@ -283,9 +1549,73 @@ where
     /// connections are closed.
     pub async fn close(&self) {
         let attrs = &self.attributes;
-        let span = crate::instrument_op!("sqlx.pool.close", attrs);
+        let span = crate::instrument_op!(crate::Operation::ClosePool, attrs);
         async { self.inner.close().await }.instrument(span).await
     }
+
+    /// Ends the use of a connection pool, giving up after `timeout`.
+    ///
+    /// Behaves like [`Pool::close`], but stops waiting for outstanding
+    /// connections once the timeout elapses so shutdown hooks don't hang on a
+    /// wedged connection. The pool stays closed either way.
+    ///
+    /// Returns `true` if all connections were closed in time. Otherwise the
+    /// number of connections still outstanding is recorded as
+    /// `db.client.connection.outstanding` on the `sqlx.pool.close` span.
+    pub async fn close_with_timeout(&self, timeout: std::time::Duration) -> bool {
+        let attrs = &self.attributes;
+        let span = crate::instrument_op!(
+            crate::Operation::ClosePool,
+            attrs,
+            "db.client.connection.outstanding" = ::tracing::field::Empty,
+        );
+        async {
+            let close = std::pin::pin!(self.inner.close());
+            let delay = futures_timer::Delay::new(timeout);
+            match futures_util::future::select(close, delay).await {
+                futures_util::future::Either::Left(_) => true,
+                futures_util::future::Either::Right(_) => {
+                    tracing::Span::current()
+                        .record("db.client.connection.outstanding", self.inner.size());
+                    false
+                }
+            }
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+/// Configuration for a SQLx connection pool with tracing hooks installed.
+///
+/// Wraps a SQLx [`PoolOptions`](sqlx::pool::PoolOptions) and installs
+/// `after_connect` and `before_acquire` callbacks that enrich the
+/// `sqlx.pool.acquire` span with connection state (new or reused) and the
+/// time the connection spent idle. Connections discarded by the pool (idle
+/// timeout, max lifetime, or rejected/failed by a user callback) emit a
+/// `sqlx.connection.close` event carrying the reason.
+///
+/// Callbacks set directly on the wrapped SQLx options are replaced; use
+/// [`PoolOptions::after_connect`], [`PoolOptions::before_acquire`] and
+/// [`PoolOptions::after_release`] instead so they run alongside the tracing hooks.
+pub struct PoolOptions<DB>
+where
+    DB: sqlx::Database,
+{
+    inner: sqlx::pool::PoolOptions<DB>,
+    after_connect: Option<Arc<options::AfterConnectFn<DB>>>,
+    before_acquire: Option<Arc<options::BeforeAcquireFn<DB>>>,
+    after_release: Option<Arc<options::AfterReleaseFn<DB>>>,
+    record_server_address: bool,
+    record_settings: bool,
+}
+
+impl<DB: sqlx::Database> std::fmt::Debug for PoolOptions<DB> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PoolOptions")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
 }
 
 /// Wrapper for a mutable SQLx connection reference with tracing attributes.
@@ -297,6 +1627,8 @@ where
 {
     inner: &'c mut DB::Connection,
     attributes: Arc<Attributes>,
+    idle: Option<Arc<idle::IdleTracker>>,
+    connection_id: u64,
 }
 
 impl<'c, DB: sqlx::Database> std::fmt::Debug for Connection<'c, DB> {
@@ -315,6 +1647,9 @@ where
 {
     inner: sqlx::pool::PoolConnection<DB>,
     attributes: Arc<Attributes>,
+    id: u64,
+    lease: tracing::Span,
+    _run: Option<repeat::RunGuard>,
 }
 
 /// An in-progress database transaction or savepoint, instrumented for tracing.
@@ -328,6 +1663,9 @@ where
 ///
 /// Use [`Transaction::executor`] to obtain a tracing-instrumented executor
 /// for running queries within the transaction.
+///
+/// Each transaction owns a `sqlx.transaction` span covering its lifetime,
+/// see [`Transaction::span`].
 #[derive(Debug)]
 pub struct Transaction<'c, DB>
 where
@@ -335,4 +1673,10 @@ where
 {
     inner: sqlx::Transaction<'c, DB>,
     attributes: Arc<Attributes>,
+    idle: Arc<idle::IdleTracker>,
+    savepoints: Arc<std::sync::atomic::AtomicUsize>,
+    span: tracing::Span,
+    connection_id: u64,
+    _queue: Option<queue::QueueSlot>,
+    _run: Option<repeat::RunGuard>,
 }
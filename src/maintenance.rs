@@ -0,0 +1,42 @@
+use tracing::Instrument;
+
+/// Helper macro creating the span of a maintenance helper.
+///
+/// The span records the maintained table, if any, as `db.sql.table` and the
+/// time the statement took as `db.maintenance.duration_ms`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! maintenance_span {
+    ($operation:expr, $attrs:expr, $table:expr) => {
+        $crate::instrument_op!(
+            $operation,
+            $attrs,
+            "db.sql.table" = $table,
+            "db.maintenance.duration_ms" = ::tracing::field::Empty,
+        )
+    };
+}
+
+impl<DB> crate::Pool<DB>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+    for<'a> &'a mut DB::Connection: sqlx::Executor<'a, Database = DB>,
+{
+    /// Runs the maintenance statement `sql` on a pooled connection within
+    /// `span`, created by `maintenance_span!`, which parents its
+    /// `sqlx.execute` span.
+    pub(crate) async fn maintain(&self, span: tracing::Span, sql: &str) -> Result<(), sqlx::Error> {
+        async {
+            let start = std::time::Instant::now();
+            let mut conn = self.acquire().await?;
+            let res = sqlx::Executor::execute(&mut conn, sql).await.map(drop);
+            tracing::Span::current().record(
+                "db.maintenance.duration_ms",
+                start.elapsed().as_millis() as u64,
+            );
+            res
+        }
+        .instrument(span)
+        .await
+    }
+}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::{Counter, Histogram};
+
+/// OpenTelemetry metrics of a pool, created from the global meter provider
+/// when the pool is built.
+#[derive(Debug)]
+pub(crate) struct PoolMetrics {
+    wait_time: Histogram<f64>,
+    errors: Counter<u64>,
+    attributes: [KeyValue; 1],
+}
+
+impl PoolMetrics {
+    pub(crate) fn new(pool_name: &str) -> Self {
+        let meter = opentelemetry::global::meter("sqlx-tracing");
+        let wait_time = meter
+            .f64_histogram("db.client.connection.wait_time")
+            .with_unit("s")
+            .with_description("The time it took to obtain an open connection from the pool")
+            .build();
+        let errors = meter
+            .u64_counter("db.client.operation.errors")
+            .with_unit("{error}")
+            .with_description("The number of failed operations, by error category")
+            .build();
+        Self {
+            wait_time,
+            errors,
+            attributes: [KeyValue::new(
+                "db.client.connection.pool.name",
+                pool_name.to_owned(),
+            )],
+        }
+    }
+
+    /// Records the time spent waiting for a connection in `Pool::acquire`.
+    pub(crate) fn record_wait(&self, wait: Duration) {
+        self.wait_time.record(wait.as_secs_f64(), &self.attributes);
+    }
+
+    /// Counts a failed operation, with the `error.category` given by
+    /// [`error_category`](crate::span::error_category).
+    pub(crate) fn record_error(&self, category: &'static str) {
+        let [pool_name] = &self.attributes;
+        self.errors.add(
+            1,
+            &[pool_name.clone(), KeyValue::new("error.category", category)],
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use std::str::FromStr;
+
+use sqlx::migrate::MigrateDatabase;
+use tracing::Instrument;
+
+/// Returns the attributes of the database targeted by `url`, or default ones
+/// when it cannot be parsed, in which case SQLx reports the error.
+fn attributes<DB>(url: &str) -> crate::Attributes
+where
+    DB: crate::prelude::Database + sqlx::Database,
+{
+    <<DB::Connection as sqlx::Connection>::Options as FromStr>::from_str(url)
+        .map(|options| DB::connection_attributes(&options).build())
+        .unwrap_or_default()
+}
+
+/// Creates the database targeted by `url`, with
+/// [`MigrateDatabase::create_database`].
+///
+/// The operation is wrapped in a `sqlx.database.create` span recording the
+/// target database as `db.name`, along with its host and user. This is meant
+/// for test harnesses and provisioning tools.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// if !sqlx_tracing::database_exists::<sqlx::Postgres>(&url).await? {
+///     sqlx_tracing::create_database::<sqlx::Postgres>(&url).await?;
+/// }
+/// ```
+pub async fn create_database<DB>(url: &str) -> Result<(), sqlx::Error>
+where
+    DB: MigrateDatabase + crate::prelude::Database + sqlx::Database,
+{
+    let attrs = attributes::<DB>(url);
+    let record_details = attrs.config().error_details();
+    let span = crate::instrument_op!(crate::Operation::CreateDatabase, attrs);
+    async {
+        DB::create_database(url)
+            .await
+            .inspect_err(|e| crate::span::record_error(e, &record_details))
+    }
+    .instrument(span)
+    .await
+}
+
+/// Returns whether the database targeted by `url` exists, with
+/// [`MigrateDatabase::database_exists`].
+///
+/// The check is wrapped in a `sqlx.database.exists` span recording its
+/// outcome as `db.database.exists`, see [`create_database`].
+pub async fn database_exists<DB>(url: &str) -> Result<bool, sqlx::Error>
+where
+    DB: MigrateDatabase + crate::prelude::Database + sqlx::Database,
+{
+    let attrs = attributes::<DB>(url);
+    let record_details = attrs.config().error_details();
+    let span = crate::instrument_op!(
+        crate::Operation::DatabaseExists,
+        attrs,
+        "db.database.exists" = ::tracing::field::Empty,
+    );
+    async {
+        DB::database_exists(url)
+            .await
+            .inspect(|exists| {
+                tracing::Span::current().record("db.database.exists", exists);
+            })
+            .inspect_err(|e| crate::span::record_error(e, &record_details))
+    }
+    .instrument(span)
+    .await
+}
+
+/// Drops the database targeted by `url`, with
+/// [`MigrateDatabase::drop_database`].
+///
+/// The operation is wrapped in a `sqlx.database.drop` span, see
+/// [`create_database`].
+pub async fn drop_database<DB>(url: &str) -> Result<(), sqlx::Error>
+where
+    DB: MigrateDatabase + crate::prelude::Database + sqlx::Database,
+{
+    let attrs = attributes::<DB>(url);
+    let record_details = attrs.config().error_details();
+    let span = crate::instrument_op!(crate::Operation::DropDatabase, attrs);
+    async {
+        DB::drop_database(url)
+            .await
+            .inspect_err(|e| crate::span::record_error(e, &record_details))
+    }
+    .instrument(span)
+    .await
+}
@@ -0,0 +1,99 @@
+impl crate::prelude::Database for sqlx::MySql {
+    const SYSTEM: &'static str = "mysql";
+    const MAX_BIND_PARAMETERS: usize = u16::MAX as usize;
+
+    fn default_port() -> Option<u16> {
+        Some(3306)
+    }
+
+    fn connection_attributes(
+        options: &sqlx::mysql::MySqlConnectOptions,
+    ) -> crate::AttributesBuilder {
+        let ssl_mode = options.get_ssl_mode();
+        let builder = crate::Attributes::builder()
+            .with_host(options.get_host())
+            .with_port(options.get_port())
+            .with_user(options.get_username())
+            .with_ssl_mode(ssl_mode_name(ssl_mode));
+        let builder = match options.get_database() {
+            Some(database) => builder.with_database(database),
+            None => builder,
+        };
+        match tls_established(ssl_mode) {
+            Some(tls) => builder.with_tls(tls),
+            None => builder,
+        }
+    }
+
+    fn connection_string(options: &sqlx::mysql::MySqlConnectOptions) -> Option<String> {
+        Some(crate::options::sanitized_url(options, &["ssl-key"]))
+    }
+
+    fn execute_builder<'e>(
+        conn: &'e mut sqlx::MySqlConnection,
+        builder: &'e mut sqlx::QueryBuilder<'_, Self>,
+    ) -> futures_core::future::BoxFuture<'e, Result<sqlx::mysql::MySqlQueryResult, sqlx::Error>>
+    {
+        sqlx::Executor::execute(conn, builder.build())
+    }
+
+    fn rows_affected(result: &sqlx::mysql::MySqlQueryResult) -> u64 {
+        result.rows_affected()
+    }
+
+    fn row_size(row: &sqlx::mysql::MySqlRow) -> u64 {
+        use sqlx::{Row, ValueRef};
+        (0..row.len())
+            .filter_map(|index| row.try_get_raw(index).ok())
+            .filter(|value| !value.is_null())
+            .map(|value| {
+                <&[u8] as sqlx::Decode<sqlx::MySql>>::decode(value)
+                    .map_or(0, |bytes| bytes.len() as u64)
+            })
+            .sum()
+    }
+
+    fn reborrow_arguments<'a, 'q: 'a>(
+        arguments: <Self as sqlx::Database>::Arguments<'q>,
+    ) -> Result<<Self as sqlx::Database>::Arguments<'a>, <Self as sqlx::Database>::Arguments<'q>>
+    {
+        Ok(arguments)
+    }
+
+    fn record_result(result: &sqlx::mysql::MySqlQueryResult, record_metadata: bool) {
+        let span = tracing::Span::current();
+        span.record("db.response.affected_rows", result.rows_affected());
+        if record_metadata {
+            span.record("db.mysql.last_insert_id", result.last_insert_id());
+        }
+    }
+}
+
+/// Returns the name of an SSL mode, as recorded in `db.ssl_mode`.
+pub(crate) fn ssl_mode_name(mode: sqlx::mysql::MySqlSslMode) -> &'static str {
+    use sqlx::mysql::MySqlSslMode;
+
+    match mode {
+        MySqlSslMode::Disabled => "disabled",
+        MySqlSslMode::Preferred => "preferred",
+        MySqlSslMode::Required => "required",
+        MySqlSslMode::VerifyCa => "verify_ca",
+        MySqlSslMode::VerifyIdentity => "verify_identity",
+    }
+}
+
+/// Returns whether connections made with `mode` are known to use TLS.
+///
+/// `preferred` falls back to a plain connection when the server does not
+/// support TLS, so the outcome is unknown and `None` is returned.
+pub(crate) fn tls_established(mode: sqlx::mysql::MySqlSslMode) -> Option<bool> {
+    use sqlx::mysql::MySqlSslMode;
+
+    match mode {
+        MySqlSslMode::Disabled => Some(false),
+        MySqlSslMode::Preferred => None,
+        MySqlSslMode::Required | MySqlSslMode::VerifyCa | MySqlSslMode::VerifyIdentity => {
+            Some(true)
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Names of the spans and events emitted by this crate.
+//!
+//! Filtering layers and tests can match on these constants instead of
+//! hard-coding the strings.
+
+/// Span of [`Executor::execute`](sqlx::Executor::execute), and of each
+/// statement of a script.
+pub const EXECUTE: &str = "sqlx.execute";
+/// Span of [`Executor::execute_many`](sqlx::Executor::execute_many).
+pub const EXECUTE_MANY: &str = "sqlx.execute_many";
+/// Span of [`Executor::fetch`](sqlx::Executor::fetch).
+pub const FETCH: &str = "sqlx.fetch";
+/// Span of [`Executor::fetch_all`](sqlx::Executor::fetch_all).
+pub const FETCH_ALL: &str = "sqlx.fetch_all";
+/// Span of [`Executor::fetch_many`](sqlx::Executor::fetch_many).
+pub const FETCH_MANY: &str = "sqlx.fetch_many";
+/// Span of [`Executor::fetch_one`](sqlx::Executor::fetch_one).
+pub const FETCH_ONE: &str = "sqlx.fetch_one";
+/// Span of [`Executor::fetch_optional`](sqlx::Executor::fetch_optional).
+pub const FETCH_OPTIONAL: &str = "sqlx.fetch_optional";
+/// Span of [`Executor::prepare`](sqlx::Executor::prepare).
+pub const PREPARE: &str = "sqlx.prepare";
+/// Span of [`Executor::prepare_with`](sqlx::Executor::prepare_with).
+pub const PREPARE_WITH: &str = "sqlx.prepare_with";
+/// Span of `Executor::describe`.
+pub const DESCRIBE: &str = "sqlx.describe";
+
+/// Span of `execute_script`, parenting one [`EXECUTE`] span per statement.
+pub const EXECUTE_SCRIPT: &str = "sqlx.execute_script";
+/// Span of `execute_concurrent`, parenting one [`EXECUTE`] span per
+/// statement.
+pub const EXECUTE_CONCURRENT: &str = "sqlx.execute_concurrent";
+/// Span of `insert_many`.
+pub const INSERT_MANY: &str = "sqlx.insert_many";
+/// Span of the Postgres `bulk_copy`.
+pub const BULK_COPY: &str = "sqlx.bulk_copy";
+/// Span of a statement sampled for `EXPLAIN` on Postgres.
+pub const EXPLAIN: &str = "sqlx.explain";
+/// Span of a `CachedPool` lookup.
+pub const CACHE_FETCH: &str = "sqlx.cache.fetch";
+
+/// Span of a connection acquisition from the pool.
+pub const POOL_ACQUIRE: &str = "sqlx.pool.acquire";
+/// Event emitted when acquiring a connection exceeds the configured
+/// threshold.
+pub const POOL_ACQUIRE_SLOW: &str = "sqlx.pool.acquire_slow";
+/// Span of the construction of a pool by `PoolBuilder::build`.
+pub const POOL_CREATE: &str = "sqlx.pool.create";
+/// Event emitted when the last handle of a pool is dropped without closing
+/// it while connections are still open.
+pub const POOL_DROPPED: &str = "sqlx.pool.dropped";
+/// Span of the closing of the pool.
+pub const POOL_CLOSE: &str = "sqlx.pool.close";
+/// Span covering the lifetime of an acquired connection, when enabled.
+pub const CONNECTION_LEASE: &str = "sqlx.connection.lease";
+/// Span, or event when configured, of a connection ping.
+pub const CONNECTION_PING: &str = "sqlx.connection.ping";
+/// Span of an explicit connection close, and event emitted when the pool
+/// discards a connection.
+pub const CONNECTION_CLOSE: &str = "sqlx.connection.close";
+/// Event emitted when a failed operation breaks its connection.
+pub const CONNECTION_BROKEN: &str = "sqlx.connection.broken";
+
+/// Span covering the lifetime of a transaction.
+pub const TRANSACTION: &str = "sqlx.transaction";
+/// Span of the beginning of a transaction.
+pub const TRANSACTION_BEGIN: &str = "sqlx.transaction.begin";
+/// Span of a transaction commit.
+pub const TRANSACTION_COMMIT: &str = "sqlx.transaction.commit";
+/// Span of a transaction rollback.
+pub const TRANSACTION_ROLLBACK: &str = "sqlx.transaction.rollback";
+/// Span of the preparation of a transaction for two-phase commit.
+pub const TRANSACTION_PREPARE: &str = "sqlx.transaction.prepare";
+/// Span of the commit of a prepared transaction.
+pub const TRANSACTION_COMMIT_PREPARED: &str = "sqlx.transaction.commit_prepared";
+/// Span of the rollback of a prepared transaction.
+pub const TRANSACTION_ROLLBACK_PREPARED: &str = "sqlx.transaction.rollback_prepared";
+
+/// Event emitted when a statement's SQL is empty or only whitespace.
+pub const STATEMENT_EMPTY: &str = "sqlx.statement.empty";
+/// Event emitted when a statement starts once the budget set with
+/// `with_budget` is exhausted.
+pub const QUERY_BUDGET_EXHAUSTED: &str = "sqlx.query_budget.exhausted";
+/// Event emitted the first time a value of a span field is replaced by the
+/// guard set with `with_cardinality_limit`.
+pub const CARDINALITY_LIMITED: &str = "sqlx.cardinality.limited";
+/// Event recording an error mapped to an OK span status by
+/// `with_error_status`.
+pub const ERROR_EXPECTED: &str = "sqlx.error.expected";
+/// Event emitted the first time a pool ran more distinct statements than the
+/// statement cache of a connection holds.
+pub const STATEMENT_CACHE_EXCEEDED: &str = "sqlx.statement_cache.exceeded";
+/// Event emitted periodically while streaming rows, when enabled.
+pub const STREAM_PROGRESS: &str = "sqlx.stream.progress";
+/// Event emitted by `TracedRow::try_get_traced` when a column fails to
+/// decode.
+pub const ROW_DECODE_ERROR: &str = "sqlx.row.decode_error";
+
+/// Span of the connection of a Postgres listener.
+pub const LISTENER_CONNECT: &str = "sqlx.listener.connect";
+/// Span of a Postgres `LISTEN` command.
+pub const LISTEN: &str = "sqlx.listen";
+/// Span of a Postgres `UNLISTEN` command.
+pub const UNLISTEN: &str = "sqlx.unlisten";
+/// Consumer span of a received Postgres notification.
+pub const NOTIFICATION: &str = "sqlx.notification";
+
+/// Span of an operation retried on SQLite busy errors.
+pub const SQLITE_BUSY_RETRY: &str = "sqlx.sqlite.busy_retry";
+/// Event emitted for each SQLite busy error retried.
+pub const SQLITE_BUSY: &str = "sqlx.sqlite.busy";
+/// Span of a SQLite maintenance script, parenting one [`EXECUTE`] span per
+/// statement.
+pub const SQLITE_MAINTENANCE: &str = "sqlx.sqlite.maintenance";
+/// Span of the direct use of a SQLite database handle.
+pub const SQLITE_RAW: &str = "sqlx.sqlite.raw";
+/// Span of a custom SQLite function invocation, or of a custom aggregate.
+pub const SQLITE_FUNCTION: &str = "sqlx.sqlite.function";
+
+/// Span of a `vacuum` maintenance helper.
+pub const VACUUM: &str = "sqlx.maintenance.vacuum";
+/// Span of an `analyze` maintenance helper.
+pub const ANALYZE: &str = "sqlx.maintenance.analyze";
+/// Span of the Postgres `reindex` maintenance helper.
+pub const REINDEX: &str = "sqlx.maintenance.reindex";
+/// Span of the SQLite `optimize` maintenance helper.
+pub const OPTIMIZE: &str = "sqlx.maintenance.optimize";
+
+/// Span of the creation of a database.
+pub const DATABASE_CREATE: &str = "sqlx.database.create";
+/// Span of the existence check of a database.
+pub const DATABASE_EXISTS: &str = "sqlx.database.exists";
+/// Span of the removal of a database.
+pub const DATABASE_DROP: &str = "sqlx.database.drop";
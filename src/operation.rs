@@ -0,0 +1,232 @@
+use crate::names;
+
+/// Database operation traced by this crate, each with its own span.
+///
+/// The span name of an operation is returned by [`Operation::span_name`],
+/// and [`Operation::from_span_name`] maps a span name back to its operation,
+/// e.g. in a filtering layer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Operation {
+    /// [`Executor::execute`](sqlx::Executor::execute).
+    Execute,
+    /// [`Executor::execute_many`](sqlx::Executor::execute_many).
+    ExecuteMany,
+    /// [`Executor::fetch`](sqlx::Executor::fetch).
+    Fetch,
+    /// [`Executor::fetch_all`](sqlx::Executor::fetch_all).
+    FetchAll,
+    /// [`Executor::fetch_many`](sqlx::Executor::fetch_many).
+    FetchMany,
+    /// [`Executor::fetch_one`](sqlx::Executor::fetch_one).
+    FetchOne,
+    /// [`Executor::fetch_optional`](sqlx::Executor::fetch_optional).
+    FetchOptional,
+    /// [`Executor::prepare`](sqlx::Executor::prepare).
+    Prepare,
+    /// [`Executor::prepare_with`](sqlx::Executor::prepare_with).
+    PrepareWith,
+    /// `Executor::describe`.
+    Describe,
+    /// `execute_script`.
+    ExecuteScript,
+    /// `execute_concurrent`.
+    ExecuteConcurrent,
+    /// `insert_many`.
+    InsertMany,
+    /// The Postgres `bulk_copy`.
+    BulkCopy,
+    /// `EXPLAIN` of a sampled Postgres statement.
+    Explain,
+    /// Lookup of a [`CachedPool`](crate::CachedPool).
+    CacheFetch,
+    /// Acquisition of a connection from the pool.
+    Acquire,
+    /// Connection of a pool created with
+    /// [`PoolOptions::connect`](crate::PoolOptions::connect).
+    CreatePool,
+    /// Closing of the pool.
+    ClosePool,
+    /// Lifetime of an acquired connection.
+    Lease,
+    /// Connection ping.
+    Ping,
+    /// Explicit connection close.
+    CloseConnection,
+    /// Lifetime of a transaction.
+    Transaction,
+    /// Beginning of a transaction.
+    Begin,
+    /// Transaction commit.
+    Commit,
+    /// Transaction rollback.
+    Rollback,
+    /// Postgres `PREPARE TRANSACTION` of a two-phase commit.
+    PrepareTransaction,
+    /// Postgres `COMMIT PREPARED` of a two-phase commit.
+    CommitPrepared,
+    /// Postgres `ROLLBACK PREPARED` of a two-phase commit.
+    RollbackPrepared,
+    /// Connection of a Postgres listener.
+    ListenerConnect,
+    /// Postgres `LISTEN` command.
+    Listen,
+    /// Postgres `UNLISTEN` command.
+    Unlisten,
+    /// Receipt of a Postgres notification.
+    Notification,
+    /// SQLite operation retried on busy errors.
+    SqliteBusyRetry,
+    /// SQLite maintenance script.
+    SqliteMaintenance,
+    /// Direct use of the SQLite database handle.
+    SqliteRaw,
+    /// Invocation of a custom SQLite function.
+    SqliteFunction,
+    /// `VACUUM` maintenance.
+    Vacuum,
+    /// `ANALYZE` maintenance.
+    Analyze,
+    /// Postgres `REINDEX` maintenance.
+    Reindex,
+    /// SQLite `PRAGMA optimize` maintenance.
+    Optimize,
+    /// Creation of a database with `create_database`.
+    CreateDatabase,
+    /// Existence check of a database with `database_exists`.
+    DatabaseExists,
+    /// Removal of a database with `drop_database`.
+    DropDatabase,
+}
+
+impl Operation {
+    /// Every operation, in declaration order.
+    pub const ALL: [Operation; 44] = [
+        Self::Execute,
+        Self::ExecuteMany,
+        Self::Fetch,
+        Self::FetchAll,
+        Self::FetchMany,
+        Self::FetchOne,
+        Self::FetchOptional,
+        Self::Prepare,
+        Self::PrepareWith,
+        Self::Describe,
+        Self::ExecuteScript,
+        Self::ExecuteConcurrent,
+        Self::InsertMany,
+        Self::BulkCopy,
+        Self::Explain,
+        Self::CacheFetch,
+        Self::Acquire,
+        Self::CreatePool,
+        Self::ClosePool,
+        Self::Lease,
+        Self::Ping,
+        Self::CloseConnection,
+        Self::Transaction,
+        Self::Begin,
+        Self::Commit,
+        Self::Rollback,
+        Self::PrepareTransaction,
+        Self::CommitPrepared,
+        Self::RollbackPrepared,
+        Self::ListenerConnect,
+        Self::Listen,
+        Self::Unlisten,
+        Self::Notification,
+        Self::SqliteBusyRetry,
+        Self::SqliteMaintenance,
+        Self::SqliteRaw,
+        Self::SqliteFunction,
+        Self::Vacuum,
+        Self::Analyze,
+        Self::Reindex,
+        Self::Optimize,
+        Self::CreateDatabase,
+        Self::DatabaseExists,
+        Self::DropDatabase,
+    ];
+
+    /// Returns the name of the span of this operation, as listed in
+    /// [`names`](crate::names).
+    pub const fn span_name(self) -> &'static str {
+        match self {
+            Self::Execute => names::EXECUTE,
+            Self::ExecuteMany => names::EXECUTE_MANY,
+            Self::Fetch => names::FETCH,
+            Self::FetchAll => names::FETCH_ALL,
+            Self::FetchMany => names::FETCH_MANY,
+            Self::FetchOne => names::FETCH_ONE,
+            Self::FetchOptional => names::FETCH_OPTIONAL,
+            Self::Prepare => names::PREPARE,
+            Self::PrepareWith => names::PREPARE_WITH,
+            Self::Describe => names::DESCRIBE,
+            Self::ExecuteScript => names::EXECUTE_SCRIPT,
+            Self::ExecuteConcurrent => names::EXECUTE_CONCURRENT,
+            Self::InsertMany => names::INSERT_MANY,
+            Self::BulkCopy => names::BULK_COPY,
+            Self::Explain => names::EXPLAIN,
+            Self::CacheFetch => names::CACHE_FETCH,
+            Self::Acquire => names::POOL_ACQUIRE,
+            Self::CreatePool => names::POOL_CREATE,
+            Self::ClosePool => names::POOL_CLOSE,
+            Self::Lease => names::CONNECTION_LEASE,
+            Self::Ping => names::CONNECTION_PING,
+            Self::CloseConnection => names::CONNECTION_CLOSE,
+            Self::Transaction => names::TRANSACTION,
+            Self::Begin => names::TRANSACTION_BEGIN,
+            Self::Commit => names::TRANSACTION_COMMIT,
+            Self::Rollback => names::TRANSACTION_ROLLBACK,
+            Self::PrepareTransaction => names::TRANSACTION_PREPARE,
+            Self::CommitPrepared => names::TRANSACTION_COMMIT_PREPARED,
+            Self::RollbackPrepared => names::TRANSACTION_ROLLBACK_PREPARED,
+            Self::ListenerConnect => names::LISTENER_CONNECT,
+            Self::Listen => names::LISTEN,
+            Self::Unlisten => names::UNLISTEN,
+            Self::Notification => names::NOTIFICATION,
+            Self::SqliteBusyRetry => names::SQLITE_BUSY_RETRY,
+            Self::SqliteMaintenance => names::SQLITE_MAINTENANCE,
+            Self::SqliteRaw => names::SQLITE_RAW,
+            Self::SqliteFunction => names::SQLITE_FUNCTION,
+            Self::Vacuum => names::VACUUM,
+            Self::Analyze => names::ANALYZE,
+            Self::Reindex => names::REINDEX,
+            Self::Optimize => names::OPTIMIZE,
+            Self::CreateDatabase => names::DATABASE_CREATE,
+            Self::DatabaseExists => names::DATABASE_EXISTS,
+            Self::DropDatabase => names::DATABASE_DROP,
+        }
+    }
+
+    /// Returns the name of this operation, as recorded in `db.operation.name`
+    /// on its span: the span name without the `sqlx.` prefix, e.g.
+    /// `pool.acquire` or `transaction.commit`.
+    ///
+    /// Statement spans record the statement's operation instead, e.g.
+    /// `SELECT`.
+    pub fn operation_name(self) -> &'static str {
+        let name = self.span_name();
+        name.strip_prefix("sqlx.").unwrap_or(name)
+    }
+
+    /// Returns the operation whose span is named `name`, if any.
+    pub fn from_span_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|operation| operation.span_name() == name)
+    }
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.span_name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Operation {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.span_name())
+    }
+}
@@ -0,0 +1,345 @@
+use std::time::Duration;
+
+use futures_core::future::BoxFuture;
+use sqlx::ConnectOptions;
+use sqlx::pool::PoolConnectionMetadata;
+use tracing::Instrument;
+
+pub(crate) type AfterConnectFn<DB> = dyn for<'c> Fn(
+        &'c mut <DB as sqlx::Database>::Connection,
+        PoolConnectionMetadata,
+    ) -> BoxFuture<'c, Result<(), sqlx::Error>>
+    + Send
+    + Sync
+    + 'static;
+
+pub(crate) type BeforeAcquireFn<DB> = dyn for<'c> Fn(
+        &'c mut <DB as sqlx::Database>::Connection,
+        PoolConnectionMetadata,
+    ) -> BoxFuture<'c, Result<bool, sqlx::Error>>
+    + Send
+    + Sync
+    + 'static;
+
+pub(crate) type AfterReleaseFn<DB> = BeforeAcquireFn<DB>;
+
+impl<DB: sqlx::Database> Default for crate::PoolOptions<DB> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<DB: sqlx::Database> From<sqlx::pool::PoolOptions<DB>> for crate::PoolOptions<DB> {
+    /// Wrap existing SQLx pool options so the tracing hooks get installed.
+    fn from(inner: sqlx::pool::PoolOptions<DB>) -> Self {
+        Self {
+            inner,
+            after_connect: None,
+            before_acquire: None,
+            after_release: None,
+            record_server_address: false,
+            record_settings: false,
+        }
+    }
+}
+
+impl<DB: sqlx::Database> crate::PoolOptions<DB> {
+    /// Returns the default SQLx pool configuration with tracing hooks.
+    pub fn new() -> Self {
+        Self::from(sqlx::pool::PoolOptions::new())
+    }
+
+    /// Perform an asynchronous action after a new connection is established.
+    ///
+    /// See [`sqlx::pool::PoolOptions::after_connect`].
+    pub fn after_connect<F>(mut self, callback: F) -> Self
+    where
+        for<'c> F: Fn(
+                &'c mut DB::Connection,
+                PoolConnectionMetadata,
+            ) -> BoxFuture<'c, Result<(), sqlx::Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.after_connect = Some(std::sync::Arc::new(callback));
+        self
+    }
+
+    /// Perform an asynchronous action on a previously idle connection before giving it out.
+    ///
+    /// See [`sqlx::pool::PoolOptions::before_acquire`].
+    pub fn before_acquire<F>(mut self, callback: F) -> Self
+    where
+        for<'c> F: Fn(
+                &'c mut DB::Connection,
+                PoolConnectionMetadata,
+            ) -> BoxFuture<'c, Result<bool, sqlx::Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.before_acquire = Some(std::sync::Arc::new(callback));
+        self
+    }
+
+    /// Perform an asynchronous action on a connection before it is returned to the pool.
+    ///
+    /// See [`sqlx::pool::PoolOptions::after_release`].
+    pub fn after_release<F>(mut self, callback: F) -> Self
+    where
+        for<'c> F: Fn(
+                &'c mut DB::Connection,
+                PoolConnectionMetadata,
+            ) -> BoxFuture<'c, Result<bool, sqlx::Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.after_release = Some(std::sync::Arc::new(callback));
+        self
+    }
+
+    /// Enable or disable recording the address of the server each new
+    /// connection reached, as `db.client.connection.server_address` on the
+    /// `sqlx.pool.acquire` span that opened it.
+    ///
+    /// In a multi-host or failover setup, see
+    /// [`PoolBuilder::with_hosts`](crate::PoolBuilder::with_hosts), this tells
+    /// which candidate the connections actually use, which explains latency
+    /// shifts after a failover. The address is queried once per connection,
+    /// with `inet_server_addr()`.
+    ///
+    /// Only supported by Postgres, ignored by other databases. Disabled by
+    /// default.
+    pub fn with_server_address_recording(mut self, enabled: bool) -> Self {
+        self.record_server_address = enabled;
+        self
+    }
+
+    /// Enable or disable recording the effective settings of each new
+    /// connection on the `sqlx.pool.acquire` span that opened it.
+    ///
+    /// For SQLite, the `journal_mode`, `synchronous` and `cache_size` PRAGMAs
+    /// are queried once per connection and recorded as
+    /// `db.sqlite.journal_mode`, `db.sqlite.synchronous` and
+    /// `db.sqlite.cache_size`, after the
+    /// [`after_connect`](Self::after_connect) callback ran, so performance
+    /// investigations can see the configuration each connection actually
+    /// runs with.
+    ///
+    /// Only supported by SQLite, ignored by other databases. Disabled by
+    /// default.
+    pub fn with_settings_recording(mut self, enabled: bool) -> Self {
+        self.record_settings = enabled;
+        self
+    }
+
+    /// Returns the SQLx pool options with the tracing hooks installed.
+    pub fn into_inner(self) -> sqlx::pool::PoolOptions<DB>
+    where
+        DB: crate::prelude::Database,
+    {
+        let Self {
+            inner,
+            after_connect,
+            before_acquire,
+            after_release,
+            record_server_address,
+            record_settings,
+        } = self;
+
+        let inner = inner.after_connect(move |conn, meta| {
+            let span = tracing::Span::current();
+            span.record("db.client.connection.state", "new");
+            let callback = after_connect.clone();
+            Box::pin(async move {
+                if record_server_address
+                    && let Some(address) = DB::server_address(conn)
+                    // Not knowing the address is no reason to drop the connection.
+                    && let Ok(Some(address)) = address.await
+                {
+                    span.record("db.client.connection.server_address", address);
+                }
+                if let Some(callback) = callback {
+                    callback(conn, meta).await?;
+                }
+                // Queried last, so the settings changed by the callback are seen.
+                if record_settings && let Some(settings) = DB::record_settings(conn, &span) {
+                    // Neither is failing to query the settings.
+                    let _ = settings.await;
+                }
+                Ok(())
+            })
+        });
+
+        let max_lifetime = inner.get_max_lifetime();
+        let idle_timeout = inner.get_idle_timeout();
+        let inner = inner.before_acquire(move |conn, meta| {
+            let span = tracing::Span::current();
+            span.record("db.client.connection.state", "reused");
+            span.record(
+                "db.client.connection.idle_ms",
+                meta.idle_for.as_millis() as u64,
+            );
+            // SQLx only reaps expired connections periodically, discard them here
+            // so the pool opens a fresh one and the reason is observable.
+            if idle_timeout.is_some_and(|timeout| meta.idle_for > timeout) {
+                record_close("idle_timeout", meta.age);
+                return Box::pin(async { Ok(false) });
+            }
+            if max_lifetime.is_some_and(|lifetime| meta.age > lifetime) {
+                record_close("max_lifetime", meta.age);
+                return Box::pin(async { Ok(false) });
+            }
+            match before_acquire {
+                Some(ref callback) => {
+                    let age = meta.age;
+                    checked(callback(conn, meta), age)
+                }
+                None => Box::pin(async { Ok(true) }),
+            }
+        });
+
+        match after_release {
+            Some(callback) => inner.after_release(move |conn, meta| {
+                let age = meta.age;
+                checked(callback(conn, meta), age)
+            }),
+            None => inner,
+        }
+    }
+
+    /// Create a new pool from these options and immediately open at least one connection.
+    ///
+    /// Returns a [`PoolBuilder`](crate::PoolBuilder) so the tracing attributes
+    /// can still be adjusted before building the [`Pool`](crate::Pool).
+    ///
+    /// SQLx's built-in statement logging is disabled, as every statement is
+    /// already traced; see [`PoolBuilder::with_sqlx_logging`](crate::PoolBuilder::with_sqlx_logging).
+    ///
+    /// Connecting is recorded as a `sqlx.pool.create` span, carrying the
+    /// attributes extracted from the connect options along with the pool
+    /// options, such as `db.client.connection.max`, which anchors the
+    /// lifecycle of the pool in the trace timeline. The span records the
+    /// error when no connection could be opened.
+    pub async fn connect(self, url: &str) -> Result<crate::PoolBuilder<DB>, sqlx::Error>
+    where
+        DB: crate::prelude::Database,
+        crate::PoolBuilder<DB>: From<sqlx::Pool<DB>>,
+    {
+        self.connect_with(url.parse()?).await
+    }
+
+    /// Create a new pool from these options and the given connect options,
+    /// immediately opening at least one connection.
+    ///
+    /// SQLx's built-in statement logging is disabled, and connecting is
+    /// recorded as a `sqlx.pool.create` span, see [`connect`](Self::connect).
+    pub async fn connect_with(
+        self,
+        options: <DB::Connection as sqlx::Connection>::Options,
+    ) -> Result<crate::PoolBuilder<DB>, sqlx::Error>
+    where
+        DB: crate::prelude::Database,
+        crate::PoolBuilder<DB>: From<sqlx::Pool<DB>>,
+    {
+        let attributes = crate::connection_attributes::<DB>(&options);
+        let record_details = attributes.config().error_details();
+        let inner = self.into_inner();
+        let span = crate::instrument_op!(
+            crate::Operation::CreatePool,
+            attributes,
+            "db.client.connection.max" = inner.get_max_connections(),
+            "db.client.connection.min" = inner.get_min_connections(),
+            "db.client.connection.acquire_timeout_ms" =
+                inner.get_acquire_timeout().as_millis() as u64,
+            "db.client.connection.idle_timeout_ms" = inner
+                .get_idle_timeout()
+                .map(|timeout| timeout.as_millis() as u64),
+            "db.client.connection.max_lifetime_ms" = inner
+                .get_max_lifetime()
+                .map(|lifetime| lifetime.as_millis() as u64),
+            "db.client.connection.test_before_acquire" = inner.get_test_before_acquire(),
+        );
+        let pool = async {
+            inner
+                .connect_with(options.disable_statement_logging())
+                .await
+                .inspect_err(|e| crate::span::record_error(e, &record_details))
+        }
+        .instrument(span)
+        .await?;
+        Ok(crate::PoolBuilder::with_connection_attributes(
+            pool, attributes,
+        ))
+    }
+
+    /// Create a new pool from these options and the given connect options,
+    /// without opening any connection until one is first needed.
+    ///
+    /// SQLx's built-in statement logging is disabled, see [`connect`](Self::connect).
+    pub fn connect_lazy_with(
+        self,
+        options: <DB::Connection as sqlx::Connection>::Options,
+    ) -> crate::PoolBuilder<DB>
+    where
+        DB: crate::prelude::Database,
+        crate::PoolBuilder<DB>: From<sqlx::Pool<DB>>,
+    {
+        crate::PoolBuilder::from(
+            self.into_inner()
+                .connect_lazy_with(options.disable_statement_logging()),
+        )
+    }
+}
+
+/// Returns the connection URL of `options` with the username and password
+/// removed, along with the `secret_params` query parameters, such as client
+/// keys that may be given inline rather than as a path.
+#[cfg(any(feature = "mysql", feature = "postgres"))]
+pub(crate) fn sanitized_url(options: &impl ConnectOptions, secret_params: &[&str]) -> String {
+    let mut url = options.to_url_lossy();
+    // Both setters only fail for URLs that cannot have credentials.
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !secret_params.contains(&key.as_ref()))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+    url.to_string()
+}
+
+/// Emits a `sqlx.connection.close` event for a connection discarded by the pool.
+fn record_close(reason: &'static str, age: Duration) {
+    if cfg!(feature = "disabled") {
+        return;
+    }
+    tracing::event!(
+        name: crate::names::CONNECTION_CLOSE,
+        tracing::Level::INFO,
+        {
+            "db.client.connection.close_reason" = reason,
+            "db.client.connection.age_ms" = age.as_millis() as u64,
+        },
+        "connection discarded by the pool"
+    );
+}
+
+/// Wraps a user hook so that rejecting or failing a connection emits a close event.
+fn checked(
+    fut: BoxFuture<'_, Result<bool, sqlx::Error>>,
+    age: Duration,
+) -> BoxFuture<'_, Result<bool, sqlx::Error>> {
+    Box::pin(async move {
+        let res = fut.await;
+        match res {
+            Ok(true) => {}
+            Ok(false) => record_close("rejected", age),
+            Err(_) => record_close("error", age),
+        }
+        res
+    })
+}
@@ -0,0 +1,48 @@
+use opentelemetry::baggage::BaggageExt;
+use opentelemetry::trace::{SpanId, TraceContextExt, TraceId};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Returns the OpenTelemetry trace id of the current tracing span.
+///
+/// Useful to include in application error responses so that a failing
+/// database operation can be found in the observability backend.
+///
+/// Returns `None` when the current span is not exported through
+/// `tracing-opentelemetry`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// if let Err(err) = sqlx::query("...").execute(&pool).await {
+///     let trace_id = sqlx_tracing::current_trace_id();
+///     // include trace_id in the error response
+/// }
+/// ```
+pub fn current_trace_id() -> Option<TraceId> {
+    let context = tracing::Span::current().context();
+    let span = context.span();
+    let span_context = span.span_context();
+    span_context.is_valid().then(|| span_context.trace_id())
+}
+
+/// Returns the OpenTelemetry span id of the current tracing span.
+///
+/// Returns `None` when the current span is not exported through
+/// `tracing-opentelemetry`.
+pub fn current_span_id() -> Option<SpanId> {
+    let context = tracing::Span::current().context();
+    let span = context.span();
+    let span_context = span.span_context();
+    span_context.is_valid().then(|| span_context.span_id())
+}
+
+/// Records the W3C Baggage entries of `span`'s OpenTelemetry context as
+/// attributes, restricted to `keys` when set.
+pub(crate) fn record_baggage(span: &tracing::Span, keys: Option<&[String]>) {
+    let context = span.context();
+    for (key, (value, _)) in context.baggage() {
+        if keys.is_none_or(|keys| keys.iter().any(|allowed| allowed == key.as_str())) {
+            span.set_attribute(key.clone(), value.clone());
+        }
+    }
+}
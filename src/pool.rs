@@ -1,6 +1,396 @@
-use futures::{StreamExt, TryStreamExt};
-use tracing::Instrument;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
+/// Returns a new pool name unique within the process, recorded as
+/// `db.client.pool.name` unless set on the builder.
+pub(crate) fn next_name() -> String {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+    format!("pool-{}", NEXT_ID.fetch_add(1, Ordering::Relaxed))
+}
+
+/// SQLx pool shared by the clones of a [`Pool`](crate::Pool), dropped along
+/// with the last of them.
+///
+/// Dropping a pool that still has open connections is usually a mistake,
+/// e.g. a pool built in a function instead of shared with the application,
+/// so it emits a `sqlx.pool.dropped` warning event.
+#[derive(Debug)]
+pub(crate) struct SharedPool<DB: sqlx::Database> {
+    pool: sqlx::Pool<DB>,
+    attributes: std::sync::Arc<crate::Attributes>,
+}
+
+impl<DB: sqlx::Database> SharedPool<DB> {
+    pub(crate) fn new(pool: sqlx::Pool<DB>, attributes: std::sync::Arc<crate::Attributes>) -> Self {
+        Self { pool, attributes }
+    }
+}
+
+impl<DB: sqlx::Database> std::ops::Deref for SharedPool<DB> {
+    type Target = sqlx::Pool<DB>;
+
+    fn deref(&self) -> &sqlx::Pool<DB> {
+        &self.pool
+    }
+}
+
+impl<DB: sqlx::Database> Drop for SharedPool<DB> {
+    fn drop(&mut self) {
+        if cfg!(feature = "disabled") || self.pool.is_closed() || self.pool.size() == 0 {
+            return;
+        }
+        tracing::event!(
+            name: crate::names::POOL_DROPPED,
+            tracing::Level::WARN,
+            {
+                "db.client.pool.name" = self.attributes.pool_name.as_deref(),
+                "pool.size" = self.pool.size(),
+                "pool.idle" = self.pool.num_idle(),
+            },
+            "pool dropped without being closed while connections were open"
+        );
+    }
+}
+
+/// SQLx pool run as an executor by value, so that the future of a rewritten
+/// statement can own it, like SQLx does for its own pool futures.
+pub(crate) struct OwnedPool<DB: sqlx::Database>(sqlx::Pool<DB>);
+
+impl<DB: sqlx::Database> OwnedPool<DB> {
+    pub(crate) fn new(pool: &sqlx::Pool<DB>) -> Self {
+        Self(pool.clone())
+    }
+}
+
+impl<DB: sqlx::Database> std::fmt::Debug for OwnedPool<DB> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<'p, DB> sqlx::Executor<'p> for OwnedPool<DB>
+where
+    DB: sqlx::Database,
+    for<'c> &'c mut DB::Connection: sqlx::Executor<'c, Database = DB>,
+{
+    type Database = DB;
+
+    fn fetch_many<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> futures_core::stream::BoxStream<
+        'e,
+        Result<sqlx::Either<DB::QueryResult, DB::Row>, sqlx::Error>,
+    >
+    where
+        'p: 'e,
+        E: 'q + sqlx::Execute<'q, DB>,
+    {
+        self.0.fetch_many(query)
+    }
+
+    fn fetch_optional<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> futures_core::future::BoxFuture<'e, Result<Option<DB::Row>, sqlx::Error>>
+    where
+        'p: 'e,
+        E: 'q + sqlx::Execute<'q, DB>,
+    {
+        self.0.fetch_optional(query)
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [DB::TypeInfo],
+    ) -> futures_core::future::BoxFuture<'e, Result<DB::Statement<'q>, sqlx::Error>>
+    where
+        'p: 'e,
+    {
+        self.0.prepare_with(sql, parameters)
+    }
+
+    fn describe<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+    ) -> futures_core::future::BoxFuture<'e, Result<sqlx::Describe<DB>, sqlx::Error>>
+    where
+        'p: 'e,
+    {
+        self.0.describe(sql)
+    }
+}
+
+/// Tracks an in-flight acquisition in the pool's count of pending acquires,
+/// until dropped.
+pub(crate) struct PendingAcquire<'a> {
+    pending: &'a AtomicUsize,
+    waiting: usize,
+}
+
+impl<'a> PendingAcquire<'a> {
+    pub(crate) fn new(pending: &'a AtomicUsize) -> Self {
+        let waiting = pending.fetch_add(1, Ordering::Relaxed);
+        Self { pending, waiting }
+    }
+
+    /// Number of acquisitions already pending when this one began.
+    pub(crate) fn waiting(&self) -> usize {
+        self.waiting
+    }
+}
+
+/// Emits a `sqlx.pool.acquire_slow` event with the state of `pool` when
+/// waiting `wait` for a connection exceeded `threshold`.
+///
+/// `pending` is the number of acquisitions in flight, including this one.
+pub(crate) fn report_slow_acquire<DB: sqlx::Database>(
+    pool: &sqlx::Pool<DB>,
+    threshold: Option<std::time::Duration>,
+    wait: std::time::Duration,
+    pending: &AtomicUsize,
+) {
+    if cfg!(feature = "disabled") || threshold.is_none_or(|threshold| wait <= threshold) {
+        return;
+    }
+    tracing::event!(
+        name: crate::names::POOL_ACQUIRE_SLOW,
+        tracing::Level::WARN,
+        {
+            "db.client.connection.wait_ms" = wait.as_millis() as u64,
+            "pool.size" = pool.size(),
+            "pool.idle" = pool.num_idle(),
+            "pool.max_connections" = pool.options().get_max_connections(),
+            "pool.pending_acquires" = pending.load(Ordering::Relaxed).saturating_sub(1),
+        },
+        "acquiring a connection took longer than the configured threshold"
+    );
+}
+
+impl Drop for PendingAcquire<'_> {
+    fn drop(&mut self) {
+        self.pending.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Number of [`Pool::acquire`](crate::Pool::acquire),
+/// [`Pool::try_acquire`](crate::Pool::try_acquire) and
+/// [`Pool::try_begin`](crate::Pool::try_begin) calls of a pool per outcome,
+/// returned by
+/// [`Pool::acquire_outcomes`](crate::Pool::acquire_outcomes).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AcquireOutcomes {
+    /// Connections handed out while an idle connection was available when
+    /// the acquisition began.
+    pub immediate: u64,
+    /// Connections handed out after waiting for one to be released or
+    /// established, as none was idle when the acquisition began.
+    pub waited: u64,
+    /// Acquisitions that gave up after the pool's acquire timeout or the
+    /// request deadline.
+    pub timed_out: u64,
+    /// Acquisitions that failed because the pool was closed.
+    pub pool_closed: u64,
+    /// Acquisitions that failed for another reason, such as a failure to
+    /// establish a connection.
+    pub failed: u64,
+    /// Non-blocking acquisitions that found no connection available.
+    pub unavailable: u64,
+}
+
+/// Reason a [`Pool::acquire`](crate::Pool::acquire) call failed, recorded as
+/// `pool.acquire.error_reason` on its `sqlx.pool.acquire` span.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AcquireErrorReason {
+    /// The pool's acquire timeout elapsed, as
+    /// [`sqlx::Error::PoolTimedOut`].
+    TimedOut,
+    /// The request deadline set with [`with_deadline`](crate::with_deadline)
+    /// passed.
+    DeadlineExceeded,
+    /// The pool was closed, as [`sqlx::Error::PoolClosed`].
+    PoolClosed,
+    /// Establishing a new connection failed, or a connection hook returned
+    /// an error.
+    Connect,
+}
+
+impl AcquireErrorReason {
+    /// Returns the reason of an acquisition that failed with `err`.
+    pub fn of(err: &sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::PoolTimedOut => Self::TimedOut,
+            _ if crate::DeadlineExceeded::is(err) => Self::DeadlineExceeded,
+            sqlx::Error::PoolClosed => Self::PoolClosed,
+            _ => Self::Connect,
+        }
+    }
+
+    /// Returns the value of the `pool.acquire.error_reason` field.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::TimedOut => "timed_out",
+            Self::DeadlineExceeded => "deadline_exceeded",
+            Self::PoolClosed => "pool_closed",
+            Self::Connect => "connect",
+        }
+    }
+}
+
+impl std::fmt::Display for AcquireErrorReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Counters behind [`AcquireOutcomes`], shared by the clones of a pool.
+#[derive(Debug, Default)]
+pub(crate) struct AcquireCounters {
+    immediate: AtomicU64,
+    waited: AtomicU64,
+    timed_out: AtomicU64,
+    pool_closed: AtomicU64,
+    failed: AtomicU64,
+    unavailable: AtomicU64,
+}
+
+impl AcquireCounters {
+    /// Counts the outcome of an acquisition and returns its
+    /// `db.client.connection.acquire_outcome` value.
+    pub(crate) fn record<T>(&self, res: &Result<T, sqlx::Error>, waited: bool) -> &'static str {
+        let (counter, outcome) = match res {
+            Ok(_) if waited => (&self.waited, "waited"),
+            Ok(_) => (&self.immediate, "immediate"),
+            Err(sqlx::Error::PoolTimedOut) => (&self.timed_out, "timed_out"),
+            Err(e) if crate::DeadlineExceeded::is(e) => (&self.timed_out, "timed_out"),
+            Err(sqlx::Error::PoolClosed) => (&self.pool_closed, "pool_closed"),
+            Err(_) => (&self.failed, "failed"),
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        outcome
+    }
+
+    /// Counts the outcome of a non-blocking acquisition, handed out
+    /// immediately or unavailable, and returns its
+    /// `db.client.connection.acquire_outcome` value.
+    pub(crate) fn record_try(&self, acquired: bool) -> &'static str {
+        let (counter, outcome) = if acquired {
+            (&self.immediate, "acquired")
+        } else {
+            (&self.unavailable, "unavailable")
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        outcome
+    }
+
+    pub(crate) fn snapshot(&self) -> AcquireOutcomes {
+        AcquireOutcomes {
+            immediate: self.immediate.load(Ordering::Relaxed),
+            waited: self.waited.load(Ordering::Relaxed),
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+            pool_closed: self.pool_closed.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            unavailable: self.unavailable.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl<DB> crate::Pool<DB>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+    for<'c> &'c mut DB::Connection: sqlx::Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: sqlx::IntoArguments<'q, DB>,
+{
+    /// Runs the query `sql` and maps its first row to `T`, failing with
+    /// [`sqlx::Error::RowNotFound`] when it returns none.
+    ///
+    /// A shorthand for [`sqlx::query_as`], for scripts and admin tools, traced
+    /// as a `sqlx.fetch_one` span like
+    /// [`Executor::fetch_one`](sqlx::Executor::fetch_one).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let (count,): (i64,) = pool.fetch_one_as("SELECT COUNT(*) FROM users").await?;
+    /// ```
+    ///
+    /// The row is decoded within the span, which records the column and SQL
+    /// type of decode errors as `db.error.column` and `db.error.source_type`.
+    pub async fn fetch_one_as<T>(&self, sql: &str) -> Result<T, sqlx::Error>
+    where
+        T: for<'r> sqlx::FromRow<'r, DB::Row> + Send,
+    {
+        let writes = crate::queue::writes(sql);
+        crate::queue::future(self.writer_queue.as_ref(), writes, || {
+            let attrs = &self.attributes;
+            let record_size = attrs.config().record_payload_size;
+            let query = crate::span::Query::new(sqlx::query::<DB>(sql), attrs);
+            let sql = &query.sql();
+            crate::exec_fut!(
+                @record crate::span::record_one;
+                crate::Operation::FetchOne,
+                sql,
+                attrs,
+                async move {
+                    let row = query.fetch_one(crate::pool::OwnedPool::new(self.inner())).await?;
+                    if record_size {
+                        crate::span::record_response_size::<DB>(std::iter::once(&row));
+                    }
+                    T::from_row(&row)
+                }
+            )
+        })
+        .await
+    }
+
+    /// Runs the query `sql` and maps all its rows to `T`.
+    ///
+    /// A shorthand for [`sqlx::query_as`], traced as a `sqlx.fetch_all` span
+    /// like [`Executor::fetch_all`](sqlx::Executor::fetch_all), see
+    /// [`fetch_one_as`](Self::fetch_one_as).
+    pub async fn fetch_all_as<T>(&self, sql: &str) -> Result<Vec<T>, sqlx::Error>
+    where
+        T: for<'r> sqlx::FromRow<'r, DB::Row> + Send,
+    {
+        let writes = crate::queue::writes(sql);
+        crate::queue::future(self.writer_queue.as_ref(), writes, || {
+            let attrs = &self.attributes;
+            let record_size = attrs.config().record_payload_size;
+            let query = crate::span::Query::new(sqlx::query::<DB>(sql), attrs);
+            let sql = &query.sql();
+            crate::exec_fut!(
+                @record |rows: &Vec<T>| crate::span::record_rows(rows);
+                crate::Operation::FetchAll,
+                sql,
+                attrs,
+                async move {
+                    let rows = query.fetch_all(crate::pool::OwnedPool::new(self.inner())).await?;
+                    if record_size {
+                        crate::span::record_response_size::<DB>(&rows);
+                    }
+                    rows.iter().map(T::from_row).collect()
+                }
+            )
+        })
+        .await
+    }
+
+    /// Executes the statement `sql`, without preparing it, and returns its
+    /// result.
+    ///
+    /// A shorthand for [`Executor::execute`](sqlx::Executor::execute) with
+    /// the SQL itself, traced as a `sqlx.execute` span. Use
+    /// [`execute_script`](Self::execute_script) to run several statements
+    /// with a span each.
+    pub async fn execute_sql(&self, sql: &str) -> Result<DB::QueryResult, sqlx::Error> {
+        sqlx::Executor::execute(self, sql).await
+    }
+}
+
+#[cfg(feature = "disabled")]
+crate::passthrough_executor!(impl<'p> for &'_ crate::Pool<DB>, |pool| pool.inner());
+
+#[cfg(not(feature = "disabled"))]
 impl<'p, DB> sqlx::Executor<'p> for &'_ crate::Pool<DB>
 where
     DB: sqlx::Database + crate::prelude::Database,
@@ -12,77 +402,129 @@ where
     fn describe<'e, 'q: 'e>(
         self,
         sql: &'q str,
-    ) -> futures::future::BoxFuture<'e, Result<sqlx::Describe<Self::Database>, sqlx::Error>> {
-        let attrs = &self.attributes;
-        crate::exec_fut!("sqlx.describe", sql, attrs, self.inner.describe(sql))
+    ) -> futures_core::future::BoxFuture<'e, Result<sqlx::Describe<Self::Database>, sqlx::Error>>
+    {
+        crate::queue::future(self.writer_queue.as_ref(), false, || {
+            let attrs = &self.attributes;
+            crate::exec_fut!(
+                crate::Operation::Describe,
+                sql,
+                attrs,
+                self.inner.describe(sql)
+            )
+        })
     }
 
     fn execute<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<<Self::Database as sqlx::Database>::QueryResult, sqlx::Error>,
     >
     where
         E: 'q + sqlx::Execute<'q, Self::Database>,
     {
-        let sql = query.sql();
-        let attrs = &self.attributes;
-        crate::exec_fut!("sqlx.execute", sql, attrs, self.inner.execute(query))
+        let writes = crate::queue::writes(sqlx::Execute::sql(&query));
+        crate::queue::future(self.writer_queue.as_ref(), writes, || {
+            let attrs = &self.attributes;
+            let query = crate::span::Query::new(query, attrs);
+            let sql = &query.sql();
+            crate::exec_fut_result!(
+                sql,
+                attrs,
+                query.execute(crate::pool::OwnedPool::new(self.inner()))
+            )
+        })
     }
 
     fn execute_many<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::stream::BoxStream<
+    ) -> futures_core::stream::BoxStream<
         'e,
         Result<<Self::Database as sqlx::Database>::QueryResult, sqlx::Error>,
     >
     where
         E: 'q + sqlx::Execute<'q, Self::Database>,
     {
-        let sql = query.sql();
-        let attrs = &self.attributes;
-        crate::exec_stream!(
-            "sqlx.execute_many",
-            sql,
-            attrs,
-            self.inner.execute_many(query)
-        )
+        let writes = crate::queue::writes(sqlx::Execute::sql(&query));
+        crate::queue::stream(self.writer_queue.as_ref(), writes, || {
+            let attrs = &self.attributes;
+            let query = crate::span::Query::new(query, attrs);
+            let sql = &query.sql();
+            crate::exec_stream!(
+                crate::Operation::ExecuteMany,
+                sql,
+                attrs,
+                query.execute_many(crate::pool::OwnedPool::new(self.inner())),
+                |_| false
+            )
+        })
     }
 
     fn fetch<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::stream::BoxStream<'e, Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>>
+    ) -> futures_core::stream::BoxStream<
+        'e,
+        Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>,
+    >
     where
         E: 'q + sqlx::Execute<'q, Self::Database>,
     {
-        let sql = query.sql();
-        let attrs = &self.attributes;
-        crate::exec_stream!("sqlx.fetch", sql, attrs, self.inner.fetch(query))
+        let writes = crate::queue::writes(sqlx::Execute::sql(&query));
+        crate::queue::stream(self.writer_queue.as_ref(), writes, || {
+            let attrs = &self.attributes;
+            let query = crate::span::Query::new(query, attrs);
+            let sql = &query.sql();
+            crate::exec_stream!(
+                crate::Operation::Fetch,
+                sql,
+                attrs,
+                crate::stream::measure_rows::<DB, _>(
+                    query.fetch(crate::pool::OwnedPool::new(self.inner())),
+                    attrs
+                ),
+                |_| true
+            )
+        })
     }
 
     fn fetch_all<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<Vec<<Self::Database as sqlx::Database>::Row>, sqlx::Error>,
     >
     where
         E: 'q + sqlx::Execute<'q, Self::Database>,
     {
-        let sql = query.sql();
-        let attrs = &self.attributes;
-        crate::exec_fut_rows!(sql, attrs, self.inner.fetch_all(query))
+        let writes = crate::queue::writes(sqlx::Execute::sql(&query));
+        crate::queue::future(self.writer_queue.as_ref(), writes, || {
+            let attrs = &self.attributes;
+            let (query, sample) = crate::explain::sample(query, attrs);
+            let query = crate::span::Query::new(query, attrs);
+            let sql = &query.sql();
+            crate::explain::after(
+                &self.inner,
+                attrs,
+                sample,
+                sql,
+                crate::exec_fut_rows!(
+                    sql,
+                    attrs,
+                    query.fetch_all(crate::pool::OwnedPool::new(self.inner()))
+                ),
+            )
+        })
     }
 
     fn fetch_many<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::stream::BoxStream<
+    ) -> futures_core::stream::BoxStream<
         'e,
         Result<
             sqlx::Either<
@@ -95,63 +537,256 @@ where
     where
         E: 'q + sqlx::Execute<'q, Self::Database>,
     {
-        let sql = query.sql();
-        let attrs = &self.attributes;
-        crate::exec_stream!("sqlx.fetch_many", sql, attrs, self.inner.fetch_many(query))
+        let writes = crate::queue::writes(sqlx::Execute::sql(&query));
+        crate::queue::stream(self.writer_queue.as_ref(), writes, || {
+            let attrs = &self.attributes;
+            let query = crate::span::Query::new(query, attrs);
+            let sql = &query.sql();
+            crate::exec_stream!(
+                crate::Operation::FetchMany,
+                sql,
+                attrs,
+                crate::stream::measure_results::<DB, _>(
+                    query.fetch_many(crate::pool::OwnedPool::new(self.inner())),
+                    attrs
+                ),
+                sqlx::Either::is_right
+            )
+        })
     }
 
     fn fetch_one<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<'e, Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>>
+    ) -> futures_core::future::BoxFuture<
+        'e,
+        Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>,
+    >
+    where
+        E: 'q + sqlx::Execute<'q, Self::Database>,
+    {
+        let writes = crate::queue::writes(sqlx::Execute::sql(&query));
+        crate::queue::future(self.writer_queue.as_ref(), writes, || {
+            let attrs = &self.attributes;
+            let (query, sample) = crate::explain::sample(query, attrs);
+            let query = crate::span::Query::new(query, attrs);
+            let sql = &query.sql();
+            crate::explain::after(
+                &self.inner,
+                attrs,
+                sample,
+                sql,
+                crate::exec_fut_one!(
+                    sql,
+                    attrs,
+                    query.fetch_one(crate::pool::OwnedPool::new(self.inner()))
+                ),
+            )
+        })
+    }
+
+    fn fetch_optional<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> futures_core::future::BoxFuture<
+        'e,
+        Result<Option<<Self::Database as sqlx::Database>::Row>, sqlx::Error>,
+    >
+    where
+        E: 'q + sqlx::Execute<'q, Self::Database>,
+    {
+        let writes = crate::queue::writes(sqlx::Execute::sql(&query));
+        crate::queue::future(self.writer_queue.as_ref(), writes, || {
+            let attrs = &self.attributes;
+            let (query, sample) = crate::explain::sample(query, attrs);
+            let query = crate::span::Query::new(query, attrs);
+            let sql = &query.sql();
+            crate::explain::after(
+                &self.inner,
+                attrs,
+                sample,
+                sql,
+                crate::exec_fut_opt!(
+                    sql,
+                    attrs,
+                    query.fetch_optional(crate::pool::OwnedPool::new(self.inner()))
+                ),
+            )
+        })
+    }
+
+    fn prepare<'e, 'q: 'e>(
+        self,
+        query: &'q str,
+    ) -> futures_core::future::BoxFuture<
+        'e,
+        Result<<Self::Database as sqlx::Database>::Statement<'q>, sqlx::Error>,
+    > {
+        crate::queue::future(self.writer_queue.as_ref(), false, || {
+            let attrs = &self.attributes;
+            crate::exec_fut!(
+                crate::Operation::Prepare,
+                query,
+                attrs,
+                crate::span::prepared(query, self.inner.prepare(query))
+            )
+        })
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [<Self::Database as sqlx::Database>::TypeInfo],
+    ) -> futures_core::future::BoxFuture<
+        'e,
+        Result<<Self::Database as sqlx::Database>::Statement<'q>, sqlx::Error>,
+    > {
+        crate::queue::future(self.writer_queue.as_ref(), false, || {
+            let attrs = &self.attributes;
+            crate::exec_fut!(
+                crate::Operation::PrepareWith,
+                sql,
+                attrs,
+                crate::span::prepared(sql, self.inner.prepare_with(sql, parameters))
+            )
+        })
+    }
+}
+
+/// Owned pools run statements like borrowed ones, so a clone can be handed to
+/// APIs taking an executor by value, e.g. from framework state holding an
+/// `Arc<Pool>`.
+impl<'p, DB> sqlx::Executor<'p> for crate::Pool<DB>
+where
+    DB: sqlx::Database + crate::prelude::Database,
+    for<'c> &'c mut DB::Connection: sqlx::Executor<'c, Database = DB>,
+{
+    type Database = DB;
+
+    #[doc(hidden)]
+    fn describe<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+    ) -> futures_core::future::BoxFuture<'e, Result<sqlx::Describe<Self::Database>, sqlx::Error>>
+    {
+        (&self).describe(sql)
+    }
+
+    fn execute<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> futures_core::future::BoxFuture<
+        'e,
+        Result<<Self::Database as sqlx::Database>::QueryResult, sqlx::Error>,
+    >
+    where
+        E: 'q + sqlx::Execute<'q, Self::Database>,
+    {
+        (&self).execute(query)
+    }
+
+    fn execute_many<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> futures_core::stream::BoxStream<
+        'e,
+        Result<<Self::Database as sqlx::Database>::QueryResult, sqlx::Error>,
+    >
+    where
+        E: 'q + sqlx::Execute<'q, Self::Database>,
+    {
+        (&self).execute_many(query)
+    }
+
+    fn fetch<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> futures_core::stream::BoxStream<
+        'e,
+        Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>,
+    >
+    where
+        E: 'q + sqlx::Execute<'q, Self::Database>,
+    {
+        (&self).fetch(query)
+    }
+
+    fn fetch_all<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> futures_core::future::BoxFuture<
+        'e,
+        Result<Vec<<Self::Database as sqlx::Database>::Row>, sqlx::Error>,
+    >
+    where
+        E: 'q + sqlx::Execute<'q, Self::Database>,
+    {
+        (&self).fetch_all(query)
+    }
+
+    fn fetch_many<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> futures_core::stream::BoxStream<
+        'e,
+        Result<
+            sqlx::Either<
+                <Self::Database as sqlx::Database>::QueryResult,
+                <Self::Database as sqlx::Database>::Row,
+            >,
+            sqlx::Error,
+        >,
+    >
+    where
+        E: 'q + sqlx::Execute<'q, Self::Database>,
+    {
+        (&self).fetch_many(query)
+    }
+
+    fn fetch_one<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> futures_core::future::BoxFuture<
+        'e,
+        Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>,
+    >
     where
         E: 'q + sqlx::Execute<'q, Self::Database>,
     {
-        let sql = query.sql();
-        let attrs = &self.attributes;
-        crate::exec_fut_one!(sql, attrs, self.inner.fetch_one(query))
+        (&self).fetch_one(query)
     }
 
     fn fetch_optional<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<Option<<Self::Database as sqlx::Database>::Row>, sqlx::Error>,
     >
     where
         E: 'q + sqlx::Execute<'q, Self::Database>,
     {
-        let sql = query.sql();
-        let attrs = &self.attributes;
-        crate::exec_fut_opt!(sql, attrs, self.inner.fetch_optional(query))
+        (&self).fetch_optional(query)
     }
 
     fn prepare<'e, 'q: 'e>(
         self,
         query: &'q str,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<<Self::Database as sqlx::Database>::Statement<'q>, sqlx::Error>,
     > {
-        let attrs = &self.attributes;
-        crate::exec_fut!("sqlx.prepare", query, attrs, self.inner.prepare(query))
+        (&self).prepare(query)
     }
 
     fn prepare_with<'e, 'q: 'e>(
         self,
         sql: &'q str,
         parameters: &'e [<Self::Database as sqlx::Database>::TypeInfo],
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<<Self::Database as sqlx::Database>::Statement<'q>, sqlx::Error>,
     > {
-        let attrs = &self.attributes;
-        crate::exec_fut!(
-            "sqlx.prepare_with",
-            sql,
-            attrs,
-            self.inner.prepare_with(sql, parameters)
-        )
+        (&self).prepare_with(sql, parameters)
     }
 }
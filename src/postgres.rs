@@ -1,3 +1,879 @@
+use tracing::Instrument;
+use tracing::instrument::WithSubscriber;
+
 impl crate::prelude::Database for sqlx::Postgres {
     const SYSTEM: &'static str = "postgresql";
+    const MAX_BIND_PARAMETERS: usize = u16::MAX as usize;
+
+    fn default_port() -> Option<u16> {
+        Some(5432)
+    }
+
+    fn connection_attributes(
+        options: &sqlx::postgres::PgConnectOptions,
+    ) -> crate::AttributesBuilder {
+        let ssl_mode = options.get_ssl_mode();
+        // A comma-separated list of hosts lists failover candidates.
+        let hosts: Vec<&str> = options.get_host().split(',').collect();
+        let builder = crate::Attributes::builder()
+            .with_host(hosts[0])
+            .with_port(options.get_port())
+            .with_user(options.get_username())
+            .with_ssl_mode(ssl_mode_name(ssl_mode))
+            .with_statement_cache_capacity(statement_cache_capacity(options));
+        let builder = if hosts.len() > 1 {
+            builder.with_hosts(hosts)
+        } else {
+            builder
+        };
+        let builder = match options.get_database() {
+            Some(database) => builder.with_database(database),
+            None => builder,
+        };
+        match tls_established(ssl_mode) {
+            Some(tls) => builder.with_tls(tls),
+            None => builder,
+        }
+    }
+
+    fn connection_string(options: &sqlx::postgres::PgConnectOptions) -> Option<String> {
+        Some(crate::options::sanitized_url(options, &["sslkey"]))
+    }
+
+    fn execute_builder<'e>(
+        conn: &'e mut sqlx::PgConnection,
+        builder: &'e mut sqlx::QueryBuilder<'_, Self>,
+    ) -> futures_core::future::BoxFuture<'e, Result<sqlx::postgres::PgQueryResult, sqlx::Error>>
+    {
+        sqlx::Executor::execute(conn, builder.build())
+    }
+
+    fn rows_affected(result: &sqlx::postgres::PgQueryResult) -> u64 {
+        result.rows_affected()
+    }
+
+    fn row_size(row: &sqlx::postgres::PgRow) -> u64 {
+        use sqlx::{Row, ValueRef};
+        (0..row.len())
+            .filter_map(|index| row.try_get_raw(index).ok())
+            .filter(|value| !value.is_null())
+            .map(|value| {
+                <&[u8] as sqlx::Decode<sqlx::Postgres>>::decode(value)
+                    .map_or(0, |bytes| bytes.len() as u64)
+            })
+            .sum()
+    }
+
+    fn server_address(
+        conn: &mut sqlx::PgConnection,
+    ) -> Option<futures_core::future::BoxFuture<'_, Result<Option<String>, sqlx::Error>>> {
+        Some(Box::pin(
+            sqlx::query_scalar("SELECT host(inet_server_addr())").fetch_one(conn),
+        ))
+    }
+
+    fn record_result(result: &sqlx::postgres::PgQueryResult, _record_metadata: bool) {
+        tracing::Span::current().record("db.response.affected_rows", result.rows_affected());
+    }
+
+    fn set_statement_timeout(
+        conn: &mut sqlx::PgConnection,
+        timeout: std::time::Duration,
+    ) -> Option<futures_core::future::BoxFuture<'_, Result<(), sqlx::Error>>> {
+        // `SET` takes no bind parameters, and a timeout of 0 disables it.
+        let sql = format!(
+            "SET LOCAL statement_timeout = {}",
+            timeout.as_millis().max(1)
+        );
+        Some(Box::pin(async move {
+            sqlx::Executor::execute(conn, sqlx::raw_sql(&sql))
+                .await
+                .map(drop)
+        }))
+    }
+
+    fn reborrow_arguments<'a, 'q: 'a>(
+        arguments: <Self as sqlx::Database>::Arguments<'q>,
+    ) -> Result<<Self as sqlx::Database>::Arguments<'a>, <Self as sqlx::Database>::Arguments<'q>>
+    {
+        Ok(arguments)
+    }
+
+    fn clone_arguments<'q>(
+        arguments: &<Self as sqlx::Database>::Arguments<'q>,
+    ) -> Option<<Self as sqlx::Database>::Arguments<'q>> {
+        Some(arguments.clone())
+    }
+
+    fn explain(
+        pool: &sqlx::PgPool,
+        attrs: &std::sync::Arc<crate::Attributes>,
+        sql: &str,
+        arguments: Option<sqlx::postgres::PgArguments>,
+    ) {
+        type DB = sqlx::Postgres;
+
+        let record_details = attrs.config().error_details();
+        let span = crate::instrument!(
+            crate::Operation::Explain,
+            sql,
+            attrs,
+            "db.postgres.plan_cost" = ::tracing::field::Empty,
+            "db.postgres.rows_estimated" = ::tracing::field::Empty,
+            "db.postgres.rows_examined" = ::tracing::field::Empty,
+            "db.postgres.execution_time_ms" = ::tracing::field::Empty,
+        );
+        let explain = if attrs.config().explain_analyze {
+            format!("EXPLAIN (ANALYZE, FORMAT TEXT) {sql}")
+        } else {
+            format!("EXPLAIN (FORMAT TEXT) {sql}")
+        };
+        let pool = pool.clone();
+        // Runs the plan off the request path, reporting to the subscriber of
+        // the statement.
+        crate::explain::spawn(
+            async move {
+                use sqlx::Row;
+
+                let query = match arguments {
+                    Some(arguments) => sqlx::query_with(&explain, arguments),
+                    None => sqlx::query(&explain),
+                };
+                match query.fetch_all(&pool).await {
+                    Ok(rows) => {
+                        let plan = rows
+                            .iter()
+                            .filter_map(|row| row.try_get::<String, _>(0).ok())
+                            .collect::<Vec<_>>();
+                        record_plan(&plan);
+                    }
+                    Err(e) => crate::span::record_error(&e, &record_details),
+                }
+            }
+            .instrument(span)
+            .with_current_subscriber(),
+        );
+    }
+}
+
+/// Records the statistics of an `EXPLAIN` text plan on the current span.
+///
+/// The estimates are those of the top node. With `ANALYZE`, rows examined
+/// are the rows read by scan nodes, including those removed by filters, over
+/// all loops; the returned rows are those of the top node.
+fn record_plan(plan: &[String]) {
+    let span = tracing::Span::current();
+    if let Some(estimate) = plan
+        .first()
+        .and_then(|line| line.split_once("(cost="))
+        .and_then(|(_, estimate)| estimate.split_once(')'))
+        .map(|(estimate, _)| estimate)
+    {
+        let mut parts = estimate.split_whitespace();
+        if let Some(cost) = parts
+            .next()
+            .and_then(|cost| cost.split_once(".."))
+            .and_then(|(_, total)| total.parse::<f64>().ok())
+        {
+            span.record("db.postgres.plan_cost", cost);
+        }
+        if let Some(rows) = parts
+            .find_map(|part| part.strip_prefix("rows="))
+            .and_then(|rows| rows.parse::<u64>().ok())
+        {
+            span.record("db.postgres.rows_estimated", rows);
+        }
+    }
+    let mut examined = None;
+    let mut returned = None;
+    let mut scan_loops = None;
+    for line in plan {
+        let line = line.trim();
+        if let Some((node, actual)) = line.split_once("(actual ") {
+            let value = |name: &str| {
+                actual
+                    .split_whitespace()
+                    .find_map(|part| part.strip_prefix(name))
+                    .and_then(|value| value.trim_end_matches(')').parse::<u64>().ok())
+            };
+            let (rows, loops) = (value("rows=").unwrap_or(0), value("loops=").unwrap_or(1));
+            returned.get_or_insert(rows * loops);
+            scan_loops = node.contains(" Scan").then_some(loops);
+            if scan_loops.is_some() {
+                *examined.get_or_insert(0) += rows * loops;
+            }
+        } else if let Some(removed) = line
+            .strip_prefix("Rows Removed by Filter: ")
+            .or_else(|| line.strip_prefix("Rows Removed by Index Recheck: "))
+            && let (Some(loops), Ok(removed)) = (scan_loops, removed.parse::<u64>())
+        {
+            *examined.get_or_insert(0) += removed * loops;
+        } else if let Some(time) = line
+            .strip_prefix("Execution Time: ")
+            .and_then(|time| time.strip_suffix(" ms"))
+            .and_then(|time| time.parse::<f64>().ok())
+        {
+            span.record("db.postgres.execution_time_ms", time);
+        }
+    }
+    if let Some(examined) = examined {
+        span.record("db.postgres.rows_examined", examined);
+    }
+    if let Some(returned) = returned {
+        span.record("db.response.returned_rows", returned);
+    }
+}
+
+/// Returns the statement cache capacity of `options`, which SQLx only exposes
+/// in their URL.
+fn statement_cache_capacity(options: &sqlx::postgres::PgConnectOptions) -> usize {
+    use sqlx::ConnectOptions;
+
+    options
+        .to_url_lossy()
+        .query_pairs()
+        .find(|(key, _)| key == "statement-cache-capacity")
+        .and_then(|(_, value)| value.parse().ok())
+        // SQLx's default
+        .unwrap_or(100)
+}
+
+/// Returns the libpq name of an SSL mode, as recorded in `db.ssl_mode`.
+pub(crate) fn ssl_mode_name(mode: sqlx::postgres::PgSslMode) -> &'static str {
+    use sqlx::postgres::PgSslMode;
+
+    match mode {
+        PgSslMode::Disable => "disable",
+        PgSslMode::Allow => "allow",
+        PgSslMode::Prefer => "prefer",
+        PgSslMode::Require => "require",
+        PgSslMode::VerifyCa => "verify-ca",
+        PgSslMode::VerifyFull => "verify-full",
+    }
+}
+
+/// Returns whether connections made with `mode` are known to use TLS.
+///
+/// `allow` and `prefer` fall back between plain and encrypted connections
+/// depending on the server, so the outcome is unknown and `None` is returned.
+pub(crate) fn tls_established(mode: sqlx::postgres::PgSslMode) -> Option<bool> {
+    use sqlx::postgres::PgSslMode;
+
+    match mode {
+        PgSslMode::Disable => Some(false),
+        PgSslMode::Allow | PgSslMode::Prefer => None,
+        PgSslMode::Require | PgSslMode::VerifyCa | PgSslMode::VerifyFull => Some(true),
+    }
+}
+
+/// Size of the buffer accumulated before sending COPY data to the server.
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+impl crate::PoolBuilder<sqlx::Postgres> {
+    /// Run `EXPLAIN` for one in every `every` `SELECT` statements executed
+    /// through the pool's `fetch_all`, `fetch_one` and `fetch_optional`.
+    ///
+    /// Once the original statement succeeded, each sampled statement is
+    /// explained in a background task, which does not delay the statement,
+    /// on a `sqlx.explain` span recording the planner's estimates of the top
+    /// node as `db.postgres.plan_cost` and `db.postgres.rows_estimated`.
+    /// Other statements do not count towards the rate. A rate of `0` disables
+    /// sampling.
+    ///
+    /// The background task needs the `runtime-tokio` or `runtime-async-std`
+    /// feature. Without them, or outside of a tokio runtime with only
+    /// `runtime-tokio`, no statement is sampled.
+    ///
+    /// Disabled by default.
+    pub fn with_explain_sampling(mut self, every: u64) -> Self {
+        self.config.explain_sampling = std::num::NonZeroU64::new(every);
+        self
+    }
+
+    /// Explain the statements sampled with
+    /// [`with_explain_sampling`](Self::with_explain_sampling) with
+    /// `EXPLAIN ANALYZE`.
+    ///
+    /// The `sqlx.explain` span then also records the rows read by scans as
+    /// `db.postgres.rows_examined` next to the rows returned, which exposes
+    /// inefficient scans, and the server-side execution time as
+    /// `db.postgres.execution_time_ms`. The sampled statement runs a second
+    /// time, so keep the rate low.
+    ///
+    /// Disabled by default.
+    pub fn with_explain_analyze(mut self, enabled: bool) -> Self {
+        self.config.explain_analyze = enabled;
+        self
+    }
+}
+
+impl crate::Pool<sqlx::Postgres> {
+    /// Loads `rows` into `table` using `COPY ... FROM STDIN`.
+    ///
+    /// Each row yields one value per column, with `None` written as `NULL`.
+    /// Values are escaped for the COPY text format. Returns the number of rows
+    /// copied.
+    ///
+    /// The table and column names are quoted as identifiers, so they are
+    /// case-sensitive: `Users` names the table `"Users"`, not `users`. Each
+    /// part of a schema-qualified table name such as `public.users` is quoted
+    /// separately, while column names are quoted whole.
+    ///
+    /// The load is wrapped in a `sqlx.bulk_copy` span recording the rows
+    /// (`db.bulk.rows`), bytes (`db.bulk.bytes`) and throughput
+    /// (`db.bulk.rows_per_sec`) of the copy.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let rows = vec![[Some("Alice"), Some("30")], [Some("Bob"), None]];
+    /// pool.bulk_copy("users", &["name", "age"], rows).await?;
+    /// ```
+    pub async fn bulk_copy<R, S>(
+        &self,
+        table: &str,
+        columns: &[&str],
+        rows: impl IntoIterator<Item = R>,
+    ) -> Result<u64, sqlx::Error>
+    where
+        R: IntoIterator<Item = Option<S>>,
+        S: AsRef<str>,
+    {
+        let mut conn = self.acquire().await?;
+        conn.bulk_copy(table, columns, rows).await
+    }
+}
+
+impl crate::Pool<sqlx::Postgres> {
+    /// Runs `VACUUM` on `table`, or on every table of the database when
+    /// `None`.
+    ///
+    /// The statement is wrapped in a `sqlx.maintenance.vacuum` span recording
+    /// the table as `db.sql.table` and the time it took as
+    /// `db.maintenance.duration_ms`. The table name is quoted as an
+    /// identifier, so it is case-sensitive; each part of a schema-qualified
+    /// name such as `public.users` is quoted separately.
+    pub async fn vacuum(&self, table: Option<&str>) -> Result<(), sqlx::Error> {
+        type DB = sqlx::Postgres;
+
+        let sql = maintenance_statement("VACUUM", table);
+        let span = crate::maintenance_span!(crate::Operation::Vacuum, self.attributes, table);
+        self.maintain(span, &sql).await
+    }
+
+    /// Runs `ANALYZE` on `table`, or on every table of the database when
+    /// `None`.
+    ///
+    /// The statement is wrapped in a `sqlx.maintenance.analyze` span, see
+    /// [`Pool::vacuum`](crate::Pool::vacuum).
+    pub async fn analyze(&self, table: Option<&str>) -> Result<(), sqlx::Error> {
+        type DB = sqlx::Postgres;
+
+        let sql = maintenance_statement("ANALYZE", table);
+        let span = crate::maintenance_span!(crate::Operation::Analyze, self.attributes, table);
+        self.maintain(span, &sql).await
+    }
+
+    /// Rebuilds the indexes of `table` with `REINDEX TABLE`.
+    ///
+    /// The statement is wrapped in a `sqlx.maintenance.reindex` span, see
+    /// [`Pool::vacuum`](crate::Pool::vacuum).
+    pub async fn reindex(&self, table: &str) -> Result<(), sqlx::Error> {
+        type DB = sqlx::Postgres;
+
+        let sql = format!("REINDEX TABLE {}", quote_table(table));
+        let span = crate::maintenance_span!(crate::Operation::Reindex, self.attributes, table);
+        self.maintain(span, &sql).await
+    }
+}
+
+/// Returns the `command` maintenance statement for `table`, or for the whole
+/// database.
+fn maintenance_statement(command: &str, table: Option<&str>) -> String {
+    match table {
+        Some(table) => format!("{command} {}", quote_table(table)),
+        None => command.to_owned(),
+    }
+}
+
+impl crate::Pool<sqlx::Postgres> {
+    /// Creates a [`PgListener`] on a dedicated connection of this pool, to
+    /// receive `NOTIFY` messages.
+    pub async fn listener(&self) -> Result<PgListener, sqlx::Error> {
+        type DB = sqlx::Postgres;
+
+        let attrs = &self.attributes;
+        let record_details = attrs.config().error_details();
+        let span = crate::instrument_op!(crate::Operation::ListenerConnect, attrs);
+        async {
+            sqlx::postgres::PgListener::connect_with(&self.inner)
+                .await
+                .map(|inner| PgListener {
+                    inner,
+                    attributes: self.attributes.clone(),
+                })
+                .inspect_err(|e| crate::span::record_error(e, &record_details))
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+/// Traced wrapper around [`sqlx::postgres::PgListener`], created with
+/// [`Pool::listener`](crate::Pool::listener).
+///
+/// `LISTEN` and `UNLISTEN` commands get `sqlx.listen` and `sqlx.unlisten`
+/// spans recording the channel as `messaging.destination.name`.
+#[derive(Debug)]
+pub struct PgListener {
+    inner: sqlx::postgres::PgListener,
+    attributes: std::sync::Arc<crate::Attributes>,
+}
+
+impl PgListener {
+    /// Starts listening for notifications on `channel`.
+    pub async fn listen(&mut self, channel: &str) -> Result<(), sqlx::Error> {
+        type DB = sqlx::Postgres;
+
+        let attrs = &self.attributes;
+        let span = crate::instrument_op!(
+            crate::Operation::Listen,
+            attrs,
+            "messaging.destination.name" = channel
+        );
+        let record_details = self.attributes.config().error_details();
+        self.inner
+            .listen(channel)
+            .instrument(span)
+            .await
+            .inspect_err(|e| crate::span::record_error(e, &record_details))
+    }
+
+    /// Stops listening for notifications on `channel`.
+    pub async fn unlisten(&mut self, channel: &str) -> Result<(), sqlx::Error> {
+        type DB = sqlx::Postgres;
+
+        let attrs = &self.attributes;
+        let span = crate::instrument_op!(
+            crate::Operation::Unlisten,
+            attrs,
+            "messaging.destination.name" = channel
+        );
+        let record_details = self.attributes.config().error_details();
+        self.inner
+            .unlisten(channel)
+            .instrument(span)
+            .await
+            .inspect_err(|e| crate::span::record_error(e, &record_details))
+    }
+
+    /// Listens on `channel` and returns the stream of notifications received
+    /// by this listener, including those of channels listened to before.
+    ///
+    /// Each notification gets its own `sqlx.notification` consumer span
+    /// recording the channel (`messaging.destination.name`), the payload size
+    /// (`messaging.message.body.size`) and, when the payload is a JSON object
+    /// with a numeric `timestamp` field holding Unix epoch seconds, the
+    /// delivery latency (`db.postgres.notification.latency_ms`). The span is
+    /// returned with the notification, so its processing can be traced as a
+    /// child of it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut notifications = pool.listener().await?.notifications("events").await?;
+    /// while let Some(notification) = notifications.try_next().await? {
+    ///     handle(notification.payload())
+    ///         .instrument(notification.span().clone())
+    ///         .await;
+    /// }
+    /// ```
+    pub async fn notifications(
+        mut self,
+        channel: &str,
+    ) -> Result<
+        futures_core::stream::BoxStream<'static, Result<Notification, sqlx::Error>>,
+        sqlx::Error,
+    > {
+        self.listen(channel).await?;
+        Ok(Box::pin(futures_util::stream::unfold(
+            self,
+            |mut listener| async move {
+                let res = listener.inner.recv().await;
+                let res = res.map(|inner| {
+                    let span = listener.notification_span(&inner);
+                    Notification { inner, span }
+                });
+                Some((res, listener))
+            },
+        )))
+    }
+
+    /// Returns the underlying SQLx listener, bypassing tracing.
+    pub fn inner(&mut self) -> &mut sqlx::postgres::PgListener {
+        &mut self.inner
+    }
+
+    fn notification_span(&self, notification: &sqlx::postgres::PgNotification) -> tracing::Span {
+        if cfg!(feature = "disabled") {
+            return tracing::Span::none();
+        }
+        let attrs = &self.attributes;
+        let span = tracing::info_span!(
+            crate::Operation::Notification.span_name(),
+            "db.client.pool.name" = attrs.pool_name.as_deref(),
+            "db.name" = attrs.database,
+            "db.system.name" = attrs.system_name::<sqlx::Postgres>(),
+            "db.postgres.notification.process_id" = notification.process_id(),
+            "db.postgres.notification.latency_ms" = ::tracing::field::Empty,
+            "messaging.destination.name" = notification.channel(),
+            "messaging.message.body.size" = notification.payload().len(),
+            "net.peer.name" = attrs.host,
+            "net.peer.port" = attrs.port,
+            "otel.kind" = "consumer",
+            "peer.service" = attrs.name,
+            "sqlx.context" = ::tracing::field::Empty,
+        );
+        crate::span::record_context(&span, attrs);
+        if let Some(latency) = delivery_latency(notification.payload()) {
+            span.record("db.postgres.notification.latency_ms", latency);
+        }
+        span
+    }
+}
+
+/// Notification received by a [`PgListener`], with the consumer span
+/// created on receipt.
+#[derive(Debug)]
+pub struct Notification {
+    inner: sqlx::postgres::PgNotification,
+    span: tracing::Span,
+}
+
+impl Notification {
+    /// Returns the channel the notification was sent on.
+    pub fn channel(&self) -> &str {
+        self.inner.channel()
+    }
+
+    /// Returns the payload of the notification.
+    pub fn payload(&self) -> &str {
+        self.inner.payload()
+    }
+
+    /// Returns the `sqlx.notification` span of the notification.
+    pub fn span(&self) -> &tracing::Span {
+        &self.span
+    }
+
+    /// Returns the underlying SQLx notification.
+    pub fn into_inner(self) -> sqlx::postgres::PgNotification {
+        self.inner
+    }
+}
+
+/// Returns the time elapsed in milliseconds since the `timestamp` embedded in
+/// a JSON payload, e.g. `{"timestamp": 1760000000.123, ...}`, as produced by
+/// `extract(epoch from clock_timestamp())`.
+fn delivery_latency(payload: &str) -> Option<f64> {
+    let payload = payload.trim_start();
+    if !payload.starts_with('{') {
+        return None;
+    }
+    let (_, rest) = payload.split_once("\"timestamp\"")?;
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    let end = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(rest.len());
+    let sent = rest[..end].parse::<f64>().ok()?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_secs_f64();
+    Some(((now - sent) * 1000.0).max(0.0))
+}
+
+impl crate::PoolConnection<sqlx::Postgres> {
+    /// Loads `rows` into `table` using `COPY ... FROM STDIN`.
+    ///
+    /// See [`Pool::bulk_copy`](crate::Pool::bulk_copy).
+    pub async fn bulk_copy<R, S>(
+        &mut self,
+        table: &str,
+        columns: &[&str],
+        rows: impl IntoIterator<Item = R>,
+    ) -> Result<u64, sqlx::Error>
+    where
+        R: IntoIterator<Item = Option<S>>,
+        S: AsRef<str>,
+    {
+        let attributes = self.attributes.clone();
+        let lease = self.lease.clone();
+        bulk_copy(&mut self.inner, &attributes, table, columns, rows)
+            .instrument(lease)
+            .await
+    }
+}
+
+impl crate::Transaction<'_, sqlx::Postgres> {
+    /// Loads `rows` into `table` using `COPY ... FROM STDIN` within this transaction.
+    ///
+    /// See [`Pool::bulk_copy`](crate::Pool::bulk_copy).
+    pub async fn bulk_copy<R, S>(
+        &mut self,
+        table: &str,
+        columns: &[&str],
+        rows: impl IntoIterator<Item = R>,
+    ) -> Result<u64, sqlx::Error>
+    where
+        R: IntoIterator<Item = Option<S>>,
+        S: AsRef<str>,
+    {
+        let attributes = self.attributes.clone();
+        let idle = crate::idle::IdleTracker::resume(Some(&self.idle));
+        let res = bulk_copy(&mut self.inner, &attributes, table, columns, rows).await;
+        if let Some(idle) = idle {
+            idle.pause();
+        }
+        res
+    }
+}
+
+impl crate::Transaction<'_, sqlx::Postgres> {
+    /// Prepares this transaction for two-phase commit under the global
+    /// transaction id `gid`, with `PREPARE TRANSACTION`.
+    ///
+    /// This consumes the `Transaction` and releases its connection: the
+    /// prepared transaction outlives it on the server until it is resolved
+    /// with [`Pool::commit_prepared`](crate::Pool::commit_prepared) or
+    /// [`Pool::rollback_prepared`](crate::Pool::rollback_prepared), possibly
+    /// from another process. The server must allow it with a non-zero
+    /// `max_prepared_transactions`, and savepoints cannot be prepared.
+    ///
+    /// The statement is wrapped in a `sqlx.transaction.prepare` span
+    /// recording `gid` as `db.transaction.gid`, along with the fields of the
+    /// [`commit`](crate::Transaction::commit) span.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut tx = pool.begin().await?;
+    /// sqlx::query("UPDATE accounts SET balance = balance - 10 WHERE id = 1")
+    ///     .execute(&mut tx.executor())
+    ///     .await?;
+    /// tx.prepare_twophase("transfer-42").await?;
+    /// // Once every participant is prepared:
+    /// pool.commit_prepared("transfer-42").await?;
+    /// ```
+    pub async fn prepare_twophase(mut self, gid: &str) -> Result<(), sqlx::Error> {
+        type DB = sqlx::Postgres;
+
+        if self.idle.depth() > 0 {
+            return Err(sqlx::Error::InvalidArgument(
+                "a savepoint cannot be prepared for two-phase commit".to_owned(),
+            ));
+        }
+        let attrs = self.attributes.clone();
+        let record_details = attrs.config().error_details();
+        let idle = self.idle.finish().as_millis() as u64;
+        let span = crate::instrument_op!(
+            crate::Operation::PrepareTransaction,
+            attrs,
+            "db.transaction.gid" = gid,
+            "db.transaction.idle_ms" = idle,
+            "db.transaction.savepoints" =
+                self.savepoints.load(std::sync::atomic::Ordering::Relaxed),
+            "db.transaction.label" = self.idle.label(),
+        );
+        async {
+            let sql = format!("PREPARE TRANSACTION {}", quote_literal(gid));
+            let res = match sqlx::Executor::execute(&mut *self.inner, sql.as_str()).await {
+                // The transaction is no longer open on the connection, so this
+                // `COMMIT` is a no-op closing the SQLx transaction.
+                Ok(_) => self.inner.commit().await,
+                Err(err) => Err(err),
+            };
+            res.inspect_err(|e| crate::span::record_error(e, &record_details))
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+impl crate::Pool<sqlx::Postgres> {
+    /// Commits the transaction prepared under the global transaction id
+    /// `gid` with [`Transaction::prepare_twophase`](crate::Transaction::prepare_twophase),
+    /// with `COMMIT PREPARED`.
+    ///
+    /// The statement is wrapped in a `sqlx.transaction.commit_prepared` span
+    /// recording `gid` as `db.transaction.gid`.
+    pub async fn commit_prepared(&self, gid: &str) -> Result<(), sqlx::Error> {
+        type DB = sqlx::Postgres;
+
+        let span = crate::instrument_op!(
+            crate::Operation::CommitPrepared,
+            self.attributes,
+            "db.transaction.gid" = gid,
+        );
+        self.resolve_prepared(span, "COMMIT PREPARED", gid).await
+    }
+
+    /// Rolls back the transaction prepared under the global transaction id
+    /// `gid`, with `ROLLBACK PREPARED`.
+    ///
+    /// The statement is wrapped in a `sqlx.transaction.rollback_prepared`
+    /// span, see [`Pool::commit_prepared`](crate::Pool::commit_prepared).
+    pub async fn rollback_prepared(&self, gid: &str) -> Result<(), sqlx::Error> {
+        type DB = sqlx::Postgres;
+
+        let span = crate::instrument_op!(
+            crate::Operation::RollbackPrepared,
+            self.attributes,
+            "db.transaction.gid" = gid,
+        );
+        self.resolve_prepared(span, "ROLLBACK PREPARED", gid).await
+    }
+
+    /// Runs `command` on the prepared transaction `gid` on a pooled
+    /// connection within `span`.
+    async fn resolve_prepared(
+        &self,
+        span: tracing::Span,
+        command: &str,
+        gid: &str,
+    ) -> Result<(), sqlx::Error> {
+        let record_details = self.attributes.config().error_details();
+        let sql = format!("{command} {}", quote_literal(gid));
+        async {
+            let res = match self.acquire().await {
+                Ok(mut conn) => sqlx::Executor::execute(&mut conn, sql.as_str())
+                    .await
+                    .map(drop),
+                Err(err) => Err(err),
+            };
+            res.inspect_err(|e| crate::span::record_error(e, &record_details))
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+/// Quotes `value` as an SQL string literal.
+fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Quotes `name` as an SQL identifier, as a whole.
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quotes the table `name` as an SQL identifier, quoting each part of a
+/// schema-qualified name separately.
+fn quote_table(name: &str) -> String {
+    name.split('.')
+        .map(quote_ident)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Runs the COPY within a `sqlx.bulk_copy` span and records its throughput.
+async fn bulk_copy<R, S>(
+    conn: &mut sqlx::PgConnection,
+    attrs: &crate::Attributes,
+    table: &str,
+    columns: &[&str],
+    rows: impl IntoIterator<Item = R>,
+) -> Result<u64, sqlx::Error>
+where
+    R: IntoIterator<Item = Option<S>>,
+    S: AsRef<str>,
+{
+    type DB = sqlx::Postgres;
+
+    let record_details = attrs.config().error_details();
+    let span = crate::instrument_op!(
+        crate::Operation::BulkCopy,
+        attrs,
+        "db.sql.table" = table,
+        "db.bulk.rows" = ::tracing::field::Empty,
+        "db.bulk.bytes" = ::tracing::field::Empty,
+        "db.bulk.rows_per_sec" = ::tracing::field::Empty
+    );
+    async {
+        let start = std::time::Instant::now();
+        let (rows, bytes) = copy_rows(conn, table, columns, rows)
+            .await
+            .inspect_err(|e| crate::span::record_error(e, &record_details))?;
+        let span = tracing::Span::current();
+        span.record("db.bulk.rows", rows);
+        span.record("db.bulk.bytes", bytes);
+        let elapsed = start.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            span.record("db.bulk.rows_per_sec", rows as f64 / elapsed);
+        }
+        Ok(rows)
+    }
+    .instrument(span)
+    .await
+}
+
+/// Streams `rows` to the server in COPY text format, returning the number of
+/// rows copied and the number of bytes sent.
+async fn copy_rows<R, S>(
+    conn: &mut sqlx::PgConnection,
+    table: &str,
+    columns: &[&str],
+    rows: impl IntoIterator<Item = R>,
+) -> Result<(u64, usize), sqlx::Error>
+where
+    R: IntoIterator<Item = Option<S>>,
+    S: AsRef<str>,
+{
+    let columns = columns
+        .iter()
+        .map(|column| quote_ident(column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let statement = format!("COPY {} ({columns}) FROM STDIN", quote_table(table));
+    let mut copy = conn.copy_in_raw(&statement).await?;
+    let mut buffer = String::with_capacity(COPY_BUFFER_SIZE);
+    let mut bytes = 0;
+    for row in rows {
+        for (index, value) in row.into_iter().enumerate() {
+            if index > 0 {
+                buffer.push('\t');
+            }
+            match value {
+                Some(value) => escape_copy_value(&mut buffer, value.as_ref()),
+                None => buffer.push_str("\\N"),
+            }
+        }
+        buffer.push('\n');
+        if buffer.len() >= COPY_BUFFER_SIZE {
+            bytes += buffer.len();
+            copy.send(buffer.as_bytes()).await?;
+            buffer.clear();
+        }
+    }
+    if !buffer.is_empty() {
+        bytes += buffer.len();
+        copy.send(buffer.into_bytes()).await?;
+    }
+    let rows = copy.finish().await?;
+    Ok((rows, bytes))
+}
+
+/// Appends `value` to `buffer`, escaping characters that are special in the
+/// COPY text format.
+fn escape_copy_value(buffer: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '\\' => buffer.push_str("\\\\"),
+            '\t' => buffer.push_str("\\t"),
+            '\n' => buffer.push_str("\\n"),
+            '\r' => buffer.push_str("\\r"),
+            c => buffer.push(c),
+        }
+    }
 }
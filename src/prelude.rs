@@ -1,3 +1,197 @@
+//! The items most code using the crate needs, in a single import.
+//!
+//! Besides the traced [`Pool`], [`PoolConnection`] and [`Transaction`], the
+//! SQLx query builders and row traits are re-exported from the SQLx release
+//! the crate is built with, so downstream code stays aligned with it:
+//!
+//! ```rust,ignore
+//! use sqlx_tracing::prelude::*;
+//!
+//! #[derive(FromRow)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! let users: Vec<User> = query_as("SELECT name FROM users").fetch_all(&pool).await?;
+//! ```
+//!
+//! It also holds the [`Database`] trait integrating a database driver.
+
+pub use sqlx::{FromRow, Row, query, query_as, query_scalar};
+
+pub use crate::{Pool, PoolConnection, Transaction};
+
 pub trait Database {
+    /// Value of the `db.system.name` span field.
     const SYSTEM: &'static str;
+    /// Maximum number of bind parameters accepted in a single statement.
+    const MAX_BIND_PARAMETERS: usize;
+
+    /// Returns the value of the `db.system.name` span field.
+    ///
+    /// Defaults to [`SYSTEM`](Self::SYSTEM).
+    fn system_name() -> &'static str {
+        Self::SYSTEM
+    }
+
+    /// Returns the port the database listens on by default, recorded as
+    /// `server.port` when the connect options don't specify one.
+    fn default_port() -> Option<u16> {
+        None
+    }
+
+    /// Extracts the connection attributes, such as host, port, database and
+    /// user, from the connect options of a pool.
+    ///
+    /// Defaults to empty attributes, which drivers should override to get
+    /// complete spans.
+    fn connection_attributes(
+        _options: &<<Self as sqlx::Database>::Connection as sqlx::Connection>::Options,
+    ) -> crate::AttributesBuilder
+    where
+        Self: sqlx::Database,
+    {
+        crate::Attributes::builder()
+    }
+
+    /// Returns the connection string of the connect options, with any
+    /// credentials removed, as recorded in `db.connection_string`.
+    fn connection_string(
+        _options: &<<Self as sqlx::Database>::Connection as sqlx::Connection>::Options,
+    ) -> Option<String>
+    where
+        Self: sqlx::Database,
+    {
+        None
+    }
+
+    /// Builds and executes the query held by `builder` on `conn`.
+    ///
+    /// Implemented per database because the borrow of the builder cannot be
+    /// expressed generically over `sqlx::Database::Arguments`.
+    #[doc(hidden)]
+    fn execute_builder<'e>(
+        conn: &'e mut <Self as sqlx::Database>::Connection,
+        builder: &'e mut sqlx::QueryBuilder<'_, Self>,
+    ) -> futures_core::future::BoxFuture<
+        'e,
+        Result<<Self as sqlx::Database>::QueryResult, sqlx::Error>,
+    >
+    where
+        Self: sqlx::Database;
+
+    /// Records the outcome of an `execute` call on the current span, such as
+    /// the number of affected rows. Database-specific metadata, like the last
+    /// inserted row id, is only recorded when `record_metadata` is set.
+    #[doc(hidden)]
+    fn record_result(result: &<Self as sqlx::Database>::QueryResult, record_metadata: bool)
+    where
+        Self: sqlx::Database;
+
+    /// Returns the number of rows affected by an `execute` call.
+    #[doc(hidden)]
+    fn rows_affected(result: &<Self as sqlx::Database>::QueryResult) -> u64
+    where
+        Self: sqlx::Database;
+
+    /// Returns the approximate size in bytes of the column values of `row`,
+    /// as recorded in `db.response.bytes`.
+    #[doc(hidden)]
+    fn row_size(row: &<Self as sqlx::Database>::Row) -> u64
+    where
+        Self: sqlx::Database;
+
+    /// Returns the address of the server `conn` is connected to, recorded as
+    /// `db.client.connection.server_address`, or `None` when the database
+    /// cannot tell it.
+    #[doc(hidden)]
+    fn server_address(
+        _conn: &mut <Self as sqlx::Database>::Connection,
+    ) -> Option<futures_core::future::BoxFuture<'_, Result<Option<String>, sqlx::Error>>>
+    where
+        Self: sqlx::Database,
+    {
+        None
+    }
+
+    /// Records the effective settings of the new connection `conn`, such as
+    /// SQLite's `PRAGMA journal_mode`, on `span`, or returns `None` when the
+    /// database has none worth recording.
+    #[doc(hidden)]
+    fn record_settings<'c>(
+        _conn: &'c mut <Self as sqlx::Database>::Connection,
+        _span: &tracing::Span,
+    ) -> Option<futures_core::future::BoxFuture<'c, Result<(), sqlx::Error>>>
+    where
+        Self: sqlx::Database,
+    {
+        None
+    }
+
+    /// Returns whether the writes of a pool with `options` are serialized
+    /// behind a single writer, in which case statements record the number of
+    /// writers queued ahead of them as `db.sqlite.queue_position`.
+    #[doc(hidden)]
+    fn serializes_writes(_options: &sqlx::pool::PoolOptions<Self>) -> bool
+    where
+        Self: sqlx::Database,
+    {
+        false
+    }
+
+    /// Limits the duration of the statements of the transaction open on
+    /// `conn` to `timeout`, or returns `None` when the database does not
+    /// support it.
+    #[doc(hidden)]
+    fn set_statement_timeout(
+        _conn: &mut <Self as sqlx::Database>::Connection,
+        _timeout: std::time::Duration,
+    ) -> Option<futures_core::future::BoxFuture<'_, Result<(), sqlx::Error>>>
+    where
+        Self: sqlx::Database,
+    {
+        None
+    }
+
+    /// Returns `arguments` for SQL living shorter than them, which is how the
+    /// rewritten SQL of a [query rewriter](crate::PoolBuilder::with_query_rewriter)
+    /// is sent, or gives them back when the database's arguments cannot be
+    /// reborrowed, in which case the query is sent without rewriting it.
+    ///
+    /// Implementations only have to return the arguments, as long as their
+    /// type is covariant in its lifetime.
+    #[doc(hidden)]
+    fn reborrow_arguments<'a, 'q: 'a>(
+        arguments: <Self as sqlx::Database>::Arguments<'q>,
+    ) -> Result<<Self as sqlx::Database>::Arguments<'a>, <Self as sqlx::Database>::Arguments<'q>>
+    where
+        Self: sqlx::Database,
+    {
+        Err(arguments)
+    }
+
+    /// Returns a copy of `arguments` when the database supports explaining
+    /// sampled statements.
+    #[doc(hidden)]
+    fn clone_arguments<'q>(
+        _arguments: &<Self as sqlx::Database>::Arguments<'q>,
+    ) -> Option<<Self as sqlx::Database>::Arguments<'q>>
+    where
+        Self: sqlx::Database,
+    {
+        None
+    }
+
+    /// Explains a sampled statement on `pool` in a background task, recording
+    /// the plan on a `sqlx.explain` span.
+    #[doc(hidden)]
+    fn explain<'q>(
+        _pool: &sqlx::Pool<Self>,
+        _attrs: &std::sync::Arc<crate::Attributes>,
+        _sql: &str,
+        _arguments: Option<<Self as sqlx::Database>::Arguments<'q>>,
+    ) where
+        Self: sqlx::Database,
+    {
+    }
 }
@@ -0,0 +1,167 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
+
+/// Count of the writes, i.e. the write statements and transactions, holding
+/// or waiting for the writer of a pool serializing its writes, such as a
+/// SQLite pool limited to a single connection.
+///
+/// A fast statement can still take long to complete when many writes are
+/// queued ahead of it.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct WriterQueue(Arc<AtomicUsize>);
+
+impl WriterQueue {
+    /// Returns a queue for pools serializing their writes, see
+    /// [`Database::serializes_writes`](crate::prelude::Database::serializes_writes),
+    /// or `None` for any other pool, or when instrumentation is compiled out
+    /// with the `disabled` feature.
+    pub(crate) fn new<DB>(pool: &sqlx::Pool<DB>) -> Option<Self>
+    where
+        DB: crate::prelude::Database + sqlx::Database,
+    {
+        (!cfg!(feature = "disabled") && DB::serializes_writes(pool.options())).then(Self::default)
+    }
+
+    /// Returns the number of writes holding or waiting for the writer.
+    pub(crate) fn writes(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Joins the queue, until the returned slot is dropped.
+    pub(crate) fn join(&self) -> QueueSlot {
+        let position = self.0.fetch_add(1, Ordering::Relaxed);
+        QueueSlot {
+            queue: self.0.clone(),
+            position,
+        }
+    }
+}
+
+/// Place in a [`WriterQueue`], left when dropped.
+#[derive(Debug)]
+pub(crate) struct QueueSlot {
+    queue: Arc<AtomicUsize>,
+    position: usize,
+}
+
+impl QueueSlot {
+    /// Number of writes holding or waiting for the writer when this slot was
+    /// taken.
+    pub(crate) fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl Drop for QueueSlot {
+    fn drop(&mut self) {
+        self.queue.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Runs `f` with `position`, if any, recorded as `db.sqlite.queue_position`
+/// on the spans it creates.
+pub(crate) fn in_scope<R>(position: Option<usize>, f: impl FnOnce() -> R) -> R {
+    match position {
+        Some(position) => {
+            crate::context::in_scope([("db.sqlite.queue_position", position.to_string())], f)
+        }
+        None => f(),
+    }
+}
+
+/// Returns whether the statement `sql` may write, i.e. is anything but a
+/// `SELECT`, `VALUES` or `EXPLAIN` statement.
+pub(crate) fn writes(sql: &str) -> bool {
+    !crate::span::operation(sql).is_some_and(|operation| {
+        ["SELECT", "VALUES", "EXPLAIN"]
+            .iter()
+            .any(|read| read.eq_ignore_ascii_case(operation))
+    })
+}
+
+/// Returns the position in `queue`, if any, of an operation, along with its
+/// slot when it `writes`, i.e. joins the queue.
+pub(crate) fn enter(
+    queue: Option<&WriterQueue>,
+    writes: bool,
+) -> (Option<usize>, Option<QueueSlot>) {
+    match queue {
+        Some(queue) if writes => {
+            let slot = queue.join();
+            (Some(slot.position()), Some(slot))
+        }
+        Some(queue) => (Some(queue.writes()), None),
+        None => (None, None),
+    }
+}
+
+/// Joins `queue`, if any, when the statement `writes`, until the future
+/// returned by `f` completes or is dropped, recording the position in the
+/// queue on the spans created by `f`.
+pub(crate) fn future<'e, T: 'e>(
+    queue: Option<&WriterQueue>,
+    writes: bool,
+    f: impl FnOnce() -> BoxFuture<'e, T>,
+) -> BoxFuture<'e, T> {
+    match enter(queue, writes) {
+        (position, Some(slot)) => Box::pin(Queued {
+            inner: in_scope(position, f),
+            slot: Some(slot),
+        }),
+        (position, None) => in_scope(position, f),
+    }
+}
+
+/// Joins `queue`, if any, when the statement `writes`, until the stream
+/// returned by `f` ends or is dropped, recording the position in the queue
+/// on the spans created by `f`.
+pub(crate) fn stream<'e, T: 'e>(
+    queue: Option<&WriterQueue>,
+    writes: bool,
+    f: impl FnOnce() -> BoxStream<'e, T>,
+) -> BoxStream<'e, T> {
+    match enter(queue, writes) {
+        (position, Some(slot)) => Box::pin(Queued {
+            inner: in_scope(position, f),
+            slot: Some(slot),
+        }),
+        (position, None) => in_scope(position, f),
+    }
+}
+
+/// Future or stream holding a place in a writer queue until it is done.
+struct Queued<T> {
+    inner: T,
+    slot: Option<QueueSlot>,
+}
+
+impl<T: Future + Unpin> Future for Queued<T> {
+    type Output = T::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let res = Pin::new(&mut self.inner).poll(cx);
+        if res.is_ready() {
+            self.slot = None;
+        }
+        res
+    }
+}
+
+impl<T: Stream + Unpin> Stream for Queued<T> {
+    type Item = T::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = Pin::new(&mut self.inner).poll_next(cx);
+        if let Poll::Ready(None) = item {
+            self.slot = None;
+        }
+        item
+    }
+}
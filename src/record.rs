@@ -0,0 +1,73 @@
+//! Helpers recording the outcome of an operation on the current span, the
+//! same way this crate records the statements it traces.
+//!
+//! They let operations run outside of the instrumented executors, e.g. on the
+//! raw connection returned by `inner()`, report errors and returned rows with
+//! the same fields and the same redaction rules:
+//!
+//! ```rust,ignore
+//! let span = tracing::info_span!(
+//!     "custom.fetch",
+//!     "db.response.returned_rows" = tracing::field::Empty,
+//!     "error.type" = tracing::field::Empty,
+//!     "error.message" = tracing::field::Empty,
+//!     "otel.status_code" = tracing::field::Empty,
+//! );
+//! let res = sqlx::query("SELECT 1")
+//!     .fetch_optional(pool.inner())
+//!     .instrument(span.clone())
+//!     .await;
+//! let _enter = span.enter();
+//! match res {
+//!     Ok(row) => sqlx_tracing::record::optional(&row),
+//!     Err(err) => sqlx_tracing::record::error(&err, pool.attributes()),
+//! }
+//! ```
+//!
+//! Only the fields declared on the span are recorded, see the fields of the
+//! crate's statement spans for the complete list.
+
+/// Records `err` on the current span: `otel.status_code`, `error.type`,
+/// `db.response.status_code`, `db.error.constraint`, the column involved in
+/// decode errors, and the error message and stacktrace.
+///
+/// The message and stacktrace follow the configuration of `attributes`: they
+/// are omitted when disabled with
+/// [`with_error_detail_recording`](crate::PoolBuilder::with_error_detail_recording)
+/// or rate-limited with
+/// [`with_error_detail_rate_limit`](crate::PoolBuilder::with_error_detail_rate_limit),
+/// in which case `error.details_redacted`, and `error.message_hash` when
+/// enabled, are recorded instead. With the `metrics` feature, the error is
+/// also counted in the metrics of the pool `attributes` belong to.
+pub fn error(err: &sqlx::Error, attributes: &crate::Attributes) {
+    crate::span::record_error(err, &attributes.config().error_details());
+}
+
+/// Emits a `sqlx.connection.broken` event when `err` is an I/O, TLS or
+/// protocol error, after which SQLx discards the connection instead of
+/// returning it to the pool.
+///
+/// The event carries `connection_id` when known, as
+/// `db.client.connection.id`, and the kind of error as
+/// `db.client.connection.broken_reason`.
+pub fn broken(err: &sqlx::Error, connection_id: Option<u64>) {
+    crate::span::record_broken(err, connection_id);
+}
+
+/// Records the number of `rows` returned as `db.response.returned_rows` on
+/// the current span, like `fetch_all`.
+pub fn rows<T>(rows: &[T]) {
+    crate::span::record_rows(rows);
+}
+
+/// Records a single returned row as `db.response.returned_rows` on the
+/// current span, like `fetch_one`.
+pub fn one<T>(row: &T) {
+    crate::span::record_one(row);
+}
+
+/// Records whether a row was returned, `0` or `1`, as
+/// `db.response.returned_rows` on the current span, like `fetch_optional`.
+pub fn optional<T>(row: &Option<T>) {
+    crate::span::record_optional(row);
+}
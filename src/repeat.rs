@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Runs of identical consecutive statements per connection, grouped under
+/// the span of their first statement when enabled with
+/// [`PoolBuilder::with_statement_grouping`](crate::PoolBuilder::with_statement_grouping).
+#[derive(Debug, Default)]
+pub(crate) struct StatementGroups(Mutex<HashMap<u64, Run>>);
+
+/// Identical statements run one after the other on a connection.
+#[derive(Debug)]
+struct Run {
+    sql: String,
+    transaction: Option<u64>,
+    span: tracing::Span,
+    count: u64,
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        if self.count > 1 {
+            self.span.record("db.statement.repetitions", self.count);
+        }
+    }
+}
+
+impl StatementGroups {
+    /// Returns the span of the run `sql` continues on `connection_id`, or a
+    /// new span created with `new` starting a run.
+    fn span(
+        &self,
+        connection_id: u64,
+        transaction: Option<u64>,
+        sql: &str,
+        new: impl FnOnce() -> tracing::Span,
+    ) -> tracing::Span {
+        let mut runs = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(run) = runs.get_mut(&connection_id)
+            && run.sql == sql
+            && run.transaction == transaction
+        {
+            run.count += 1;
+            return run.span.clone();
+        }
+        let span = new();
+        // Replacing the previous run, if any, ends it.
+        runs.insert(
+            connection_id,
+            Run {
+                sql: sql.to_owned(),
+                transaction,
+                span: span.clone(),
+                count: 1,
+            },
+        );
+        span
+    }
+
+    /// Ends the run of `connection_id`, if any.
+    fn end(&self, connection_id: u64) {
+        let run = self
+            .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&connection_id);
+        // Recorded outside of the lock.
+        drop(run);
+    }
+}
+
+/// Returns the span of a statement running `sql` on `connection_id`, grouped
+/// with the identical statements right before it on the same connection and
+/// transaction when enabled, or created with `new` otherwise.
+///
+/// Statements with empty SQL are flagged first, and get a disabled span when
+/// configured to be skipped, as do statements within `untraced`, which never
+/// join a run.
+pub(crate) fn span(
+    attrs: &crate::Attributes,
+    connection_id: Option<u64>,
+    transaction: Option<&crate::idle::IdleTracker>,
+    sql: &str,
+    new: impl FnOnce() -> tracing::Span,
+) -> tracing::Span {
+    if crate::untraced::active() {
+        return tracing::Span::none();
+    }
+    if !crate::empty::check(attrs, connection_id, sql) {
+        return tracing::Span::none();
+    }
+    match (&attrs.config().statement_groups, connection_id) {
+        (Some(groups), Some(connection_id)) => groups.span(
+            connection_id,
+            transaction.map(crate::idle::IdleTracker::id),
+            sql,
+            new,
+        ),
+        _ => new(),
+    }
+}
+
+/// Ends the run of statements of a connection or transaction handle when
+/// dropped, so that its span closes with it.
+#[derive(Debug)]
+pub(crate) struct RunGuard {
+    groups: Arc<StatementGroups>,
+    connection_id: u64,
+}
+
+impl RunGuard {
+    pub(crate) fn new(attrs: &crate::Attributes, connection_id: u64) -> Option<Self> {
+        let groups = attrs.config().statement_groups.clone()?;
+        Some(Self {
+            groups,
+            connection_id,
+        })
+    }
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        self.groups.end(self.connection_id);
+    }
+}
@@ -0,0 +1,116 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+type RewriteFn = dyn Fn(&str) -> Cow<'_, str> + Send + Sync;
+
+/// Function rewriting SQL before it is sent to the database, set with
+/// [`PoolBuilder::with_query_rewriter`](crate::PoolBuilder::with_query_rewriter).
+#[derive(Clone)]
+pub(crate) struct QueryRewriter(Arc<RewriteFn>);
+
+impl QueryRewriter {
+    pub(crate) fn new<F>(rewriter: F) -> Self
+    where
+        F: Fn(&str) -> Cow<'_, str> + Send + Sync + 'static,
+    {
+        Self(Arc::new(rewriter))
+    }
+
+    /// Rewrites `sql`.
+    pub(crate) fn apply<'s>(&self, sql: &'s str) -> Cow<'s, str> {
+        (self.0)(sql)
+    }
+
+    /// Rewrites `sql`, returning `None` when it is unchanged.
+    pub(crate) fn rewrite(&self, sql: &str) -> Option<String> {
+        match (self.0)(sql) {
+            Cow::Borrowed(_) => None,
+            Cow::Owned(rewritten) => Some(rewritten),
+        }
+    }
+}
+
+impl std::fmt::Debug for QueryRewriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueryRewriter").finish_non_exhaustive()
+    }
+}
+
+/// Query sent in place of one whose SQL was rewritten, with the arguments
+/// taken from the original query.
+///
+/// It borrows the rewritten SQL from the future or stream sending it, which
+/// owns the SQL for as long as the driver needs it.
+pub(crate) struct Rewritten<'s, DB: sqlx::Database> {
+    sql: &'s str,
+    arguments: Option<DB::Arguments<'s>>,
+    persistent: bool,
+}
+
+impl<'s, DB> Rewritten<'s, DB>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+{
+    /// Takes the arguments of `query` to send them with the rewritten `sql`.
+    ///
+    /// Databases whose arguments cannot be sent with SQL owned by the future,
+    /// see [`Database::reborrow_arguments`](crate::prelude::Database::reborrow_arguments),
+    /// get the original query back instead.
+    pub(crate) fn new<'q, E>(
+        sql: &'s str,
+        mut query: E,
+    ) -> Result<Result<Self, Rewritten<'q, DB>>, sqlx::Error>
+    where
+        E: sqlx::Execute<'q, DB>,
+        'q: 's,
+    {
+        let persistent = query.persistent();
+        let arguments = query.take_arguments().map_err(sqlx::Error::Encode)?;
+        let arguments = match arguments.map(DB::reborrow_arguments) {
+            None => None,
+            Some(Ok(arguments)) => Some(arguments),
+            Some(Err(arguments)) => {
+                return Ok(Err(Rewritten {
+                    sql: query.sql(),
+                    arguments: Some(arguments),
+                    persistent,
+                }));
+            }
+        };
+        Ok(Ok(Self {
+            sql,
+            arguments,
+            persistent,
+        }))
+    }
+}
+
+impl<'s, DB> sqlx::Execute<'s, DB> for Rewritten<'s, DB>
+where
+    DB: sqlx::Database,
+{
+    fn sql(&self) -> &'s str {
+        self.sql
+    }
+
+    fn statement(&self) -> Option<&DB::Statement<'s>> {
+        None
+    }
+
+    fn take_arguments(
+        &mut self,
+    ) -> Result<Option<<DB as sqlx::Database>::Arguments<'s>>, sqlx::error::BoxDynError> {
+        use sqlx::Arguments;
+
+        let arguments = self.arguments.take();
+        tracing::Span::current().record(
+            "db.query.parameter_count",
+            arguments.as_ref().map_or(0, Arguments::len),
+        );
+        Ok(arguments)
+    }
+
+    fn persistent(&self) -> bool {
+        self.persistent
+    }
+}
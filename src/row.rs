@@ -0,0 +1,56 @@
+use sqlx::{Column, ColumnIndex, Decode, Row, Type, TypeInfo};
+
+/// Extension of [`sqlx::Row`] reporting column decode failures.
+///
+/// Decode errors usually surface in application code, after the query span
+/// has ended, so nothing ties them to the query or the offending column.
+/// [`TracedRow::try_get_traced`] emits a `sqlx.row.decode_error` event in the
+/// current span with the column name, ordinal and database type, and the
+/// Rust type it was decoded as. Decoding rows within the span of the request
+/// that ran the query keeps the event next to its query span.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use sqlx_tracing::TracedRow;
+///
+/// let row = sqlx::query("SELECT id, name FROM users").fetch_one(&pool).await?;
+/// let name: String = row.try_get_traced("name")?;
+/// ```
+pub trait TracedRow: Row {
+    /// Decodes the value of the column at `index` like
+    /// [`Row::try_get`], emitting a `sqlx.row.decode_error` event when the
+    /// column is missing or its value cannot be decoded as `T`.
+    fn try_get_traced<'r, T, I>(&'r self, index: I) -> Result<T, sqlx::Error>
+    where
+        I: ColumnIndex<Self>,
+        usize: ColumnIndex<Self>,
+        T: Decode<'r, Self::Database> + Type<Self::Database>,
+    {
+        let (res, ordinal) = match index.index(self) {
+            Ok(ordinal) => (self.try_get::<T, _>(ordinal), Some(ordinal)),
+            Err(err) => (Err(err), None),
+        };
+        if let Err(err) = &res
+            && !cfg!(feature = "disabled")
+        {
+            let column = ordinal.and_then(|ordinal| self.columns().get(ordinal));
+            tracing::event!(
+                name: crate::names::ROW_DECODE_ERROR,
+                tracing::Level::ERROR,
+                {
+                    "db.column.index" = format!("{index:?}"),
+                    "db.column.name" = column.map(Column::name),
+                    "db.column.ordinal" = column.map(Column::ordinal),
+                    "db.column.type" = column.map(|column| column.type_info().name()),
+                    "db.column.rust_type" = std::any::type_name::<T>(),
+                    "error.message" = %err,
+                },
+                "failed to decode column"
+            );
+        }
+        res
+    }
+}
+
+impl<R: Row> TracedRow for R {}
@@ -0,0 +1,38 @@
+use std::cell::RefCell;
+use std::thread::LocalKey;
+
+/// Thread-local slot holding the value of the innermost scope, such as the
+/// deadline set with [`with_deadline`](crate::with_deadline).
+///
+/// Scoped futures install their value each time they are polled, so that it
+/// follows them across threads.
+pub(crate) type Slot<T> = LocalKey<RefCell<T>>;
+
+/// Installs `value` in `slot` on the current thread, until the returned
+/// guard restores the previous value.
+pub(crate) fn install<T>(slot: &'static Slot<T>, value: T) -> Restore<T> {
+    let previous = slot.with(|current| current.replace(value));
+    Restore {
+        slot,
+        previous: Some(previous),
+    }
+}
+
+/// Returns the value installed in `slot` on the current thread.
+pub(crate) fn current<T: Clone>(slot: &'static Slot<T>) -> T {
+    slot.with(|current| current.borrow().clone())
+}
+
+/// Restores the previous value of a slot when dropped.
+pub(crate) struct Restore<T: 'static> {
+    slot: &'static Slot<T>,
+    previous: Option<T>,
+}
+
+impl<T> Drop for Restore<T> {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            self.slot.with(|current| *current.borrow_mut() = previous);
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::borrow::Cow;
+
+use tracing::Instrument;
+
+impl<DB> crate::Pool<DB>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+    for<'a> &'a mut DB::Connection: sqlx::Executor<'a, Database = DB>,
+{
+    /// Executes a script of `;`-separated SQL statements one after another.
+    ///
+    /// This is meant for seeding and admin tooling. Statements run on a single
+    /// pooled connection, without bind parameters, and execution stops at the
+    /// first failing statement. See
+    /// [`Transaction::execute_script`](crate::Transaction::execute_script) to
+    /// run a script atomically.
+    ///
+    /// The script is wrapped in a `sqlx.execute_script` span recording the
+    /// number of statements (`db.script.statements`), with one `sqlx.execute`
+    /// child span per statement. On failure, the position of the failing
+    /// statement is recorded as `db.script.failed_statement` (1-based) and
+    /// `db.script.failed_line`. Statements go through the
+    /// [query rewriter](crate::PoolBuilder::with_query_rewriter), and within a
+    /// transaction record its `db.transaction.id` like any other statement.
+    ///
+    /// Quotes, comments and Postgres dollar-quoted bodies are taken into
+    /// account when splitting, but statements containing `;` themselves, such
+    /// as SQLite triggers, or MySQL `DELIMITER` commands are not supported.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// pool.execute_script(include_str!("seed.sql")).await?;
+    /// ```
+    pub async fn execute_script(&self, script: &str) -> Result<DB::QueryResult, sqlx::Error> {
+        let mut conn = self.acquire().await?;
+        conn.execute_script(script).await
+    }
+}
+
+impl<DB> crate::PoolConnection<DB>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+    for<'a> &'a mut DB::Connection: sqlx::Executor<'a, Database = DB>,
+{
+    /// Executes a script of `;`-separated SQL statements one after another.
+    ///
+    /// See [`Pool::execute_script`](crate::Pool::execute_script).
+    pub async fn execute_script(&mut self, script: &str) -> Result<DB::QueryResult, sqlx::Error> {
+        let attributes = self.attributes.clone();
+        let id = self.id;
+        let lease = self.lease.clone();
+        execute_script::<DB>(self.inner.as_mut(), &attributes, script, None, id)
+            .instrument(lease)
+            .await
+    }
+}
+
+impl<'c, DB> crate::Transaction<'c, DB>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+    for<'a> &'a mut DB::Connection: sqlx::Executor<'a, Database = DB>,
+{
+    /// Executes a script of `;`-separated SQL statements one after another
+    /// within this transaction.
+    ///
+    /// See [`Pool::execute_script`](crate::Pool::execute_script).
+    pub async fn execute_script(&mut self, script: &str) -> Result<DB::QueryResult, sqlx::Error> {
+        let attributes = self.attributes.clone();
+        let idle = Some(&self.idle);
+        execute_script::<DB>(
+            &mut *self.inner,
+            &attributes,
+            script,
+            idle,
+            self.connection_id,
+        )
+        .await
+    }
+}
+
+/// Wraps the statements of a script in a `sqlx.execute_script` span.
+///
+/// Each statement goes through the query rewriter, and is tracked by the
+/// `idle` tracker of the transaction it runs in, if any, like statements run
+/// through the executor.
+async fn execute_script<DB>(
+    conn: &mut DB::Connection,
+    attrs: &crate::Attributes,
+    script: &str,
+    idle: Option<&std::sync::Arc<crate::idle::IdleTracker>>,
+    connection_id: u64,
+) -> Result<DB::QueryResult, sqlx::Error>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+    for<'a> &'a mut DB::Connection: sqlx::Executor<'a, Database = DB>,
+{
+    let record_details = attrs.config().error_details();
+    let statements = split(script);
+    let span = crate::instrument_op!(
+        crate::Operation::ExecuteScript,
+        attrs,
+        "db.script.statements" = statements.len(),
+        "db.script.failed_statement" = ::tracing::field::Empty,
+        "db.script.failed_line" = ::tracing::field::Empty,
+    );
+    async {
+        let mut result = DB::QueryResult::default();
+        for (index, (line, statement)) in statements.into_iter().enumerate() {
+            let statement = match &attrs.config().query_rewriter {
+                Some(rewriter) => rewriter.apply(statement),
+                None => Cow::Borrowed(statement),
+            };
+            let statement = statement.as_ref();
+            let fut = sqlx::Executor::execute(&mut *conn, statement);
+            match crate::exec_fut_result!(statement, attrs, fut, idle, Some(connection_id)).await {
+                Ok(res) => result.extend(Some(res)),
+                Err(e) => {
+                    let span = tracing::Span::current();
+                    span.record("db.script.failed_statement", index + 1);
+                    span.record("db.script.failed_line", line);
+                    crate::span::record_error(&e, &record_details);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(result)
+    }
+    .instrument(span)
+    .await
+}
+
+/// Splits `script` into its statements, paired with the line each starts on.
+///
+/// Statements are separated by `;` outside of quotes, comments and dollar-quoted
+/// bodies. Comments before a statement are dropped, and so are statements
+/// made only of comments.
+fn split(script: &str) -> Vec<(usize, &str)> {
+    let bytes = script.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b';' => {
+                if let Some(start) = start.take() {
+                    statements.push((start, script[start..i].trim_end()));
+                }
+                i += 1;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = find(bytes, i + 2, b"\n").map_or(bytes.len(), |end| end + 1);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = find(bytes, i + 2, b"*/").map_or(bytes.len(), |end| end + 2);
+            }
+            byte if byte.is_ascii_whitespace() => i += 1,
+            byte => {
+                start.get_or_insert(i);
+                i = match byte {
+                    b'\'' | b'"' | b'`' => skip_quoted(bytes, i),
+                    b'$' => skip_dollar_quoted(bytes, i),
+                    _ => i + 1,
+                };
+            }
+        }
+    }
+    if let Some(start) = start {
+        statements.push((start, script[start..].trim_end()));
+    }
+    statements
+        .into_iter()
+        .map(|(start, statement)| {
+            (
+                1 + bytes[..start].iter().filter(|b| **b == b'\n').count(),
+                statement,
+            )
+        })
+        .collect()
+}
+
+/// Returns the position of the first occurrence of `needle` at or after `from`.
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|position| from + position)
+}
+
+/// Skips a quoted string or identifier starting at `start`, where doubled
+/// quotes are escapes, and returns the position after it.
+fn skip_quoted(bytes: &[u8], start: usize) -> usize {
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if bytes[i] == quote {
+            if bytes.get(i + 1) != Some(&quote) {
+                return i + 1;
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+/// Skips a Postgres dollar-quoted body such as `$body$ ... $body$` starting at
+/// `start` and returns the position after it. Anything else starting with `$`,
+/// like a positional parameter or a `$` within an identifier, only skips the `$`.
+fn skip_dollar_quoted(bytes: &[u8], start: usize) -> usize {
+    let is_word = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_';
+    if start > 0 && is_word(&bytes[start - 1]) {
+        return start + 1;
+    }
+    let tag_len = bytes[start + 1..]
+        .iter()
+        .position(|b| !is_word(b))
+        .unwrap_or(bytes.len() - start - 1);
+    let tag_end = start + 1 + tag_len;
+    if bytes.get(tag_end) != Some(&b'$') || bytes.get(start + 1).is_some_and(u8::is_ascii_digit) {
+        return start + 1;
+    }
+    let tag = &bytes[start..=tag_end];
+    find(bytes, tag_end + 1, tag).map_or(bytes.len(), |end| end + tag.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split;
+
+    #[test]
+    fn ignores_separators_within_quotes() {
+        assert_eq!(
+            split("INSERT INTO t VALUES ('a;b', 'it''s;'); SELECT \"x;y\" FROM `z;w`"),
+            vec![
+                (1, "INSERT INTO t VALUES ('a;b', 'it''s;')"),
+                (1, "SELECT \"x;y\" FROM `z;w`"),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_separators_within_dollar_quoted_bodies() {
+        let script = "CREATE FUNCTION f() RETURNS int AS $body$ BEGIN; RETURN 1; END $body$ LANGUAGE plpgsql;\nSELECT $$a;b$$, $1";
+        assert_eq!(
+            split(script),
+            vec![
+                (
+                    1,
+                    "CREATE FUNCTION f() RETURNS int AS $body$ BEGIN; RETURN 1; END $body$ LANGUAGE plpgsql"
+                ),
+                (2, "SELECT $$a;b$$, $1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_comments_before_statements() {
+        let script = "-- seed; data\nINSERT INTO t VALUES (1);\n/* block; comment */\nINSERT INTO t VALUES (2); -- trailing;\n/* only a comment */;";
+        assert_eq!(
+            split(script),
+            vec![
+                (2, "INSERT INTO t VALUES (1)"),
+                (4, "INSERT INTO t VALUES (2)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_trailing_statement_without_separator() {
+        assert_eq!(
+            split("SELECT 1;\n\nSELECT 2\n"),
+            vec![(1, "SELECT 1"), (3, "SELECT 2")]
+        );
+        assert_eq!(split("  ;;\n"), Vec::<(usize, &str)>::new());
+    }
+
+    #[test]
+    fn keeps_unterminated_quotes_in_last_statement() {
+        assert_eq!(split("SELECT 'a;b"), vec![(1, "SELECT 'a;b")]);
+    }
+}
@@ -1,227 +1,1067 @@
 /// Macro to create a tracing span for a SQLx operation with OpenTelemetry-compatible fields.
 ///
-/// - `$name`: The operation name (e.g., "sqlx.execute").
+/// - `$operation`: The [`Operation`](crate::Operation) (e.g., `Operation::Execute`).
 /// - `$statement`: The SQL statement being executed.
 /// - `$attributes`: Connection or pool attributes for peer and db context.
+/// - Any further tokens are forwarded as additional span fields.
 ///
 /// This macro is used internally by the crate to instrument all major SQLx operations.
-/// When `record_query_text` is disabled on the attributes, the `db.query.text` field
-/// will be empty.
+/// When `record_query_text` is disabled on the attributes, or the statement's
+/// operation is not allowed by `query_text_operations`, the `db.query.text`
+/// field will be empty.
+///
+/// Prefixed with `@transaction $transaction;`, the statement is recorded as
+/// part of the transaction tracked by `$transaction`, an
+/// `Option<&IdleTracker>`.
 #[doc(hidden)]
 #[macro_export]
 macro_rules! instrument {
-    ($name:expr, $statement:expr, $attributes:expr) => {
-        tracing::info_span!(
-            $name,
-            // Database name (if available)
-            "db.name" = $attributes.database,
-            // Operation type (filled by SQLx or left empty)
-            "db.operation" = ::tracing::field::Empty,
-            // The SQL query text (conditionally recorded based on config)
-            "db.query.text" = $attributes.record_query_text.then_some($statement),
-            // Number of affected rows (to be filled after execution)
-            "db.response.affected_rows" = ::tracing::field::Empty,
-            // Number of returned rows (to be filled after execution)
-            "db.response.returned_rows" = ::tracing::field::Empty,
-            // Status code of the response (to be filled after execution)
-            "db.response.status_code" = ::tracing::field::Empty,
-            // Table name (optional, left empty)
-            "db.sql.table" = ::tracing::field::Empty,
-            // Database system (e.g., "postgresql", "sqlite")
-            "db.system.name" = DB::SYSTEM,
-            // Error type, message, and stacktrace (to be filled on error)
-            "error.type" = ::tracing::field::Empty,
-            "error.message" = ::tracing::field::Empty,
-            "error.stacktrace" = ::tracing::field::Empty,
-            // Peer (server) host and port
-            "net.peer.name" = $attributes.host,
-            "net.peer.port" = $attributes.port,
-            // OpenTelemetry semantic fields
-            "otel.kind" = "client",
-            "otel.status_code" = ::tracing::field::Empty,
-            "otel.status_description" = ::tracing::field::Empty,
-            // Peer service name (if set)
-            "peer.service" = $attributes.name,
-        )
+    ($operation:expr, $statement:expr, $attributes:expr $(, $($field:tt)+)?) => {
+        $crate::instrument!(@transaction None; $operation, $statement, $attributes $(, $($field)+)?)
     };
+    (
+        @transaction $transaction:expr;
+        $operation:expr,
+        $statement:expr,
+        $attributes:expr
+        $(, $($field:tt)+)?
+    ) => {{
+        let statement: &str = $statement;
+        let operation = $crate::span::operation(statement);
+        // Operations within `untraced` get a disabled span.
+        let span = if $crate::untraced::active() {
+            ::tracing::Span::none()
+        } else {
+            tracing::info_span!(
+                $crate::Operation::span_name($operation),
+                // Violated constraint name (to be filled on constraint errors)
+                "db.error.constraint" = ::tracing::field::Empty,
+                // Column and SQL type involved in a decode error (to be filled on error)
+                "db.error.column" = ::tracing::field::Empty,
+                "db.error.source_type" = ::tracing::field::Empty,
+                // Name of the pool the operation went through
+                "db.client.pool.name" = $attributes
+                    .pool_name
+                    .as_deref()
+                    .map(|pool_name| $attributes.limited("db.client.pool.name", pool_name))
+                    .as_deref(),
+                // Database name (if available)
+                "db.name" = $attributes
+                    .database
+                    .as_deref()
+                    .map(|database| $attributes.limited("db.name", database))
+                    .as_deref(),
+                // Schema qualifying the statement's table (if enabled), e.g. an attached SQLite database
+                "db.namespace" = $attributes
+                    .config().record_schema
+                    .then(|| $crate::span::schema(statement))
+                    .flatten()
+                    .map(|schema| $attributes.limited("db.namespace", schema))
+                    .as_deref(),
+                // Operation type, parsed from the first keyword of the statement,
+                // under its legacy and current semantic convention names
+                "db.operation" = operation
+                    .map(str::to_ascii_uppercase)
+                    .as_deref()
+                    .map(|operation| $attributes.limited("db.operation", operation))
+                    .as_deref(),
+                "db.operation.name" = operation
+                    .map(str::to_ascii_uppercase)
+                    .as_deref()
+                    .map(|operation| $attributes.limited("db.operation", operation))
+                    .as_deref(),
+                // Time left before the `with_deadline` deadline (filled when started)
+                "db.query.deadline_ms" = ::tracing::field::Empty,
+                // What was left of the `with_budget` budget (filled when started)
+                "db.query.budget.remaining_statements" = ::tracing::field::Empty,
+                "db.query.budget.remaining_ms" = ::tracing::field::Empty,
+                // Number of bind parameters (filled when the arguments are taken)
+                "db.query.parameter_count" = ::tracing::field::Empty,
+                // Approximate size of the request, i.e. of the statement text (if enabled)
+                "db.request.bytes" = $attributes
+                    .config().record_payload_size
+                    .then_some(statement.len() as u64),
+                // Prepared statement identifier (filled on prepare and prepared executions)
+                "db.statement.id" = ::tracing::field::Empty,
+                // Number of identical consecutive statements grouped in the span (if enabled)
+                "db.statement.repetitions" = ::tracing::field::Empty,
+                // The SQL query text (conditionally recorded based on config)
+                "db.query.text" = $attributes.config().query_text(statement, operation),
+                // Number of affected rows (to be filled after execution)
+                "db.response.affected_rows" = ::tracing::field::Empty,
+                // Number of returned rows (to be filled after execution)
+                "db.response.returned_rows" = ::tracing::field::Empty,
+                // Approximate size of the returned rows (if enabled, filled after execution)
+                "db.response.bytes" = ::tracing::field::Empty,
+                // Stream timings (to be filled while streaming rows)
+                "db.response.time_to_first_row_ms" = ::tracing::field::Empty,
+                "db.response.stream_duration_ms" = ::tracing::field::Empty,
+                "db.response.rows_per_sec" = ::tracing::field::Empty,
+                "db.response.database_wait_ms" = ::tracing::field::Empty,
+                "db.response.consumer_wait_ms" = ::tracing::field::Empty,
+                // Database error code of the response, e.g. the SQLSTATE (to be filled on error)
+                "db.response.status_code" = ::tracing::field::Empty,
+                // Database system (e.g., "postgresql", "sqlite")
+                "db.system.name" = $attributes.system_name::<DB>(),
+                // Database user (if available)
+                "db.user" = $attributes.user,
+                // Error type, message, and stacktrace (to be filled on error)
+                "error.type" = ::tracing::field::Empty,
+                "error.message" = ::tracing::field::Empty,
+                "error.stacktrace" = ::tracing::field::Empty,
+                // Whether the message and stacktrace were omitted, and the
+                // message's hash when enabled (to be filled on error)
+                "error.details_redacted" = ::tracing::field::Empty,
+                "error.message_hash" = ::tracing::field::Empty,
+                // Peer (server) host and port
+                "net.peer.name" = $attributes.host,
+                "net.peer.port" = $attributes.port,
+                // OpenTelemetry semantic fields
+                "otel.kind" = "client",
+                "otel.status_code" = ::tracing::field::Empty,
+                "otel.status_description" = ::tracing::field::Empty,
+                // Peer service name (if set)
+                "peer.service" = $attributes.name,
+                // Values set with `with_context` (to be filled below)
+                "sqlx.context" = ::tracing::field::Empty,
+                // Operation-specific fields (if any)
+                $($($field)+)?
+            )
+        };
+        $crate::span::record_statement_context(&span, &$attributes, statement, $transaction);
+        span
+    }};
 }
 
 /// Helper macro for executor methods that return a BoxFuture
 /// (describe, execute, prepare, prepare_with).
+///
+/// The `exec_*` helpers accept an optional trailing idle tracker, used by
+/// transactions to account for the time spent between statements, followed by
+/// an optional connection id reported when the statement breaks the connection
+/// and used to group identical consecutive statements of a connection.
+/// They only build the span and record the output, the actual
+/// instrumentation being done by [`Statement`](crate::span::Statement).
+#[cfg(not(feature = "disabled"))]
 #[doc(hidden)]
 #[macro_export]
 macro_rules! exec_fut {
-    ($span_name:expr, $sql:expr, $attrs:expr, $fut:expr) => {{
-        let record_details = $attrs.record_error_details;
-        let span = $crate::instrument!($span_name, $sql, $attrs);
-        let fut = $fut;
-        Box::pin(
-            async move {
-                fut.await
-                    .inspect_err(|e| $crate::span::record_error(e, record_details))
-            }
-            .instrument(span),
+    // Records the returned rows of the output with `$record`
+    (@record $record:expr; $operation:expr, $sql:expr, $attrs:expr, $fut:expr) => {
+        $crate::span::Statement::start(&$attrs, $operation, $sql, None, None, |idle| {
+            $crate::instrument!(@transaction idle; $operation, $sql, $attrs)
+        })
+        .run(&$attrs, || $fut, $record)
+    };
+    ($operation:expr, $sql:expr, $attrs:expr, $fut:expr) => {
+        $crate::exec_fut!($operation, $sql, $attrs, $fut, None)
+    };
+    ($operation:expr, $sql:expr, $attrs:expr, $fut:expr, $idle:expr) => {
+        $crate::exec_fut!($operation, $sql, $attrs, $fut, $idle, None)
+    };
+    ($operation:expr, $sql:expr, $attrs:expr, $fut:expr, $idle:expr, $connection_id:expr) => {
+        $crate::span::Statement::start(&$attrs, $operation, $sql, $connection_id, $idle, |idle| {
+            $crate::instrument!(@transaction idle; $operation, $sql, $attrs)
+        })
+        .run(&$attrs, || $fut, |_| None)
+    };
+}
+
+/// Helper macro for execute which records the query result through the
+/// database's [`record_result`](crate::prelude::Database::record_result) hook.
+#[cfg(not(feature = "disabled"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! exec_fut_result {
+    ($sql:expr, $attrs:expr, $fut:expr) => {
+        $crate::exec_fut_result!($sql, $attrs, $fut, None)
+    };
+    ($sql:expr, $attrs:expr, $fut:expr, $idle:expr) => {
+        $crate::exec_fut_result!($sql, $attrs, $fut, $idle, None)
+    };
+    ($sql:expr, $attrs:expr, $fut:expr, $idle:expr, $connection_id:expr) => {{
+        let record_metadata = $attrs.config().record_result_metadata;
+        $crate::span::Statement::start(
+            &$attrs,
+            $crate::Operation::Execute,
+            $sql,
+            $connection_id,
+            $idle,
+            |idle| {
+                $crate::instrument!(
+                    @transaction idle;
+                    $crate::Operation::Execute,
+                    $sql,
+                    $attrs,
+                    // Result metadata (filled by the database hook when enabled)
+                    "db.sqlite.last_insert_rowid" = ::tracing::field::Empty,
+                    "db.mysql.last_insert_id" = ::tracing::field::Empty
+                )
+            },
         )
+        .run(&$attrs, || $fut, move |res| {
+            <DB as $crate::prelude::Database>::record_result(res, record_metadata);
+            Some(<DB as $crate::prelude::Database>::rows_affected(res))
+        })
     }};
 }
 
 /// Helper macro for fetch_all which records the number of returned rows.
+#[cfg(not(feature = "disabled"))]
 #[doc(hidden)]
 #[macro_export]
 macro_rules! exec_fut_rows {
-    ($sql:expr, $attrs:expr, $fut:expr) => {{
-        let record_details = $attrs.record_error_details;
-        let span = $crate::instrument!("sqlx.fetch_all", $sql, $attrs);
-        let fut = $fut;
-        Box::pin(
-            async move {
-                fut.await
-                    .inspect(|res| {
-                        ::tracing::Span::current().record("db.response.returned_rows", res.len());
-                    })
-                    .inspect_err(|e| $crate::span::record_error(e, record_details))
-            }
-            .instrument(span),
+    ($sql:expr, $attrs:expr, $fut:expr) => {
+        $crate::exec_fut_rows!($sql, $attrs, $fut, None)
+    };
+    ($sql:expr, $attrs:expr, $fut:expr, $idle:expr) => {
+        $crate::exec_fut_rows!($sql, $attrs, $fut, $idle, None)
+    };
+    ($sql:expr, $attrs:expr, $fut:expr, $idle:expr, $connection_id:expr) => {{
+        let record_size = $attrs.config().record_payload_size;
+        $crate::span::Statement::start(
+            &$attrs,
+            $crate::Operation::FetchAll,
+            $sql,
+            $connection_id,
+            $idle,
+            |idle| $crate::instrument!(@transaction idle; $crate::Operation::FetchAll, $sql, $attrs),
         )
+        .run(&$attrs, || $fut, move |rows: &Vec<_>| {
+            if record_size {
+                $crate::span::record_response_size::<DB>(rows);
+            }
+            $crate::span::record_rows(rows)
+        })
     }};
 }
 
 /// Helper macro for fetch_one which records returned_rows = 1.
+#[cfg(not(feature = "disabled"))]
 #[doc(hidden)]
 #[macro_export]
 macro_rules! exec_fut_one {
-    ($sql:expr, $attrs:expr, $fut:expr) => {{
-        let record_details = $attrs.record_error_details;
-        let span = $crate::instrument!("sqlx.fetch_one", $sql, $attrs);
-        let fut = $fut;
-        Box::pin(
-            async move {
-                fut.await
-                    .inspect($crate::span::record_one)
-                    .inspect_err(|e| $crate::span::record_error(e, record_details))
-            }
-            .instrument(span),
+    ($sql:expr, $attrs:expr, $fut:expr) => {
+        $crate::exec_fut_one!($sql, $attrs, $fut, None)
+    };
+    ($sql:expr, $attrs:expr, $fut:expr, $idle:expr) => {
+        $crate::exec_fut_one!($sql, $attrs, $fut, $idle, None)
+    };
+    ($sql:expr, $attrs:expr, $fut:expr, $idle:expr, $connection_id:expr) => {{
+        let record_size = $attrs.config().record_payload_size;
+        $crate::span::Statement::start(
+            &$attrs,
+            $crate::Operation::FetchOne,
+            $sql,
+            $connection_id,
+            $idle,
+            |idle| $crate::instrument!(@transaction idle; $crate::Operation::FetchOne, $sql, $attrs),
         )
+        .run(&$attrs, || $fut, move |row| {
+            if record_size {
+                $crate::span::record_response_size::<DB>(::std::iter::once(row));
+            }
+            $crate::span::record_one(row)
+        })
     }};
 }
 
 /// Helper macro for fetch_optional which records returned_rows 0 or 1.
+#[cfg(not(feature = "disabled"))]
 #[doc(hidden)]
 #[macro_export]
 macro_rules! exec_fut_opt {
-    ($sql:expr, $attrs:expr, $fut:expr) => {{
-        let record_details = $attrs.record_error_details;
-        let span = $crate::instrument!("sqlx.fetch_optional", $sql, $attrs);
-        let fut = $fut;
-        Box::pin(
-            async move {
-                fut.await
-                    .inspect($crate::span::record_optional)
-                    .inspect_err(|e| $crate::span::record_error(e, record_details))
-            }
-            .instrument(span),
+    ($sql:expr, $attrs:expr, $fut:expr) => {
+        $crate::exec_fut_opt!($sql, $attrs, $fut, None)
+    };
+    ($sql:expr, $attrs:expr, $fut:expr, $idle:expr) => {
+        $crate::exec_fut_opt!($sql, $attrs, $fut, $idle, None)
+    };
+    ($sql:expr, $attrs:expr, $fut:expr, $idle:expr, $connection_id:expr) => {{
+        let record_size = $attrs.config().record_payload_size;
+        $crate::span::Statement::start(
+            &$attrs,
+            $crate::Operation::FetchOptional,
+            $sql,
+            $connection_id,
+            $idle,
+            |idle| {
+                $crate::instrument!(@transaction idle; $crate::Operation::FetchOptional, $sql, $attrs)
+            },
         )
+        .run(&$attrs, || $fut, move |row: &Option<_>| {
+            if record_size {
+                $crate::span::record_response_size::<DB>(row);
+            }
+            $crate::span::record_optional(row)
+        })
     }};
 }
 
 /// Helper macro for stream-based executor methods (execute_many, fetch, fetch_many).
+///
+/// `$is_row` tells the rows among the items of the stream. See
+/// [`InstrumentedStream`](crate::stream::InstrumentedStream) for the timings
+/// recorded on the span.
+#[cfg(not(feature = "disabled"))]
 #[doc(hidden)]
 #[macro_export]
 macro_rules! exec_stream {
-    ($span_name:expr, $sql:expr, $attrs:expr, $stream:expr) => {{
-        let record_details = $attrs.record_error_details;
-        let span = $crate::instrument!($span_name, $sql, $attrs);
-        Box::pin(
-            $stream
-                .inspect(move |_| {
-                    let _enter = span.enter();
-                })
-                .inspect_err(move |e| $crate::span::record_error(e, record_details)),
-        )
-    }};
+    ($operation:expr, $sql:expr, $attrs:expr, $stream:expr, $is_row:expr) => {
+        $crate::exec_stream!($operation, $sql, $attrs, $stream, $is_row, None)
+    };
+    ($operation:expr, $sql:expr, $attrs:expr, $stream:expr, $is_row:expr, $idle:expr) => {
+        $crate::exec_stream!($operation, $sql, $attrs, $stream, $is_row, $idle, None)
+    };
+    ($operation:expr, $sql:expr, $attrs:expr, $stream:expr, $is_row:expr, $idle:expr, $connection_id:expr) => {
+        $crate::span::Statement::start(&$attrs, $operation, $sql, $connection_id, $idle, |idle| {
+            $crate::instrument!(@transaction idle; $operation, $sql, $attrs)
+        })
+        .stream(|| $stream, $is_row)
+    };
 }
 
 /// Macro to create a tracing span for a non-SQL lifecycle operation with OpenTelemetry-compatible fields.
 ///
-/// - `$name`: The operation name (e.g., "sqlx.pool.acquire", "sqlx.transaction.commit").
+/// - `$operation`: The [`Operation`](crate::Operation) (e.g., `Operation::Acquire`, `Operation::Commit`).
 /// - `$attributes`: Connection or pool attributes for peer and db context.
+/// - Any further tokens are forwarded as additional span fields.
 ///
 /// This macro is used internally for pool and transaction lifecycle operations
 /// that don't have an associated SQL statement. It omits query-specific fields
-/// like `db.query.text`, `db.sql.table`, and `db.response.*`.
+/// like `db.query.text` and `db.response.*`.
 #[doc(hidden)]
 #[macro_export]
 macro_rules! instrument_op {
-    ($name:expr, $attributes:expr) => {
-        tracing::info_span!(
-            $name,
-            // Database name (if available)
-            "db.name" = $attributes.database,
-            // Database system (e.g., "postgresql", "sqlite")
-            "db.system.name" = DB::SYSTEM,
-            // Error type, message, and stacktrace (to be filled on error)
-            "error.type" = ::tracing::field::Empty,
-            "error.message" = ::tracing::field::Empty,
-            "error.stacktrace" = ::tracing::field::Empty,
-            // Peer (server) host and port
-            "net.peer.name" = $attributes.host,
-            "net.peer.port" = $attributes.port,
-            // OpenTelemetry semantic fields
-            "otel.kind" = "client",
-            "otel.status_code" = ::tracing::field::Empty,
-            "otel.status_description" = ::tracing::field::Empty,
-            // Peer service name (if set)
-            "peer.service" = $attributes.name,
-        )
+    ($operation:expr, $attributes:expr $(, $($field:tt)+)?) => {{
+        // Operations within `untraced` get a disabled span.
+        let span = if $crate::untraced::active() {
+            ::tracing::Span::none()
+        } else {
+            tracing::info_span!(
+                $crate::Operation::span_name($operation),
+                // Violated constraint name (to be filled on constraint errors)
+                "db.error.constraint" = ::tracing::field::Empty,
+                // Lifecycle operation, e.g. "pool.acquire" or "transaction.commit"
+                "db.operation.name" = $crate::Operation::operation_name($operation),
+                // Name of the pool the operation went through
+                "db.client.pool.name" = $attributes
+                    .pool_name
+                    .as_deref()
+                    .map(|pool_name| $attributes.limited("db.client.pool.name", pool_name))
+                    .as_deref(),
+                // Database name (if available)
+                "db.name" = $attributes
+                    .database
+                    .as_deref()
+                    .map(|database| $attributes.limited("db.name", database))
+                    .as_deref(),
+                // Database system (e.g., "postgresql", "sqlite")
+                "db.system.name" = $attributes.system_name::<DB>(),
+                // Database user (if available)
+                "db.user" = $attributes.user,
+                // Error type, message, and stacktrace (to be filled on error)
+                "error.type" = ::tracing::field::Empty,
+                "error.message" = ::tracing::field::Empty,
+                "error.stacktrace" = ::tracing::field::Empty,
+                // Whether the message and stacktrace were omitted, and the
+                // message's hash when enabled (to be filled on error)
+                "error.details_redacted" = ::tracing::field::Empty,
+                "error.message_hash" = ::tracing::field::Empty,
+                // Peer (server) host and port
+                "net.peer.name" = $attributes.host,
+                "net.peer.port" = $attributes.port,
+                // Candidate hosts of a multi-host setup (if any)
+                "db.client.connection.candidate_hosts" = $attributes.candidate_hosts(),
+                // OpenTelemetry semantic fields
+                "otel.kind" = "client",
+                "otel.status_code" = ::tracing::field::Empty,
+                "otel.status_description" = ::tracing::field::Empty,
+                // Peer service name (if set)
+                "peer.service" = $attributes.name,
+                // SSL mode from the connect options and whether it guarantees TLS
+                "db.ssl_mode" = $attributes.ssl_mode,
+                "tls.established" = $attributes.tls,
+                // Connection URL without credentials (if enabled)
+                "db.connection_string" = $attributes.connection_string.as_deref(),
+                // Values set with `with_context` (to be filled below)
+                "sqlx.context" = ::tracing::field::Empty,
+                // Operation-specific fields (if any)
+                $($($field)+)?
+            )
+        };
+        $crate::span::record_context(&span, &$attributes);
+        span
+    }};
+}
+
+/// Returns the operation of a SQL statement, i.e. its first keyword, skipping
+/// leading whitespace and comments.
+pub(crate) fn operation(statement: &str) -> Option<&str> {
+    let mut rest = statement;
+    loop {
+        rest = rest.trim_start();
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.split_once('\n').map_or("", |(_, rest)| rest);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, rest)| rest);
+        } else {
+            break;
+        }
+    }
+    let end = rest
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(rest.len());
+    (end > 0).then(|| &rest[..end])
+}
+
+/// Returns the `key:value` tags of the leading block comments of a SQL
+/// statement, as in `/* application:billing,controller:invoices */ SELECT ...`.
+///
+/// Entries without a `:`, or with a key containing whitespace, are skipped so
+/// that free-form comments are not mistaken for tags.
+pub(crate) fn comment_tags(statement: &str) -> Vec<(&str, &str)> {
+    let mut tags = Vec::new();
+    let mut rest = statement.trim_start();
+    while let Some(comment) = rest.strip_prefix("/*") {
+        let Some((comment, after)) = comment.split_once("*/") else {
+            break;
+        };
+        for entry in comment.split(',') {
+            if let Some((key, value)) = entry.split_once(':') {
+                let (key, value) = (key.trim(), value.trim());
+                if !key.is_empty() && !key.contains(char::is_whitespace) {
+                    tags.push((key, value));
+                }
+            }
+        }
+        rest = after.trim_start();
+    }
+    tags
+}
+
+/// Returns the schema qualifying the first table referenced by a SQL
+/// statement, e.g. `aux` for `SELECT * FROM aux.users`, or `None` when the
+/// table is not qualified.
+pub(crate) fn schema(statement: &str) -> Option<&str> {
+    const KEYWORDS: [&str; 5] = ["FROM", "INTO", "UPDATE", "JOIN", "TABLE"];
+
+    let mut words = statement.split_whitespace();
+    while let Some(word) = words.next() {
+        if KEYWORDS
+            .iter()
+            .any(|keyword| word.eq_ignore_ascii_case(keyword))
+        {
+            let table = words.next()?;
+            let table = table.split('(').next().unwrap_or(table);
+            let (schema, _) = table.split_once('.')?;
+            let schema = schema.trim_matches(|c| matches!(c, '"' | '`' | '[' | ']'));
+            return (!schema.is_empty()).then_some(schema);
+        }
+    }
+    None
+}
+
+/// Records the resource attributes, the attribute extensions, the proxy and
+/// upstream addresses, the values set with `with_context` and, when enabled, the OpenTelemetry
+/// baggage entries on a newly created span.
+pub(crate) fn record_context(span: &tracing::Span, attributes: &crate::Attributes) {
+    if span.is_disabled() {
+        return;
+    }
+    record_with(span, attributes, "", std::iter::empty());
+}
+
+/// Records the context of a newly created statement span like
+/// [`record_context`], along with whether it runs within a transaction
+/// (`db.transaction.active`), the id of that transaction
+/// (`db.transaction.id`), its label (`db.transaction.label`) and its
+/// savepoint depth when nested (`db.transaction.savepoint`), and the tags of the statement's leading
+/// comments when enabled.
+pub(crate) fn record_statement_context(
+    span: &tracing::Span,
+    attributes: &crate::Attributes,
+    statement: &str,
+    transaction: Option<&crate::idle::IdleTracker>,
+) {
+    if span.is_disabled() {
+        return;
+    }
+    let transaction_id = transaction.map(|transaction| transaction.id().to_string());
+    let label = transaction.and_then(crate::idle::IdleTracker::label);
+    let savepoint = transaction
+        .filter(|transaction| transaction.depth() > 0)
+        .map(|transaction| transaction.depth().to_string());
+    let transaction = std::iter::once((
+        "db.transaction.active",
+        if transaction.is_some() {
+            "true"
+        } else {
+            "false"
+        },
+    ))
+    .chain(
+        transaction_id
+            .as_deref()
+            .map(|id| ("db.transaction.id", id)),
+    )
+    .chain(
+        label
+            .as_deref()
+            .map(|label| ("db.transaction.label", label)),
+    )
+    .chain(
+        savepoint
+            .as_deref()
+            .map(|depth| ("db.transaction.savepoint", depth)),
+    );
+    record_with(span, attributes, statement, transaction);
+}
+
+/// Records the context of a newly created span, with the `transaction`
+/// pairs taking precedence over the proxy and upstream addresses.
+fn record_with<'a>(
+    span: &tracing::Span,
+    attributes: &'a crate::Attributes,
+    statement: &'a str,
+    transaction: impl Iterator<Item = (&'a str, &'a str)>,
+) {
+    let extensions = attributes.extensions();
+    let tags = attributes
+        .config()
+        .record_comment_tags
+        .then(|| comment_tags(statement))
+        .into_iter()
+        .flatten();
+    crate::context::record(
+        span,
+        attributes
+            .resource_attributes()
+            .chain(attributes.upstream())
+            .chain(transaction)
+            .chain(extensions)
+            .chain(tags),
+    );
+    #[cfg(feature = "opentelemetry")]
+    if attributes.config().record_baggage {
+        crate::otel::record_baggage(span, attributes.config().baggage_keys.as_deref());
+    }
+}
+
+/// Query wrapper recording the number of bind parameters as
+/// `db.query.parameter_count` on the current span when the driver takes the
+/// query's arguments. Parameter values are never recorded.
+///
+/// Executions of prepared statements also record the statement's
+/// `db.statement.id`, matching the one recorded by their prepare span. Their
+/// SQL is the statement's own, as returned by [`sqlx::Execute::sql`].
+///
+/// When the pool has a query rewriter, the query is sent through one of the
+/// methods named after the executor's, whose future or stream owns the
+/// rewritten SQL, see [`Rewritten`](crate::rewrite::Rewritten).
+pub(crate) struct Query<'q, E> {
+    inner: E,
+    sql: &'q str,
+    rewritten: Option<String>,
+}
+
+impl<'q, E> Query<'q, E> {
+    /// Wraps `query`, to be sent with the SQL returned by the pool's query
+    /// rewriter, if any, instead of the original one.
+    pub(crate) fn new<DB>(query: E, attrs: &crate::Attributes) -> Self
+    where
+        DB: sqlx::Database,
+        E: sqlx::Execute<'q, DB>,
+    {
+        let sql = query.sql();
+        if let Some(watch) = &attrs.statement_cache
+            && query.persistent()
+            && !crate::untraced::active()
+        {
+            watch.observe(sql);
+        }
+        let rewritten = match &attrs.config().query_rewriter {
+            // Prepared statements are sent as they were prepared.
+            Some(rewriter) if query.statement().is_none() && !cfg!(feature = "disabled") => {
+                rewriter.rewrite(sql)
+            }
+            _ => None,
+        };
+        Self {
+            inner: query,
+            sql,
+            rewritten,
+        }
+    }
+
+    /// Returns the SQL sent to the database, which is only copied when it
+    /// was rewritten.
+    pub(crate) fn sql(&self) -> std::borrow::Cow<'q, str> {
+        match &self.rewritten {
+            Some(sql) => std::borrow::Cow::Owned(sql.clone()),
+            None => std::borrow::Cow::Borrowed(self.sql),
+        }
+    }
+}
+
+/// Defines the methods sending a [`Query`] through the executor method of
+/// the same name, which returns a future (`fut`) or a stream (`stream`).
+macro_rules! send_query {
+    ($(fut $method:ident -> $output:ty;)* $(stream $stream_method:ident -> $item:ty;)*) => {
+        impl<'q, E> Query<'q, E> {
+            $(
+                pub(crate) fn $method<'e, DB, X>(
+                    mut self,
+                    executor: X,
+                ) -> futures_core::future::BoxFuture<'e, Result<$output, sqlx::Error>>
+                where
+                    DB: crate::prelude::Database + sqlx::Database,
+                    X: 'e + sqlx::Executor<'e, Database = DB>,
+                    E: 'q + sqlx::Execute<'q, DB>,
+                    'q: 'e,
+                {
+                    let Some(sql) = self.rewritten.take() else {
+                        return executor.$method(self);
+                    };
+                    Box::pin(async move {
+                        match crate::rewrite::Rewritten::new(&sql, self.inner)? {
+                            Ok(query) => executor.$method(query).await,
+                            Err(query) => executor.$method(query).await,
+                        }
+                    })
+                }
+            )*
+            $(
+                pub(crate) fn $stream_method<'e, DB, X>(
+                    mut self,
+                    executor: X,
+                ) -> futures_core::stream::BoxStream<'e, Result<$item, sqlx::Error>>
+                where
+                    DB: crate::prelude::Database + sqlx::Database,
+                    X: 'e + sqlx::Executor<'e, Database = DB>,
+                    E: 'q + sqlx::Execute<'q, DB>,
+                    'q: 'e,
+                {
+                    use futures_util::StreamExt;
+
+                    let Some(sql) = self.rewritten.take() else {
+                        return executor.$stream_method(self);
+                    };
+                    Box::pin(async_stream::stream! {
+                        let mut stream = match crate::rewrite::Rewritten::new(&sql, self.inner) {
+                            Ok(Ok(query)) => executor.$stream_method(query),
+                            Ok(Err(query)) => executor.$stream_method(query),
+                            Err(e) => futures_util::stream::once(async { Err(e) }).boxed(),
+                        };
+                        while let Some(item) = stream.next().await {
+                            yield item;
+                        }
+                    })
+                }
+            )*
+        }
     };
 }
 
+send_query! {
+    fut execute -> DB::QueryResult;
+    fut fetch_all -> Vec<DB::Row>;
+    fut fetch_one -> DB::Row;
+    fut fetch_optional -> Option<DB::Row>;
+    stream execute_many -> DB::QueryResult;
+    stream fetch -> DB::Row;
+    stream fetch_many -> sqlx::Either<DB::QueryResult, DB::Row>;
+}
+
+impl<'q, DB, E> sqlx::Execute<'q, DB> for Query<'q, E>
+where
+    DB: sqlx::Database,
+    E: sqlx::Execute<'q, DB>,
+{
+    fn sql(&self) -> &'q str {
+        self.sql
+    }
+
+    fn statement(&self) -> Option<&DB::Statement<'q>> {
+        self.inner.statement()
+    }
+
+    fn take_arguments(
+        &mut self,
+    ) -> Result<Option<<DB as sqlx::Database>::Arguments<'q>>, sqlx::error::BoxDynError> {
+        use sqlx::Arguments;
+
+        let arguments = self.inner.take_arguments()?;
+        let span = tracing::Span::current();
+        span.record(
+            "db.query.parameter_count",
+            arguments.as_ref().map_or(0, Arguments::len),
+        );
+        if self.inner.statement().is_some() {
+            span.record("db.statement.id", statement_id(self.sql));
+        }
+        Ok(arguments)
+    }
+
+    fn persistent(&self) -> bool {
+        self.inner.persistent()
+    }
+}
+
+/// Instrumentation of a statement future or stream, started by the `exec_*`
+/// macros.
+pub(crate) struct Statement {
+    span: tracing::Span,
+    deadline: Option<std::time::Instant>,
+    budget: Result<Option<crate::budget::Charge>, sqlx::Error>,
+    record_details: crate::throttle::ErrorDetails,
+    connection_id: Option<u64>,
+    idle: Option<std::sync::Arc<crate::idle::IdleTracker>>,
+    pending: Option<crate::export::Pending>,
+}
+
+impl Statement {
+    /// Starts the instrumentation of the statement `sql`, run on the
+    /// connection `connection_id`, if known, and within the transaction
+    /// tracked by `idle`, if any.
+    ///
+    /// The span is created by `span`, given the transaction tracker, unless
+    /// the statement is grouped with the previous one of its connection. The
+    /// time left before the current deadline, and the current budget, are
+    /// recorded on it.
+    pub(crate) fn start(
+        attrs: &crate::Attributes,
+        operation: crate::Operation,
+        sql: &str,
+        connection_id: Option<u64>,
+        idle: Option<&std::sync::Arc<crate::idle::IdleTracker>>,
+        span: impl FnOnce(Option<&crate::idle::IdleTracker>) -> tracing::Span,
+    ) -> Self {
+        let idle = crate::idle::IdleTracker::resume(idle);
+        let span = crate::repeat::span(attrs, connection_id, idle.as_deref(), sql, || {
+            span(idle.as_deref())
+        });
+        let pending = crate::export::Pending::start(attrs, operation, sql);
+        Self {
+            deadline: span.in_scope(crate::deadline::start),
+            budget: span.in_scope(crate::budget::start),
+            record_details: attrs.config().error_details(),
+            span,
+            connection_id,
+            idle,
+            pending,
+        }
+    }
+
+    /// Runs the future returned by `fut` within the deadline and budget,
+    /// recording its result with `record`, which returns the number of rows
+    /// to report, or its error.
+    pub(crate) fn run<'e, F, T>(
+        self,
+        attrs: &crate::Attributes,
+        fut: impl FnOnce() -> F,
+        record: impl FnOnce(&T) -> Option<u64> + Send + 'e,
+    ) -> futures_core::future::BoxFuture<'e, Result<T, sqlx::Error>>
+    where
+        F: std::future::Future<Output = Result<T, sqlx::Error>> + Send + 'e,
+        T: Send + 'e,
+    {
+        // Evaluated within the span, as some drivers take the arguments eagerly
+        let fut = self.span.in_scope(fut);
+        let span = self.span.clone();
+        Box::pin(monitor(
+            attrs,
+            tracing::Instrument::instrument(self.finish(fut, record), span),
+        ))
+    }
+
+    /// Runs the stream returned by `stream` within the deadline and budget,
+    /// counting the items for which `is_row` holds as rows, see
+    /// [`InstrumentedStream`](crate::stream::InstrumentedStream).
+    pub(crate) fn stream<'e, S, T>(
+        self,
+        stream: impl FnOnce() -> S,
+        is_row: fn(&T) -> bool,
+    ) -> futures_core::stream::BoxStream<'e, Result<T, sqlx::Error>>
+    where
+        S: futures_core::Stream<Item = Result<T, sqlx::Error>> + Send + Unpin + 'e,
+        T: 'e,
+    {
+        // Evaluated within the span, as some drivers take the arguments eagerly
+        let stream = self.span.in_scope(stream);
+        let stream = crate::deadline::Bounded::new(self.deadline, stream);
+        let stream = crate::budget::Charged::new(self.budget, stream);
+        Box::pin(crate::stream::InstrumentedStream::new(
+            stream,
+            is_row,
+            self.span,
+            self.idle,
+            self.record_details,
+            self.connection_id,
+            self.pending,
+        ))
+    }
+
+    async fn finish<F, T>(
+        self,
+        fut: F,
+        record: impl FnOnce(&T) -> Option<u64>,
+    ) -> Result<T, sqlx::Error>
+    where
+        F: std::future::Future<Output = Result<T, sqlx::Error>>,
+    {
+        let fut = crate::budget::charged(self.budget, fut);
+        let res = crate::deadline::bound(self.deadline, fut).await;
+        let rows = match &res {
+            Ok(value) => record(value),
+            Err(e) => {
+                record_error(e, &self.record_details);
+                record_broken(e, self.connection_id);
+                None
+            }
+        };
+        if let Some(pending) = self.pending {
+            pending.finish(rows, res.as_ref().err());
+        }
+        if let Some(idle) = self.idle {
+            idle.pause();
+        }
+        res
+    }
+}
+
+/// Instruments a statement future with the pool's tokio task monitor.
+#[cfg(feature = "tokio-metrics")]
+pub(crate) fn monitor<F>(attrs: &crate::Attributes, fut: F) -> tokio_metrics::Instrumented<F> {
+    attrs.config().task_monitor.instrument(fut)
+}
+
+/// Instruments a statement future with the pool's tokio task monitor.
+#[cfg(not(feature = "tokio-metrics"))]
+pub(crate) fn monitor<F>(_attrs: &crate::Attributes, fut: F) -> F {
+    fut
+}
+
+/// Records the `db.statement.id` of the statement being prepared from `sql`
+/// on the current span and returns the driver's `prepare` future.
+pub(crate) fn prepared<F>(sql: &str, fut: F) -> F {
+    tracing::Span::current().record("db.statement.id", statement_id(sql));
+    fut
+}
+
+/// Returns the identifier of the prepared statement for `sql`.
+///
+/// SQLx does not expose driver statement names, so the identifier is a
+/// 64-bit FNV-1a hash of the statement text, which is stable across
+/// processes and lets prepare and execute spans of a statement be joined.
+pub(crate) fn statement_id(sql: &str) -> String {
+    fingerprint(sql)
+}
+
+/// Returns the 64-bit FNV-1a hash of `text`, formatted as hexadecimal.
+pub(crate) fn fingerprint(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+/// Records the number of returned rows in the current tracing span and
+/// returns it. Used for fetch_all operations.
+pub(crate) fn record_rows<T>(rows: &[T]) -> Option<u64> {
+    tracing::Span::current().record("db.response.returned_rows", rows.len());
+    Some(rows.len() as u64)
+}
+
+/// Records the approximate size of the returned `rows` in the current tracing
+/// span, as estimated by the database.
+pub(crate) fn record_response_size<'r, DB>(rows: impl IntoIterator<Item = &'r DB::Row>)
+where
+    DB: crate::prelude::Database + sqlx::Database,
+{
+    let bytes: u64 = rows.into_iter().map(DB::row_size).sum();
+    tracing::Span::current().record("db.response.bytes", bytes);
+}
+
 /// Records that a single row was returned in the current tracing span.
 /// Used for fetch_one operations.
-pub fn record_one<T>(_value: &T) {
-    let span = tracing::Span::current();
-    span.record("db.response.returned_rows", 1);
+pub(crate) fn record_one<T>(_value: &T) -> Option<u64> {
+    tracing::Span::current().record("db.response.returned_rows", 1);
+    Some(1)
 }
 
 /// Records whether an optional row was returned in the current tracing span.
 /// Used for fetch_optional operations.
-pub fn record_optional<T>(value: &Option<T>) {
-    let span = tracing::Span::current();
-    span.record(
-        "db.response.returned_rows",
-        if value.is_some() { 1 } else { 0 },
+pub(crate) fn record_optional<T>(value: &Option<T>) -> Option<u64> {
+    let rows = u64::from(value.is_some());
+    tracing::Span::current().record("db.response.returned_rows", rows);
+    Some(rows)
+}
+
+/// Emits a `sqlx.connection.broken` event when `err` is an I/O, TLS or
+/// protocol error, after which SQLx discards the connection instead of
+/// returning it to the pool.
+pub fn record_broken(err: &sqlx::Error, connection_id: Option<u64>) {
+    if cfg!(feature = "disabled") {
+        return;
+    }
+    let reason = match err {
+        _ if crate::DeadlineExceeded::is(err) || crate::QueryBudgetExceeded::is(err) => return,
+        sqlx::Error::Io(_) => "io",
+        sqlx::Error::Tls(_) => "tls",
+        sqlx::Error::Protocol(_) => "protocol",
+        sqlx::Error::WorkerCrashed => "worker_crashed",
+        _ => return,
+    };
+    tracing::event!(
+        name: crate::names::CONNECTION_BROKEN,
+        tracing::Level::WARN,
+        {
+            "db.client.connection.id" = connection_id,
+            "db.client.connection.broken_reason" = reason,
+        },
+        "connection broken by a failed operation"
     );
 }
 
 /// Records error details in the current tracing span for a SQLx error.
 /// Sets OpenTelemetry status and error fields for observability backends.
 ///
-/// When `record_details` is false, only the error type (client/server) and
-/// status code are recorded, omitting potentially sensitive error messages
-/// and stacktraces.
-pub fn record_error(err: &sqlx::Error, record_details: bool) {
+/// When `record_details` disallows it, only the error type (client/server),
+/// the status code and the database error code are recorded, omitting
+/// potentially sensitive or repetitive error messages and stacktraces. The
+/// omission is then recorded as `error.details_redacted`, along with the
+/// hash of the message as `error.message_hash` when enabled.
+///
+/// Errors mapped to [`ErrorStatus::Ok`](crate::ErrorStatus::Ok) leave the
+/// span successful, and are recorded on a `sqlx.error.expected` event.
+pub(crate) fn record_error(err: &sqlx::Error, record_details: &crate::throttle::ErrorDetails) {
     let span = tracing::Span::current();
-    // Mark the span as an error for OpenTelemetry
-    span.record("otel.status_code", "error");
     // Classify error type as client or server
-    match err {
+    let error_type = match err {
+        // The deadline and budget are enforced by the client
+        _ if crate::DeadlineExceeded::is(err) || crate::QueryBudgetExceeded::is(err) => "client",
         sqlx::Error::ColumnIndexOutOfBounds { .. }
         | sqlx::Error::ColumnDecode { .. }
         | sqlx::Error::ColumnNotFound(_)
         | sqlx::Error::Decode { .. }
         | sqlx::Error::Encode { .. }
         | sqlx::Error::RowNotFound
-        | sqlx::Error::TypeNotFound { .. } => {
-            span.record("error.type", "client");
+        | sqlx::Error::TypeNotFound { .. } => "client",
+        _ => "server",
+    };
+    // Group constraint violations (unique, foreign key, ...) by constraint name
+    if let Some(constraint) = err
+        .as_database_error()
+        .and_then(|db_err| db_err.constraint())
+    {
+        span.record("db.error.constraint", constraint);
+    }
+    if let Some(code) = err.as_database_error().and_then(|db_err| db_err.code()) {
+        span.record("db.response.status_code", code.as_ref());
+    }
+    record_column(&span, err);
+    let status = record_details.status(err);
+    // Expected outcomes are not counted as failed operations
+    #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+    if status != crate::ErrorStatus::Ok {
+        record_details.count(err);
+    }
+    if status == crate::ErrorStatus::Ok {
+        // An expected outcome, recorded on an event rather than failing the span
+        span.record("otel.status_code", "ok");
+        if cfg!(feature = "disabled") {
+            return;
         }
-        _ => {
-            span.record("error.type", "server");
+        let message = record_details.message(err);
+        tracing::event!(
+            name: crate::names::ERROR_EXPECTED,
+            tracing::Level::INFO,
+            {
+                "error.type" = error_type,
+                "error.message" = message.as_deref(),
+                "error.details_redacted" = message.is_none().then_some(true),
+            },
+            "expected database error"
+        );
+        return;
+    }
+    // Mark the span as an error for OpenTelemetry
+    span.record("otel.status_code", "error");
+    span.record("error.type", error_type);
+    match record_details.message(err) {
+        Some(msg) => {
+            span.record("otel.status_description", &msg);
+            span.record("error.message", msg);
+            span.record("error.stacktrace", format!("{err:?}"));
+        }
+        None => {
+            span.record("error.details_redacted", true);
+            if record_details.hash_redacted() {
+                span.record("error.message_hash", fingerprint(&err.to_string()));
+            }
         }
     }
-    if record_details {
-        let msg = err.to_string();
-        span.record("otel.status_description", &msg);
-        span.record("error.message", msg);
-        span.record("error.stacktrace", format!("{err:?}"));
+}
+
+/// Returns the category of `err` counted in the `db.client.operation.errors`
+/// metric: `encode`, `decode`, `constraint`, `connection`, `timeout` or
+/// `other`.
+///
+/// Refines the client/server split of `error.type`, e.g. deadlines are
+/// enforced by the client and counted as timeouts.
+#[cfg(all(feature = "metrics", not(feature = "disabled")))]
+pub(crate) fn error_category(err: &sqlx::Error) -> &'static str {
+    match err {
+        _ if crate::DeadlineExceeded::is(err) => "timeout",
+        sqlx::Error::PoolTimedOut => "timeout",
+        sqlx::Error::Encode(_) => "encode",
+        sqlx::Error::ColumnDecode { .. } | sqlx::Error::Decode(_) => "decode",
+        sqlx::Error::Database(db_err)
+            if db_err.constraint().is_some()
+                || matches!(
+                    db_err.kind(),
+                    sqlx::error::ErrorKind::UniqueViolation
+                        | sqlx::error::ErrorKind::ForeignKeyViolation
+                        | sqlx::error::ErrorKind::NotNullViolation
+                        | sqlx::error::ErrorKind::CheckViolation
+                ) =>
+        {
+            "constraint"
+        }
+        sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::Protocol(_)
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::WorkerCrashed => "connection",
+        _ => "other",
     }
 }
+
+/// Records the column involved in a column error, and for decode errors caused
+/// by mismatched types, the SQL type of the column.
+fn record_column(span: &tracing::Span, err: &sqlx::Error) {
+    match err {
+        sqlx::Error::ColumnDecode { index, source } => {
+            // SQLx formats the column index with `Debug`, which quotes names.
+            span.record("db.error.column", index.trim_matches('"'));
+            if let Some(source_type) = mismatched_source_type(&source.to_string()) {
+                span.record("db.error.source_type", source_type);
+            }
+        }
+        sqlx::Error::ColumnNotFound(name) => {
+            span.record("db.error.column", name.as_str());
+        }
+        sqlx::Error::ColumnIndexOutOfBounds { index, .. } => {
+            span.record("db.error.column", index);
+        }
+        _ => {}
+    }
+}
+
+/// Extracts the SQL type of the column from a SQLx mismatched types message,
+/// which ends with "is not compatible with SQL type `<type>`".
+fn mismatched_source_type(message: &str) -> Option<&str> {
+    let (_, rest) = message.rsplit_once("is not compatible with SQL type `")?;
+    rest.split_once('`').map(|(source_type, _)| source_type)
+}
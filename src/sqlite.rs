@@ -1,3 +1,764 @@
 impl crate::prelude::Database for sqlx::Sqlite {
     const SYSTEM: &'static str = "sqlite";
+    // SQLITE_MAX_VARIABLE_NUMBER default since SQLite 3.32.0
+    const MAX_BIND_PARAMETERS: usize = 32766;
+
+    fn connection_attributes(
+        options: &sqlx::sqlite::SqliteConnectOptions,
+    ) -> crate::AttributesBuilder {
+        let builder = crate::Attributes::builder();
+        match options.get_filename().to_str() {
+            Some(filename) => builder.with_host(filename),
+            None => builder,
+        }
+    }
+
+    // built by hand because `to_url_lossy()` panics with sqlite
+    fn connection_string(options: &sqlx::sqlite::SqliteConnectOptions) -> Option<String> {
+        Some(format!("sqlite://{}", options.get_filename().display()))
+    }
+
+    fn execute_builder<'e>(
+        conn: &'e mut sqlx::SqliteConnection,
+        builder: &'e mut sqlx::QueryBuilder<'_, Self>,
+    ) -> futures_core::future::BoxFuture<'e, Result<sqlx::sqlite::SqliteQueryResult, sqlx::Error>>
+    {
+        sqlx::Executor::execute(conn, builder.build())
+    }
+
+    fn rows_affected(result: &sqlx::sqlite::SqliteQueryResult) -> u64 {
+        result.rows_affected()
+    }
+
+    fn row_size(row: &sqlx::sqlite::SqliteRow) -> u64 {
+        use sqlx::{Row, TypeInfo, ValueRef};
+        (0..row.len())
+            .filter_map(|index| row.try_get_raw(index).ok())
+            .filter(|value| !value.is_null())
+            .map(|value| {
+                // Numbers are stored on 8 bytes, and not converted to text.
+                if matches!(value.type_info().name(), "INTEGER" | "REAL") {
+                    return 8;
+                }
+                <&[u8] as sqlx::Decode<sqlx::Sqlite>>::decode(value)
+                    .map_or(0, |bytes| bytes.len() as u64)
+            })
+            .sum()
+    }
+
+    fn record_settings<'c>(
+        conn: &'c mut sqlx::SqliteConnection,
+        span: &tracing::Span,
+    ) -> Option<futures_core::future::BoxFuture<'c, Result<(), sqlx::Error>>> {
+        let span = span.clone();
+        Some(Box::pin(async move {
+            let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+                .fetch_one(&mut *conn)
+                .await?;
+            span.record("db.sqlite.journal_mode", journal_mode.to_ascii_uppercase());
+            let synchronous: i64 = sqlx::query_scalar("PRAGMA synchronous")
+                .fetch_one(&mut *conn)
+                .await?;
+            let synchronous = match synchronous {
+                0 => "OFF",
+                1 => "NORMAL",
+                2 => "FULL",
+                3 => "EXTRA",
+                _ => "UNKNOWN",
+            };
+            span.record("db.sqlite.synchronous", synchronous);
+            let cache_size: i64 = sqlx::query_scalar("PRAGMA cache_size")
+                .fetch_one(&mut *conn)
+                .await?;
+            span.record("db.sqlite.cache_size", cache_size);
+            Ok(())
+        }))
+    }
+
+    fn reborrow_arguments<'a, 'q: 'a>(
+        arguments: <Self as sqlx::Database>::Arguments<'q>,
+    ) -> Result<<Self as sqlx::Database>::Arguments<'a>, <Self as sqlx::Database>::Arguments<'q>>
+    {
+        Ok(arguments)
+    }
+
+    fn serializes_writes(options: &sqlx::pool::PoolOptions<Self>) -> bool {
+        // Pools limited to one connection are the usual way to serialize writes.
+        options.get_max_connections() == 1
+    }
+
+    fn record_result(result: &sqlx::sqlite::SqliteQueryResult, record_metadata: bool) {
+        let span = tracing::Span::current();
+        span.record("db.response.affected_rows", result.rows_affected());
+        if record_metadata {
+            span.record("db.sqlite.last_insert_rowid", result.last_insert_rowid());
+        }
+    }
+}
+
+/// Retry policy for operations failing with `SQLITE_BUSY` or `SQLITE_LOCKED`,
+/// used by [`Pool::retry_busy`](crate::Pool::retry_busy).
+///
+/// The delay between attempts starts at `initial_backoff` and doubles after
+/// every retry, up to `max_backoff`.
+#[derive(Clone, Debug)]
+pub struct BusyRetry {
+    max_retries: u32,
+    initial_backoff: std::time::Duration,
+    max_backoff: std::time::Duration,
+}
+
+impl Default for BusyRetry {
+    /// Up to 5 retries, backing off from 10ms to 1s.
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_backoff: std::time::Duration::from_millis(10),
+            max_backoff: std::time::Duration::from_secs(1),
+        }
+    }
+}
+
+impl BusyRetry {
+    /// Set the maximum number of retries before the error is returned.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay before the first retry.
+    pub fn with_initial_backoff(mut self, backoff: std::time::Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Set the upper bound of the delay between retries.
+    pub fn with_max_backoff(mut self, backoff: std::time::Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+}
+
+impl crate::Pool<sqlx::Sqlite> {
+    /// Runs `op`, retrying it while it fails because the database is busy or
+    /// locked, as happens under write contention.
+    ///
+    /// The operation is wrapped in a `sqlx.sqlite.busy_retry` span recording
+    /// the number of retries (`db.sqlite.busy_retries`) and the total time
+    /// spent backing off (`db.sqlite.busy_wait_ms`). Every retry emits a
+    /// `sqlx.sqlite.busy` event.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let retry = sqlx_tracing::sqlite::BusyRetry::default();
+    /// pool.retry_busy(&retry, || sqlx::query("UPDATE jobs SET done = 1").execute(&pool))
+    ///     .await?;
+    /// ```
+    pub async fn retry_busy<F, Fut, T>(
+        &self,
+        retry: &BusyRetry,
+        mut op: F,
+    ) -> Result<T, sqlx::Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, sqlx::Error>>,
+    {
+        use tracing::Instrument;
+
+        type DB = sqlx::Sqlite;
+
+        let attrs = &self.attributes;
+        let span = crate::instrument_op!(
+            crate::Operation::SqliteBusyRetry,
+            attrs,
+            "db.sqlite.busy_retries" = ::tracing::field::Empty,
+            "db.sqlite.busy_wait_ms" = ::tracing::field::Empty
+        );
+        async {
+            let mut backoff = retry.initial_backoff;
+            let mut waited = std::time::Duration::ZERO;
+            let mut retries = 0;
+            let res = loop {
+                match op().await {
+                    Err(err) if retries < retry.max_retries && is_busy(&err) => {
+                        retries += 1;
+                        if !cfg!(feature = "disabled") {
+                            tracing::event!(
+                                name: crate::names::SQLITE_BUSY,
+                                tracing::Level::INFO,
+                                {
+                                    "db.sqlite.busy_retries" = retries,
+                                    "db.sqlite.backoff_ms" = backoff.as_millis() as u64,
+                                },
+                                "database busy, retrying"
+                            );
+                        }
+                        futures_timer::Delay::new(backoff).await;
+                        waited += backoff;
+                        backoff = (backoff * 2).min(retry.max_backoff);
+                    }
+                    res => break res,
+                }
+            };
+            let span = tracing::Span::current();
+            span.record("db.sqlite.busy_retries", retries);
+            span.record("db.sqlite.busy_wait_ms", waited.as_millis() as u64);
+            res
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+impl crate::Pool<sqlx::Sqlite> {
+    /// Rebuilds the database file with `VACUUM`, reclaiming unused space.
+    ///
+    /// The statement is wrapped in a `sqlx.maintenance.vacuum` span recording
+    /// the time it took as `db.maintenance.duration_ms`.
+    pub async fn vacuum(&self) -> Result<(), sqlx::Error> {
+        type DB = sqlx::Sqlite;
+
+        let span =
+            crate::maintenance_span!(crate::Operation::Vacuum, self.attributes, None::<&str>);
+        self.maintain(span, "VACUUM").await
+    }
+
+    /// Lets SQLite refresh the query planner statistics that need it with
+    /// `PRAGMA optimize`.
+    ///
+    /// The statement is wrapped in a `sqlx.maintenance.optimize` span, see
+    /// [`Pool::vacuum`](crate::Pool::vacuum).
+    pub async fn optimize(&self) -> Result<(), sqlx::Error> {
+        type DB = sqlx::Sqlite;
+
+        let span =
+            crate::maintenance_span!(crate::Operation::Optimize, self.attributes, None::<&str>);
+        self.maintain(span, "PRAGMA optimize").await
+    }
+
+    /// Runs a maintenance script, such as `VACUUM` or `PRAGMA optimize`, on
+    /// a pooled connection.
+    ///
+    /// See [`PoolConnection::maintenance`](crate::PoolConnection::maintenance).
+    pub async fn maintenance(&self, script: &str) -> Result<(), sqlx::Error> {
+        let mut conn = self.acquire().await?;
+        conn.maintenance(script).await
+    }
+}
+
+impl crate::PoolConnection<sqlx::Sqlite> {
+    /// Runs a maintenance script, such as `VACUUM` or `PRAGMA optimize`, on
+    /// this connection.
+    ///
+    /// SQLx runs the statements of a SQLite connection on its own worker
+    /// thread, so long maintenance work does not block the async runtime and
+    /// needs no `spawn_blocking`. The script is wrapped in a
+    /// `sqlx.sqlite.maintenance` span, recording the first statement's
+    /// operation as `db.operation`, which parents the `sqlx.execute` span of
+    /// every statement as with [`execute_script`](crate::PoolConnection::execute_script).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// pool.maintenance("PRAGMA optimize; VACUUM").await?;
+    /// ```
+    pub async fn maintenance(&mut self, script: &str) -> Result<(), sqlx::Error> {
+        use tracing::Instrument;
+
+        type DB = sqlx::Sqlite;
+
+        let attrs = self.attributes.clone();
+        let span = self.lease.in_scope(|| {
+            crate::instrument_op!(
+                crate::Operation::SqliteMaintenance,
+                attrs,
+                "db.operation" = crate::span::operation(script).map(str::to_ascii_uppercase),
+            )
+        });
+        self.execute_script(script).instrument(span).await.map(drop)
+    }
+}
+
+impl crate::PoolConnection<sqlx::Sqlite> {
+    /// Runs `f` with the locked SQLite database handle of this connection,
+    /// for direct SQLite API calls such as registering custom functions or
+    /// loading extensions.
+    ///
+    /// Locking the handle waits for the connection's worker thread to be
+    /// idle. Both are wrapped in a `sqlx.sqlite.raw` span, recording the
+    /// connection id as `db.client.connection.id`, within which `f` runs so
+    /// that its own spans and events are attributed to the connection.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut conn = pool.acquire().await?;
+    /// conn.with_raw_handle(|handle| {
+    ///     handle.create_collation("nocase_rev", |a, b| b.to_lowercase().cmp(&a.to_lowercase()))
+    /// })
+    /// .await??;
+    /// ```
+    pub async fn with_raw_handle<F, T>(&mut self, f: F) -> Result<T, sqlx::Error>
+    where
+        F: FnOnce(&mut sqlx::sqlite::LockedSqliteHandle<'_>) -> T,
+    {
+        use tracing::Instrument;
+
+        let span = self.lease.in_scope(|| raw_span(&self.attributes, self.id));
+        with_raw_handle(&mut self.inner, &self.attributes, f)
+            .instrument(span)
+            .await
+    }
+}
+
+impl crate::Transaction<'_, sqlx::Sqlite> {
+    /// Runs `f` with the locked SQLite database handle of this transaction's
+    /// connection.
+    ///
+    /// See [`PoolConnection::with_raw_handle`](crate::PoolConnection::with_raw_handle).
+    pub async fn with_raw_handle<F, T>(&mut self, f: F) -> Result<T, sqlx::Error>
+    where
+        F: FnOnce(&mut sqlx::sqlite::LockedSqliteHandle<'_>) -> T,
+    {
+        use tracing::Instrument;
+
+        let span = raw_span(&self.attributes, self.connection_id);
+        let idle = crate::idle::IdleTracker::resume(Some(&self.idle));
+        let res = with_raw_handle(&mut self.inner, &self.attributes, f)
+            .instrument(span)
+            .await;
+        if let Some(idle) = idle {
+            idle.pause();
+        }
+        res
+    }
+}
+
+/// Returns the `sqlx.sqlite.raw` span of the connection `connection_id`.
+fn raw_span(attrs: &crate::Attributes, connection_id: u64) -> tracing::Span {
+    type DB = sqlx::Sqlite;
+
+    crate::instrument_op!(
+        crate::Operation::SqliteRaw,
+        attrs,
+        "db.client.connection.id" = connection_id,
+    )
+}
+
+/// Locks the handle of `conn` and runs `f` with it.
+async fn with_raw_handle<F, T>(
+    conn: &mut sqlx::SqliteConnection,
+    attrs: &crate::Attributes,
+    f: F,
+) -> Result<T, sqlx::Error>
+where
+    F: FnOnce(&mut sqlx::sqlite::LockedSqliteHandle<'_>) -> T,
+{
+    let mut handle = conn
+        .lock_handle()
+        .await
+        .inspect_err(|e| crate::span::record_error(e, &attrs.config().error_details()))?;
+    Ok(f(&mut handle))
+}
+
+/// Value of an argument or result of a custom SQLite function.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SqliteValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+/// Custom SQLite aggregate function, registered with [`create_aggregate`].
+///
+/// A value is created with [`Default`] for every aggregation, fed the
+/// arguments of each row with [`step`](Self::step), and consumed by
+/// [`finish`](Self::finish) to return the result.
+pub trait SqliteAggregate: Default + Send + 'static {
+    /// Accumulates the arguments of a row. An error aborts the statement.
+    fn step(&mut self, args: &[SqliteValue]) -> Result<(), String>;
+
+    /// Returns the result of the aggregation.
+    fn finish(self) -> Result<SqliteValue, String>;
+}
+
+/// Registers the custom scalar function `name`, taking `arity` arguments or
+/// any number of them when negative, on a SQLite connection.
+///
+/// Every invocation runs within a `sqlx.sqlite.function` span at the debug
+/// level, recording the function as `db.sqlite.function.name` and an
+/// error returned or a panic as `error.message`, so slow or failing
+/// functions stand out. SQLite calls functions from the connection's worker
+/// thread, so these spans are roots rather than children of the statement's
+/// span.
+///
+/// Functions belong to a connection, register them in
+/// [`PoolOptions::after_connect`](crate::PoolOptions::after_connect) to make
+/// them available on every connection of a pool.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let options = sqlx_tracing::PoolOptions::<sqlx::Sqlite>::new().after_connect(|conn, _| {
+///     Box::pin(async move {
+///         sqlx_tracing::sqlite::create_function(conn, "double", 1, |args| match &args[0] {
+///             SqliteValue::Integer(value) => Ok(SqliteValue::Integer(value * 2)),
+///             _ => Err("double() takes an integer".into()),
+///         })
+///         .await
+///     })
+/// });
+/// ```
+pub async fn create_function<F>(
+    conn: &mut sqlx::SqliteConnection,
+    name: &str,
+    arity: i32,
+    f: F,
+) -> Result<(), sqlx::Error>
+where
+    F: Fn(&[SqliteValue]) -> Result<SqliteValue, String> + Send + 'static,
+{
+    let function = Box::new(Function {
+        name: name.to_owned(),
+        f,
+    });
+    register(
+        conn,
+        name,
+        arity,
+        function,
+        (Some(call_function::<F>), None, None),
+    )
+    .await
+}
+
+/// Registers the custom aggregate function `name`, taking `arity` arguments
+/// or any number of them when negative, on a SQLite connection.
+///
+/// Every aggregation runs within a `sqlx.sqlite.function` span, recording
+/// the number of rows fed to it as `db.sqlite.function.calls`, see
+/// [`create_function`].
+pub async fn create_aggregate<A>(
+    conn: &mut sqlx::SqliteConnection,
+    name: &str,
+    arity: i32,
+) -> Result<(), sqlx::Error>
+where
+    A: SqliteAggregate,
+{
+    let function = Box::new(name.to_owned());
+    let callbacks: Callbacks = (None, Some(aggregate_step::<A>), Some(aggregate_final::<A>));
+    register(conn, name, arity, function, callbacks).await
+}
+
+type FunctionFn = unsafe extern "C" fn(
+    *mut libsqlite3_sys::sqlite3_context,
+    std::os::raw::c_int,
+    *mut *mut libsqlite3_sys::sqlite3_value,
+);
+type FinalFn = unsafe extern "C" fn(*mut libsqlite3_sys::sqlite3_context);
+/// Function, step and final callbacks of `sqlite3_create_function_v2`.
+type Callbacks = (Option<FunctionFn>, Option<FunctionFn>, Option<FinalFn>);
+
+/// Registers the function `name` on the locked handle of `conn`, handing the
+/// ownership of `data` to SQLite which drops it once the function is
+/// replaced or the connection closed.
+async fn register<T>(
+    conn: &mut sqlx::SqliteConnection,
+    name: &str,
+    arity: i32,
+    data: Box<T>,
+    (func, step, finish): Callbacks,
+) -> Result<(), sqlx::Error> {
+    use libsqlite3_sys as ffi;
+
+    let c_name = std::ffi::CString::new(name)
+        .map_err(|_| sqlx::Error::InvalidArgument(format!("invalid function name {name:?}")))?;
+    let mut handle = conn.lock_handle().await?;
+    let db = handle.as_raw_handle().as_ptr();
+    // SAFETY: the handle is locked, and SQLite calls `destroy` with `data`
+    // when the registration fails as well.
+    let rc = unsafe {
+        ffi::sqlite3_create_function_v2(
+            db,
+            c_name.as_ptr(),
+            arity,
+            ffi::SQLITE_UTF8,
+            Box::into_raw(data).cast(),
+            func,
+            step,
+            finish,
+            Some(destroy::<T>),
+        )
+    };
+    if rc == ffi::SQLITE_OK {
+        return Ok(());
+    }
+    // SAFETY: the handle is locked, and the message is copied before it is
+    // released.
+    let msg = unsafe { std::ffi::CStr::from_ptr(ffi::sqlite3_errmsg(db)) };
+    Err(sqlx::Error::InvalidArgument(format!(
+        "failed to register function {name:?}: {}",
+        msg.to_string_lossy()
+    )))
+}
+
+/// Custom scalar function and its name.
+struct Function<F> {
+    name: String,
+    f: F,
+}
+
+/// State of an aggregation, kept in the SQLite aggregate context.
+struct Aggregation<A> {
+    value: A,
+    span: tracing::Span,
+    calls: u64,
+}
+
+/// Returns the `sqlx.sqlite.function` span of an invocation of `name`.
+fn function_span(name: &str, args: usize) -> tracing::Span {
+    if cfg!(feature = "disabled") {
+        return tracing::Span::none();
+    }
+    tracing::debug_span!(
+        crate::Operation::SqliteFunction.span_name(),
+        "db.system.name" = <sqlx::Sqlite as crate::prelude::Database>::SYSTEM,
+        "db.sqlite.function.name" = name,
+        "db.sqlite.function.args" = args,
+        "db.sqlite.function.calls" = ::tracing::field::Empty,
+        "error.type" = ::tracing::field::Empty,
+        "error.message" = ::tracing::field::Empty,
+        "otel.status_code" = ::tracing::field::Empty,
+    )
+}
+
+/// Records the failure of a custom function on `span`, and reports it to
+/// SQLite.
+///
+/// # Safety
+///
+/// `ctx` must be the context of the running function.
+unsafe fn fail(ctx: *mut libsqlite3_sys::sqlite3_context, span: &tracing::Span, msg: &str) {
+    span.record("otel.status_code", "error");
+    span.record("error.type", "client");
+    span.record("error.message", msg);
+    let len = std::os::raw::c_int::try_from(msg.len()).unwrap_or(std::os::raw::c_int::MAX);
+    // SAFETY: SQLite copies the message.
+    unsafe { libsqlite3_sys::sqlite3_result_error(ctx, msg.as_ptr().cast(), len) };
+}
+
+/// Returns the message of a function's result or panic.
+fn outcome<T>(res: std::thread::Result<Result<T, String>>) -> Result<T, String> {
+    res.unwrap_or_else(|_| Err("custom function panicked".to_owned()))
+}
+
+/// Reads the `argc` arguments at `argv`.
+///
+/// # Safety
+///
+/// `argv` must point to `argc` valid values.
+unsafe fn arguments(
+    argc: std::os::raw::c_int,
+    argv: *mut *mut libsqlite3_sys::sqlite3_value,
+) -> Vec<SqliteValue> {
+    use libsqlite3_sys as ffi;
+
+    let argc = usize::try_from(argc).unwrap_or_default();
+    if argc == 0 {
+        return Vec::new();
+    }
+    // SAFETY: guaranteed by the caller.
+    let argv = unsafe { std::slice::from_raw_parts(argv, argc) };
+    // SAFETY: the bytes are read after the pointers, as SQLite requires,
+    // and copied before the function returns.
+    let bytes = |ptr: *const std::os::raw::c_void, value| unsafe {
+        let len = usize::try_from(ffi::sqlite3_value_bytes(value)).unwrap_or_default();
+        if ptr.is_null() || len == 0 {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(ptr.cast::<u8>(), len).to_vec()
+        }
+    };
+    argv.iter()
+        // SAFETY: each value is valid for the duration of the call, and read
+        // through the accessor of its type.
+        .map(|&value| unsafe {
+            match ffi::sqlite3_value_type(value) {
+                ffi::SQLITE_INTEGER => SqliteValue::Integer(ffi::sqlite3_value_int64(value)),
+                ffi::SQLITE_FLOAT => SqliteValue::Real(ffi::sqlite3_value_double(value)),
+                ffi::SQLITE_TEXT => {
+                    let text = bytes(ffi::sqlite3_value_text(value).cast(), value);
+                    SqliteValue::Text(String::from_utf8_lossy(&text).into_owned())
+                }
+                ffi::SQLITE_BLOB => SqliteValue::Blob(bytes(ffi::sqlite3_value_blob(value), value)),
+                _ => SqliteValue::Null,
+            }
+        })
+        .collect()
+}
+
+/// Sets `value` as the result of the running function.
+///
+/// # Safety
+///
+/// `ctx` must be the context of the running function.
+unsafe fn set_result(ctx: *mut libsqlite3_sys::sqlite3_context, value: SqliteValue) {
+    use libsqlite3_sys as ffi;
+
+    // SAFETY: SQLite copies transient text and blobs.
+    unsafe {
+        match value {
+            SqliteValue::Null => ffi::sqlite3_result_null(ctx),
+            SqliteValue::Integer(value) => ffi::sqlite3_result_int64(ctx, value),
+            SqliteValue::Real(value) => ffi::sqlite3_result_double(ctx, value),
+            SqliteValue::Text(text) => ffi::sqlite3_result_text64(
+                ctx,
+                text.as_ptr().cast(),
+                text.len() as u64,
+                ffi::SQLITE_TRANSIENT(),
+                ffi::SQLITE_UTF8 as u8,
+            ),
+            SqliteValue::Blob(blob) => ffi::sqlite3_result_blob64(
+                ctx,
+                blob.as_ptr().cast(),
+                blob.len() as u64,
+                ffi::SQLITE_TRANSIENT(),
+            ),
+        }
+    }
+}
+
+unsafe extern "C" fn call_function<F>(
+    ctx: *mut libsqlite3_sys::sqlite3_context,
+    argc: std::os::raw::c_int,
+    argv: *mut *mut libsqlite3_sys::sqlite3_value,
+) where
+    F: Fn(&[SqliteValue]) -> Result<SqliteValue, String>,
+{
+    // SAFETY: the user data is the `Function<F>` registered with it, and
+    // SQLite passes the arguments of the invocation.
+    let (function, args) = unsafe {
+        (
+            &*libsqlite3_sys::sqlite3_user_data(ctx).cast::<Function<F>>(),
+            arguments(argc, argv),
+        )
+    };
+    let span = function_span(&function.name, args.len());
+    let _enter = span.enter();
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| (function.f)(&args)));
+    // SAFETY: `ctx` is the context of this invocation.
+    unsafe {
+        match outcome(res) {
+            Ok(value) => set_result(ctx, value),
+            Err(msg) => fail(ctx, &span, &msg),
+        }
+    }
+}
+
+unsafe extern "C" fn aggregate_step<A>(
+    ctx: *mut libsqlite3_sys::sqlite3_context,
+    argc: std::os::raw::c_int,
+    argv: *mut *mut libsqlite3_sys::sqlite3_value,
+) where
+    A: SqliteAggregate,
+{
+    use libsqlite3_sys as ffi;
+
+    // SAFETY: the user data is the name registered with the aggregate, and
+    // the aggregate context holds a pointer to the state of the aggregation,
+    // null until the first step.
+    unsafe {
+        let name = &*ffi::sqlite3_user_data(ctx).cast::<String>();
+        let slot = ffi::sqlite3_aggregate_context(
+            ctx,
+            std::mem::size_of::<*mut Aggregation<A>>() as std::os::raw::c_int,
+        )
+        .cast::<*mut Aggregation<A>>();
+        if slot.is_null() {
+            ffi::sqlite3_result_error_nomem(ctx);
+            return;
+        }
+        let args = arguments(argc, argv);
+        if (*slot).is_null() {
+            let span = function_span(name, args.len());
+            let res = std::panic::catch_unwind(|| Ok(A::default()));
+            let value = match outcome(res) {
+                Ok(value) => value,
+                Err(msg) => return fail(ctx, &span, &msg),
+            };
+            *slot = Box::into_raw(Box::new(Aggregation {
+                value,
+                span,
+                calls: 0,
+            }));
+        }
+        let state = &mut **slot;
+        state.calls += 1;
+        let span = state.span.clone();
+        let _enter = span.enter();
+        let res =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| state.value.step(&args)));
+        if let Err(msg) = outcome(res) {
+            fail(ctx, &span, &msg);
+        }
+    }
+}
+
+unsafe extern "C" fn aggregate_final<A>(ctx: *mut libsqlite3_sys::sqlite3_context)
+where
+    A: SqliteAggregate,
+{
+    use libsqlite3_sys as ffi;
+
+    // SAFETY: see `aggregate_step`, the state is taken out of the aggregate
+    // context so that it is dropped exactly once. SQLite calls the final
+    // callback even when the statement is reset before the aggregation ends.
+    let (value, span, calls) = unsafe {
+        let name = &*ffi::sqlite3_user_data(ctx).cast::<String>();
+        let slot = ffi::sqlite3_aggregate_context(ctx, 0).cast::<*mut Aggregation<A>>();
+        if slot.is_null() || (*slot).is_null() {
+            (None, function_span(name, 0), 0)
+        } else {
+            let state = *Box::from_raw(std::mem::replace(&mut *slot, std::ptr::null_mut()));
+            (Some(state.value), state.span, state.calls)
+        }
+    };
+    span.record("db.sqlite.function.calls", calls);
+    let _enter = span.enter();
+    // Dropping the state, when it finishes or after a panic, runs user code
+    // as well.
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        value.unwrap_or_default().finish()
+    }));
+    // SAFETY: `ctx` is the context of this aggregation.
+    unsafe {
+        match outcome(res) {
+            Ok(value) => set_result(ctx, value),
+            Err(msg) => fail(ctx, &span, &msg),
+        }
+    }
+}
+
+unsafe extern "C" fn destroy<T>(data: *mut std::os::raw::c_void) {
+    // SAFETY: `data` is the box handed to `sqlite3_create_function_v2`, which
+    // SQLite destroys exactly once.
+    let data = unsafe { Box::from_raw(data.cast::<T>()) };
+    // A panic must not unwind into SQLite, and there is no statement to fail.
+    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(data)));
+}
+
+/// Returns whether `err` is a `SQLITE_BUSY` or `SQLITE_LOCKED` error,
+/// including their extended result codes.
+fn is_busy(err: &sqlx::Error) -> bool {
+    const SQLITE_BUSY: i32 = 5;
+    const SQLITE_LOCKED: i32 = 6;
+
+    err.as_database_error()
+        .and_then(|err| err.code())
+        .and_then(|code| code.parse::<i32>().ok())
+        .is_some_and(|code| matches!(code & 0xff, SQLITE_BUSY | SQLITE_LOCKED))
 }
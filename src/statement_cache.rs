@@ -0,0 +1,50 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+/// Distinct persistent statements run through a pool, compared with the
+/// capacity of the driver's statement cache, set with
+/// [`AttributesBuilder::with_statement_cache_capacity`](crate::AttributesBuilder::with_statement_cache_capacity).
+///
+/// Every connection caches the statements it prepares, evicting the least
+/// recently used one when full. Once a pool runs more distinct statements
+/// than a cache holds, connections keep preparing statements again.
+#[derive(Debug)]
+pub(crate) struct StatementCacheWatch {
+    capacity: usize,
+    // Emptied once the capacity is exceeded, as nothing is left to report.
+    seen: Mutex<Option<HashSet<String>>>,
+}
+
+impl StatementCacheWatch {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            seen: Mutex::new(Some(HashSet::new())),
+        }
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Remembers the persistent statement `sql`, emitting a
+    /// `sqlx.statement_cache.exceeded` event the first time the distinct
+    /// statements outnumber the cache capacity.
+    pub(crate) fn observe(&self, sql: &str) {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(statements) = seen.as_mut() else {
+            return;
+        };
+        if !statements.insert(crate::span::statement_id(sql)) || statements.len() <= self.capacity {
+            return;
+        }
+        *seen = None;
+        drop(seen);
+        tracing::event!(
+            name: crate::names::STATEMENT_CACHE_EXCEEDED,
+            tracing::Level::WARN,
+            { "db.client.statement_cache.capacity" = self.capacity },
+            "more distinct statements than the statement cache holds"
+        );
+    }
+}
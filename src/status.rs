@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+type StatusFn = dyn Fn(&sqlx::Error) -> ErrorStatus + Send + Sync;
+
+/// OpenTelemetry status of a span whose operation failed, returned by the
+/// function set with
+/// [`PoolBuilder::with_error_status`](crate::PoolBuilder::with_error_status).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorStatus {
+    /// The span is marked as failed, with the error recorded on it.
+    #[default]
+    Error,
+    /// The error is an expected outcome, such as a unique violation on
+    /// insert. The span is marked as successful, and the error recorded on a
+    /// `sqlx.error.expected` event instead.
+    Ok,
+}
+
+/// Function mapping errors to the status of their span.
+#[derive(Clone)]
+pub(crate) struct ErrorStatusMapper(Arc<StatusFn>);
+
+impl ErrorStatusMapper {
+    pub(crate) fn new<F>(mapper: F) -> Self
+    where
+        F: Fn(&sqlx::Error) -> ErrorStatus + Send + Sync + 'static,
+    {
+        Self(Arc::new(mapper))
+    }
+
+    pub(crate) fn status(&self, err: &sqlx::Error) -> ErrorStatus {
+        (self.0)(err)
+    }
+}
+
+impl std::fmt::Debug for ErrorStatusMapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ErrorStatusMapper").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,249 @@
+use std::num::NonZeroU64;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures_core::Stream;
+
+/// Stream wrapper used by `exec_stream!` to instrument row streams.
+///
+/// Every poll of the inner stream happens within the statement span. The time
+/// until the first item is recorded as `db.response.time_to_first_row_ms`, and
+/// the time until the stream ends, or is dropped, as
+/// `db.response.stream_duration_ms`, along with the throughput of the stream
+/// over that time as `db.response.rows_per_sec`. Only the rows are counted,
+/// not the query results of `execute_many` and `fetch_many` streams.
+///
+/// To tell slow queries from slow consumers, the stream also accumulates the
+/// time spent waiting on the database for the next item
+/// (`db.response.database_wait_ms`) and the time items spent waiting for the
+/// consumer to poll again (`db.response.consumer_wait_ms`).
+///
+/// The statement is reported to the `on_span_complete` callback when the
+/// stream ends, is dropped or yields its first error.
+pub(crate) struct InstrumentedStream<S, T> {
+    inner: S,
+    is_row: fn(&T) -> bool,
+    span: tracing::Span,
+    idle: Option<Arc<crate::idle::IdleTracker>>,
+    record_details: crate::throttle::ErrorDetails,
+    connection_id: Option<u64>,
+    pending: Option<crate::export::Pending>,
+    rows: u64,
+    start: Instant,
+    first_item: bool,
+    finished: bool,
+    waiting_since: Option<Instant>,
+    yielded_at: Option<Instant>,
+    database_wait: Duration,
+    consumer_wait: Duration,
+}
+
+impl<S, T> InstrumentedStream<S, T> {
+    pub(crate) fn new(
+        inner: S,
+        is_row: fn(&T) -> bool,
+        span: tracing::Span,
+        idle: Option<Arc<crate::idle::IdleTracker>>,
+        record_details: crate::throttle::ErrorDetails,
+        connection_id: Option<u64>,
+        pending: Option<crate::export::Pending>,
+    ) -> Self {
+        Self {
+            inner,
+            is_row,
+            span,
+            idle,
+            record_details,
+            connection_id,
+            pending,
+            rows: 0,
+            start: Instant::now(),
+            first_item: true,
+            finished: false,
+            waiting_since: None,
+            yielded_at: None,
+            database_wait: Duration::ZERO,
+            consumer_wait: Duration::ZERO,
+        }
+    }
+
+    fn finish(&mut self) {
+        if !self.finished {
+            self.finished = true;
+            let elapsed = self.start.elapsed();
+            self.span
+                .record("db.response.stream_duration_ms", elapsed.as_millis() as u64);
+            if !elapsed.is_zero() {
+                self.span.record(
+                    "db.response.rows_per_sec",
+                    self.rows as f64 / elapsed.as_secs_f64(),
+                );
+            }
+            self.span.record(
+                "db.response.database_wait_ms",
+                self.database_wait.as_millis() as u64,
+            );
+            self.span.record(
+                "db.response.consumer_wait_ms",
+                self.consumer_wait.as_millis() as u64,
+            );
+            if let Some(pending) = self.pending.take() {
+                pending.finish(Some(self.rows), None);
+            }
+        }
+    }
+}
+
+impl<S, T> Stream for InstrumentedStream<S, T>
+where
+    S: Stream<Item = Result<T, sqlx::Error>> + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let span = this.span.clone();
+        let _enter = span.enter();
+        let now = Instant::now();
+        if let Some(yielded_at) = this.yielded_at.take() {
+            this.consumer_wait += now - yielded_at;
+        }
+        let waiting_since = *this.waiting_since.get_or_insert(now);
+        let item = std::task::ready!(Pin::new(&mut this.inner).poll_next(cx));
+        let now = Instant::now();
+        this.database_wait += now - waiting_since;
+        this.waiting_since = None;
+        this.yielded_at = Some(now);
+        match &item {
+            Some(res) => {
+                if this.first_item {
+                    this.first_item = false;
+                    this.span.record(
+                        "db.response.time_to_first_row_ms",
+                        this.start.elapsed().as_millis() as u64,
+                    );
+                }
+                if let Some(idle) = &this.idle {
+                    idle.pause();
+                }
+                match res {
+                    Ok(value) if (this.is_row)(value) => this.rows += 1,
+                    Ok(_) => {}
+                    Err(e) => {
+                        crate::span::record_error(e, &this.record_details);
+                        crate::span::record_broken(e, this.connection_id);
+                        if let Some(pending) = this.pending.take() {
+                            pending.finish(Some(this.rows), Some(e));
+                        }
+                    }
+                }
+            }
+            None => this.finish(),
+        }
+        Poll::Ready(item)
+    }
+}
+
+impl<S, T> Drop for InstrumentedStream<S, T> {
+    fn drop(&mut self) {
+        // Streams abandoned before their end still report how long they ran.
+        self.finish();
+    }
+}
+
+/// Stream wrapper adding up the rows of a stream and their approximate size,
+/// as estimated by the database, when payload sizes are recorded.
+///
+/// The running size is recorded as `db.response.bytes` on the current span,
+/// which is the statement span when polled by [`InstrumentedStream`].
+///
+/// When a progress interval is set, a `sqlx.stream.progress` event carrying
+/// the running row count is emitted every time that many rows were returned.
+/// Query results of `fetch_many` streams are not counted.
+pub(crate) struct Measured<S, T> {
+    inner: S,
+    is_row: fn(&T) -> bool,
+    size: Option<fn(&T) -> u64>,
+    bytes: u64,
+    progress_interval: Option<NonZeroU64>,
+    rows: u64,
+}
+
+/// Measures the rows of a `fetch` stream.
+pub(crate) fn measure_rows<DB, S>(
+    inner: S,
+    attrs: &crate::Attributes,
+) -> Measured<S, <DB as sqlx::Database>::Row>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+{
+    Measured {
+        inner,
+        is_row: |_| true,
+        size: attrs.config().record_payload_size.then_some(DB::row_size),
+        bytes: 0,
+        progress_interval: attrs.config().stream_progress_interval,
+        rows: 0,
+    }
+}
+
+/// Measures the rows of a `fetch_many` stream, ignoring its query results.
+pub(crate) fn measure_results<DB, S>(
+    inner: S,
+    attrs: &crate::Attributes,
+) -> Measured<S, sqlx::Either<<DB as sqlx::Database>::QueryResult, <DB as sqlx::Database>::Row>>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+{
+    Measured {
+        inner,
+        is_row: sqlx::Either::is_right,
+        size: attrs
+            .config()
+            .record_payload_size
+            .then_some(|item| item.as_ref().right().map_or(0, DB::row_size)),
+        bytes: 0,
+        progress_interval: attrs.config().stream_progress_interval,
+        rows: 0,
+    }
+}
+
+impl<S, T> Measured<S, T> {
+    fn progress(&mut self) {
+        self.rows += 1;
+        if let Some(interval) = self.progress_interval
+            && self.rows % interval == 0
+        {
+            tracing::event!(
+                name: crate::names::STREAM_PROGRESS,
+                tracing::Level::INFO,
+                { "db.response.returned_rows" = self.rows },
+                "streamed rows"
+            );
+        }
+    }
+}
+
+impl<S, T> Stream for Measured<S, T>
+where
+    S: Stream<Item = Result<T, sqlx::Error>> + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let item = std::task::ready!(Pin::new(&mut this.inner).poll_next(cx));
+        if let Some(Ok(value)) = &item {
+            if let Some(size) = this.size {
+                this.bytes += size(value);
+                tracing::Span::current().record("db.response.bytes", this.bytes);
+            }
+            if (this.is_row)(value) {
+                this.progress();
+            }
+        }
+        Poll::Ready(item)
+    }
+}
@@ -0,0 +1,172 @@
+//! Ephemeral traced databases for integration tests.
+//!
+//! A [`TestDatabase`] creates a temporary database, a SQLite file or a
+//! Postgres schema, and hands out a traced [`Pool`](crate::Pool) connected to
+//! it. The statements setting it up and tearing it down run through traced
+//! pools as well, so a failing fixture shows up in the test's traces.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! let db = sqlx_tracing::testing::TestDatabase::sqlite().await?;
+//! sqlx::query("CREATE TABLE users (name TEXT)").execute(db.pool()).await?;
+//! // ...
+//! db.teardown().await?;
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Number of test databases created by this process, to name them uniquely.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Returns a name unique to this process and test database, e.g.
+/// `sqlx_tracing_test_1234_1`.
+fn unique_name() -> String {
+    format!(
+        "sqlx_tracing_test_{}_{}",
+        std::process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Temporary database with a traced pool connected to it.
+///
+/// The pool is named after the database, see
+/// [`PoolBuilder::with_pool_name`](crate::PoolBuilder::with_pool_name). Call
+/// [`teardown`](Self::teardown) at the end of the test to close the pool and
+/// drop the database. Dropping a SQLite test database removes its file as
+/// well, while a Postgres schema can only be dropped by `teardown`.
+#[derive(Debug)]
+pub struct TestDatabase<DB>
+where
+    DB: sqlx::Database,
+{
+    pool: crate::Pool<DB>,
+    name: String,
+    cleanup: Option<Cleanup>,
+}
+
+/// What to remove when a test database is torn down.
+#[derive(Debug)]
+enum Cleanup {
+    #[cfg(feature = "sqlite")]
+    File(std::path::PathBuf),
+    #[cfg(feature = "postgres")]
+    Schema(crate::Pool<sqlx::Postgres>),
+}
+
+impl<DB> TestDatabase<DB>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+{
+    /// Returns the traced pool connected to the test database.
+    pub fn pool(&self) -> &crate::Pool<DB> {
+        &self.pool
+    }
+
+    /// Returns the name of the test database, i.e. the stem of the SQLite
+    /// file or the Postgres schema.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Closes the pool and drops the test database.
+    pub async fn teardown(mut self) -> Result<(), sqlx::Error> {
+        self.pool.close().await;
+        match self.cleanup.take() {
+            #[cfg(feature = "sqlite")]
+            Some(Cleanup::File(path)) => remove_files(&path).map_err(sqlx::Error::Io),
+            #[cfg(feature = "postgres")]
+            Some(Cleanup::Schema(admin)) => {
+                let sql = format!("DROP SCHEMA \"{}\" CASCADE", self.name);
+                let res = sqlx::Executor::execute(&admin, sql.as_str()).await;
+                admin.close().await;
+                res.map(drop)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<DB> Drop for TestDatabase<DB>
+where
+    DB: sqlx::Database,
+{
+    fn drop(&mut self) {
+        #[cfg(feature = "sqlite")]
+        if let Some(Cleanup::File(path)) = &self.cleanup {
+            // Best effort, the pool may still hold the file open.
+            let _ = remove_files(path);
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl TestDatabase<sqlx::Sqlite> {
+    /// Creates a SQLite database in a temporary file.
+    pub async fn sqlite() -> Result<Self, sqlx::Error> {
+        let name = unique_name();
+        let path = std::env::temp_dir().join(format!("{name}.db"));
+        let options = sqlx::sqlite::SqliteConnectOptions::new()
+            .filename(&path)
+            .create_if_missing(true);
+        let pool = crate::PoolOptions::<sqlx::Sqlite>::new()
+            .connect_with(options)
+            .await?
+            .with_pool_name(name.as_str())
+            .build();
+        Ok(Self {
+            pool,
+            name,
+            cleanup: Some(Cleanup::File(path)),
+        })
+    }
+}
+
+/// Removes the SQLite database file at `path`, along with its journal files.
+#[cfg(feature = "sqlite")]
+fn remove_files(path: &std::path::Path) -> std::io::Result<()> {
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let mut journal = path.as_os_str().to_owned();
+        journal.push(suffix);
+        match std::fs::remove_file(journal) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+    }
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        res => res,
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl TestDatabase<sqlx::Postgres> {
+    /// Creates a schema in the Postgres database at `url`, and connects a
+    /// pool whose `search_path` is that schema.
+    ///
+    /// Unqualified tables of the tests are created in the schema, so several
+    /// tests can share a database concurrently.
+    pub async fn postgres(url: &str) -> Result<Self, sqlx::Error> {
+        let name = unique_name();
+        let options: sqlx::postgres::PgConnectOptions = url.parse()?;
+        let admin =
+            crate::PoolOptions::from(sqlx::postgres::PgPoolOptions::new().max_connections(1))
+                .connect_with(options.clone())
+                .await?
+                .with_pool_name(format!("{name}.admin"))
+                .build();
+        let sql = format!("CREATE SCHEMA \"{name}\"");
+        sqlx::Executor::execute(&admin, sql.as_str()).await?;
+        let pool = crate::PoolOptions::<sqlx::Postgres>::new()
+            .connect_with(options.options([("search_path", name.as_str())]))
+            .await?
+            .with_pool_name(name.as_str())
+            .build();
+        Ok(Self {
+            pool,
+            name,
+            cleanup: Some(Cleanup::Schema(admin)),
+        })
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Number of fingerprints above which expired windows are pruned.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Limits how often the details of identical errors are recorded, set with
+/// [`PoolBuilder::with_error_detail_rate_limit`](crate::PoolBuilder::with_error_detail_rate_limit).
+#[derive(Debug)]
+pub(crate) struct ErrorThrottle {
+    max: u32,
+    period: Duration,
+    windows: Mutex<HashMap<u64, Window>>,
+}
+
+/// Details recorded for a fingerprint within the current period.
+#[derive(Debug)]
+struct Window {
+    start: Instant,
+    recorded: u32,
+}
+
+impl ErrorThrottle {
+    pub(crate) fn new(max: u32, period: Duration) -> Self {
+        Self {
+            max,
+            period,
+            windows: Mutex::default(),
+        }
+    }
+
+    /// Returns whether the details of an error with `message` may be
+    /// recorded, counting it when they may.
+    fn allow(&self, message: &str) -> bool {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        message.hash(&mut hasher);
+        let fingerprint = hasher.finish();
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        if windows.len() >= PRUNE_THRESHOLD {
+            windows.retain(|_, window| now.duration_since(window.start) < self.period);
+        }
+        let window = windows.entry(fingerprint).or_insert(Window {
+            start: now,
+            recorded: 0,
+        });
+        if now.duration_since(window.start) >= self.period {
+            *window = Window {
+                start: now,
+                recorded: 0,
+            };
+        }
+        let allowed = window.recorded < self.max;
+        if allowed {
+            window.recorded += 1;
+        }
+        allowed
+    }
+}
+
+/// Whether the message and stacktrace of errors are recorded, possibly
+/// rate-limited per error message.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorDetails {
+    enabled: bool,
+    hash_redacted: bool,
+    throttle: Option<Arc<ErrorThrottle>>,
+    status: Option<crate::status::ErrorStatusMapper>,
+    #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+    metrics: Option<Arc<crate::metrics::PoolMetrics>>,
+}
+
+impl ErrorDetails {
+    pub(crate) fn new(
+        enabled: bool,
+        hash_redacted: bool,
+        throttle: Option<Arc<ErrorThrottle>>,
+        status: Option<crate::status::ErrorStatusMapper>,
+        #[cfg(all(feature = "metrics", not(feature = "disabled")))] metrics: Option<
+            Arc<crate::metrics::PoolMetrics>,
+        >,
+    ) -> Self {
+        Self {
+            enabled,
+            hash_redacted,
+            throttle,
+            status,
+            #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+            metrics,
+        }
+    }
+
+    /// Counts `err` in the error metrics of the pool, if any.
+    #[cfg(all(feature = "metrics", not(feature = "disabled")))]
+    pub(crate) fn count(&self, err: &sqlx::Error) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_error(crate::span::error_category(err));
+        }
+    }
+
+    /// Returns the status of the span of an operation failing with `err`.
+    pub(crate) fn status(&self, err: &sqlx::Error) -> crate::ErrorStatus {
+        self.status
+            .as_ref()
+            .map_or(crate::ErrorStatus::Error, |status| status.status(err))
+    }
+
+    /// Returns whether the messages that are not recorded are hashed.
+    pub(crate) fn hash_redacted(&self) -> bool {
+        self.hash_redacted
+    }
+
+    /// Returns the message of `err` when its details are recorded.
+    pub(crate) fn message(&self, err: &sqlx::Error) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+        let message = err.to_string();
+        self.throttle
+            .as_ref()
+            .is_none_or(|throttle| throttle.allow(&message))
+            .then_some(message)
+    }
+
+    /// Returns whether errors are rate-limited over a zero period.
+    pub(crate) fn zero_period(&self) -> bool {
+        self.throttle
+            .as_ref()
+            .is_some_and(|throttle| throttle.period.is_zero())
+    }
+}
@@ -1,7 +1,176 @@
-use futures::{StreamExt, TryStreamExt};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
 use sqlx::Error;
 use tracing::Instrument;
 
+/// Server-side timeout of the statements of a transaction, set with
+/// [`PoolBuilder::with_statement_timeout`](crate::PoolBuilder::with_statement_timeout).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StatementTimeout {
+    /// The time left before the [`with_deadline`](crate::with_deadline)
+    /// deadline when the transaction begins, if there is one.
+    Deadline,
+    /// A fixed budget, shortened to the time left before the
+    /// [`with_deadline`](crate::with_deadline) deadline, if any.
+    Budget(std::time::Duration),
+}
+
+/// Activity of a transaction, returned by
+/// [`Transaction::commit_with_report`](crate::Transaction::commit_with_report)
+/// and [`Transaction::rollback_with_report`](crate::Transaction::rollback_with_report)
+/// for services to log or attach to business events.
+///
+/// Savepoints report the activity of their enclosing transaction so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransactionReport {
+    /// Number of statements run within the transaction. A script or bulk
+    /// insert counts as one.
+    pub statements: u64,
+    /// Time spent running statements, including the final `COMMIT` or
+    /// `ROLLBACK`.
+    pub database_time: std::time::Duration,
+    /// Time spent idle between statements, as recorded in
+    /// `db.transaction.idle_ms`.
+    pub idle_time: std::time::Duration,
+    /// Number of savepoints created within the transaction.
+    pub savepoints: usize,
+    /// Time from the beginning of the transaction until its end.
+    pub duration: std::time::Duration,
+}
+
+impl StatementTimeout {
+    /// Returns the timeout for a transaction beginning now, if any.
+    fn resolve(self) -> Option<std::time::Duration> {
+        let left = crate::deadline::time_left();
+        match self {
+            Self::Deadline => left,
+            Self::Budget(budget) => Some(left.map_or(budget, |left| left.min(budget))),
+        }
+    }
+}
+
+impl<'c, DB> crate::Transaction<'c, DB>
+where
+    DB: crate::prelude::Database + sqlx::Database,
+{
+    /// Wraps a SQLx transaction, opening its `sqlx.transaction` span in the current context.
+    pub(crate) fn new(
+        inner: sqlx::Transaction<'c, DB>,
+        attributes: Arc<crate::Attributes>,
+        connection_id: u64,
+        queue: Option<crate::queue::QueueSlot>,
+    ) -> Self {
+        let idle = crate::idle::IdleTracker::new(attributes.config().transaction_idle_threshold);
+        let span = crate::instrument_op!(
+            crate::Operation::Transaction,
+            attributes,
+            "db.transaction.id" = idle.id(),
+            // Server-side statement timeout (filled once applied)
+            "db.transaction.statement_timeout_ms" = ::tracing::field::Empty,
+            // Business-level label (filled by `set_label`)
+            "db.transaction.label" = ::tracing::field::Empty,
+        );
+        Self {
+            _run: crate::repeat::RunGuard::new(&attributes, connection_id),
+            inner,
+            idle: Arc::new(idle),
+            savepoints: Default::default(),
+            attributes,
+            span,
+            connection_id,
+            _queue: queue,
+        }
+    }
+
+    /// Adopts a transaction started through SQLx directly, e.g. by a
+    /// framework, so that its remaining statements are traced with
+    /// `attributes`.
+    ///
+    /// The `sqlx.transaction` span only covers the transaction from the time
+    /// it is adopted, and a savepoint is traced as a transaction of its own.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let raw: sqlx::Transaction<'_, sqlx::Postgres> = framework_tx;
+    /// let mut tx = sqlx_tracing::Transaction::from_sqlx(raw, pool.attributes().clone());
+    /// sqlx::query("DELETE FROM carts").execute(&mut tx.executor()).await?;
+    /// tx.commit().await?;
+    /// ```
+    pub fn from_sqlx(inner: sqlx::Transaction<'c, DB>, attributes: crate::Attributes) -> Self {
+        Self::new(
+            inner,
+            Arc::new(attributes),
+            crate::connection::next_id(),
+            None,
+        )
+    }
+
+    /// Applies the statement timeout configured with
+    /// [`PoolBuilder::with_statement_timeout`](crate::PoolBuilder::with_statement_timeout)
+    /// to this newly begun transaction, recording it on its span.
+    pub(crate) async fn apply_statement_timeout(mut self) -> Result<Self, Error> {
+        let Some(timeout) = self
+            .attributes
+            .config()
+            .statement_timeout
+            .and_then(StatementTimeout::resolve)
+        else {
+            return Ok(self);
+        };
+        let Some(fut) = DB::set_statement_timeout(&mut self.inner, timeout) else {
+            return Ok(self);
+        };
+        let record_details = self.attributes.config().error_details();
+        fut.instrument(self.span.clone()).await.inspect_err(|e| {
+            self.span
+                .in_scope(|| crate::span::record_error(e, &record_details))
+        })?;
+        self.span.record(
+            "db.transaction.statement_timeout_ms",
+            timeout.as_millis().max(1) as u64,
+        );
+        Ok(self)
+    }
+
+    /// Labels this transaction for business-level grouping, e.g.
+    /// `"checkout"`.
+    ///
+    /// The label is recorded as `db.transaction.label` on the
+    /// `sqlx.transaction` span, on the commit or rollback span and, within
+    /// `sqlx.context`, on the spans of the statements run afterwards,
+    /// including those of savepoints begun afterwards. The begin span
+    /// completes before the label can be set, so it does not carry it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut tx = pool.begin().await?;
+    /// tx.set_label("checkout");
+    /// ```
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        let label = label.into();
+        self.span.record("db.transaction.label", label.as_str());
+        self.idle.set_label(label);
+    }
+
+    /// Returns the `sqlx.transaction` span covering this transaction.
+    ///
+    /// The span is opened when the transaction begins and closed when it is
+    /// committed, rolled back or dropped. Use it as the parent of application
+    /// spans to group them in the transaction subtree, e.g.
+    /// `tracing::info_span!(parent: tx.span(), "checkout")`.
+    ///
+    /// Note that [`tracing::Span::record`] only applies to fields declared
+    /// when the span is created; arbitrary attributes such as `order.id` can
+    /// be attached with `tracing_opentelemetry::OpenTelemetrySpanExt::set_attribute`.
+    pub fn span(&self) -> &tracing::Span {
+        &self.span
+    }
+}
+
 impl<'c, DB> crate::Transaction<'c, DB>
 where
     DB: crate::prelude::Database + sqlx::Database,
@@ -14,9 +183,51 @@ where
         crate::Connection {
             inner: &mut *self.inner,
             attributes: self.attributes.clone(),
+            idle: Some(self.idle.clone()),
+            connection_id: self.connection_id,
         }
     }
 
+    /// Begins a nested transaction, backed by a savepoint.
+    ///
+    /// The returned [`Transaction`](crate::Transaction) is instrumented for
+    /// tracing and counts towards the `db.transaction.savepoints` recorded on
+    /// the commit and rollback spans of the outer transaction.
+    pub async fn begin(&mut self) -> Result<crate::Transaction<'_, DB>, Error> {
+        use sqlx::Connection;
+        let attributes = self.attributes.clone();
+        let idle = Arc::new(self.idle.nested());
+        let savepoints = self.savepoints.clone();
+        let record_details = attributes.config().error_details();
+        let span = crate::instrument_op!(crate::Operation::Begin, attributes);
+        let inner = async {
+            (*self.inner)
+                .begin()
+                .await
+                .inspect_err(|e| crate::span::record_error(e, &record_details))
+        }
+        .instrument(span)
+        .await?;
+        savepoints.fetch_add(1, Ordering::Relaxed);
+        let span = crate::instrument_op!(
+            crate::Operation::Transaction,
+            attributes,
+            "db.transaction.id" = idle.id(),
+            "db.transaction.savepoint" = idle.depth(),
+            "db.transaction.label" = idle.label(),
+        );
+        Ok(crate::Transaction {
+            inner,
+            attributes,
+            idle,
+            savepoints,
+            span,
+            connection_id: self.connection_id,
+            _queue: None,
+            _run: None,
+        })
+    }
+
     /// Commits this transaction or savepoint.
     ///
     /// This consumes the `Transaction`, sending a `COMMIT` statement to the
@@ -41,17 +252,51 @@ where
     /// tx.commit().await?;
     /// ```
     pub async fn commit(self) -> Result<(), Error> {
+        self.commit_with_report().await.map(drop)
+    }
+
+    /// Commits this transaction or savepoint, returning a report of its
+    /// activity: the number of statements, the time spent running them and
+    /// idle in between, and its duration.
+    ///
+    /// See [`commit`](Self::commit).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let report = tx.commit_with_report().await?;
+    /// tracing::info!(statements = report.statements, "order placed");
+    /// ```
+    pub async fn commit_with_report(self) -> Result<TransactionReport, Error> {
         let attrs = &self.attributes;
-        let record_details = attrs.record_error_details;
-        let span = crate::instrument_op!("sqlx.transaction.commit", attrs);
+        let record_details = attrs.config().error_details();
+        let idle = self.idle.finish().as_millis() as u64;
+        let tracker = self.idle.clone();
+        let savepoints = self.savepoints.load(Ordering::Relaxed);
+        let span = crate::instrument_op!(
+            crate::Operation::Commit,
+            attrs,
+            // Time spent idle between statements
+            "db.transaction.idle_ms" = idle,
+            // Number of savepoints created within the transaction
+            "db.transaction.savepoints" = savepoints,
+            // Number of statements run within the transaction
+            "db.transaction.statements" = tracker.statements(),
+            // Label set with `set_label`
+            "db.transaction.label" = self.idle.label(),
+        );
+        let started = std::time::Instant::now();
         async {
             self.inner
                 .commit()
                 .await
-                .inspect_err(|e| crate::span::record_error(e, record_details))
+                .inspect_err(|e| crate::span::record_error(e, &record_details))
         }
         .instrument(span)
-        .await
+        .await?;
+        let mut report = tracker.report(savepoints);
+        report.database_time += started.elapsed();
+        Ok(report)
     }
 
     /// Aborts this transaction or savepoint.
@@ -83,25 +328,56 @@ where
     /// tx.rollback().await?;
     /// ```
     pub async fn rollback(self) -> Result<(), Error> {
+        self.rollback_with_report().await.map(drop)
+    }
+
+    /// Aborts this transaction or savepoint, returning a report of its
+    /// activity.
+    ///
+    /// See [`rollback`](Self::rollback) and
+    /// [`commit_with_report`](Self::commit_with_report).
+    pub async fn rollback_with_report(self) -> Result<TransactionReport, Error> {
         let attrs = &self.attributes;
-        let record_details = attrs.record_error_details;
-        let span = crate::instrument_op!("sqlx.transaction.rollback", attrs);
+        let record_details = attrs.config().error_details();
+        let idle = self.idle.finish().as_millis() as u64;
+        let tracker = self.idle.clone();
+        let savepoints = self.savepoints.load(Ordering::Relaxed);
+        let span = crate::instrument_op!(
+            crate::Operation::Rollback,
+            attrs,
+            // Time spent idle between statements
+            "db.transaction.idle_ms" = idle,
+            // Number of savepoints created within the transaction
+            "db.transaction.savepoints" = savepoints,
+            // Number of statements run within the transaction
+            "db.transaction.statements" = tracker.statements(),
+            // Label set with `set_label`
+            "db.transaction.label" = self.idle.label(),
+        );
+        let started = std::time::Instant::now();
         async {
             self.inner
                 .rollback()
                 .await
-                .inspect_err(|e| crate::span::record_error(e, record_details))
+                .inspect_err(|e| crate::span::record_error(e, &record_details))
         }
         .instrument(span)
-        .await
+        .await?;
+        let mut report = tracker.report(savepoints);
+        report.database_time += started.elapsed();
+        Ok(report)
     }
 }
 
+#[cfg(feature = "disabled")]
+crate::passthrough_executor!(impl<'c, 't> for &'c mut crate::Transaction<'t, DB>, |tx| &mut *tx.inner);
+
 /// Implements `sqlx::Executor` for a mutable reference to a tracing-instrumented transaction.
 ///
 /// Each method creates a tracing span for the SQL operation, attaches relevant attributes,
 /// and records errors or row counts as appropriate for observability.
-impl<'c, DB> sqlx::Executor<'c> for &'c mut crate::Transaction<'c, DB>
+#[cfg(not(feature = "disabled"))]
+impl<'c, 't, DB> sqlx::Executor<'c> for &'c mut crate::Transaction<'t, DB>
 where
     DB: crate::prelude::Database + sqlx::Database,
     for<'a> &'a mut DB::Connection: sqlx::Executor<'a, Database = DB>,
@@ -115,18 +391,33 @@ where
     fn describe<'e, 'q: 'e>(
         self,
         sql: &'q str,
-    ) -> futures::future::BoxFuture<'e, Result<sqlx::Describe<Self::Database>, sqlx::Error>>
+    ) -> futures_core::future::BoxFuture<'e, Result<sqlx::Describe<Self::Database>, sqlx::Error>>
     where
         'c: 'e,
     {
         let attrs = &self.attributes;
-        let record_details = attrs.record_error_details;
-        let span = crate::instrument!("sqlx.describe", sql, attrs);
+        let record_details = attrs.config().error_details();
+        let connection_id = self.connection_id;
+        let idle = crate::idle::IdleTracker::resume(Some(&self.idle));
+        let span = crate::repeat::span(attrs, Some(connection_id), idle.as_deref(), sql, || {
+            crate::instrument!(
+                @transaction idle.as_deref();
+                crate::Operation::Describe,
+                sql,
+                attrs
+            )
+        });
         Box::pin(
             async move {
                 let fut = (&mut self.inner).describe(sql);
-                fut.await
-                    .inspect_err(|e| crate::span::record_error(e, record_details))
+                let res = fut.await.inspect_err(|e| {
+                    crate::span::record_error(e, &record_details);
+                    crate::span::record_broken(e, Some(connection_id));
+                });
+                if let Some(idle) = idle {
+                    idle.pause();
+                }
+                res
             }
             .instrument(span),
         )
@@ -135,7 +426,7 @@ where
     fn execute<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<<Self::Database as sqlx::Database>::QueryResult, sqlx::Error>,
     >
@@ -143,15 +434,22 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_fut!("sqlx.execute", sql, attrs, (&mut self.inner).execute(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        crate::exec_fut_result!(
+            sql,
+            attrs,
+            query.execute(&mut *self.inner),
+            Some(&self.idle),
+            Some(self.connection_id)
+        )
     }
 
     fn execute_many<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::stream::BoxStream<
+    ) -> futures_core::stream::BoxStream<
         'e,
         Result<<Self::Database as sqlx::Database>::QueryResult, sqlx::Error>,
     >
@@ -159,33 +457,49 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
         crate::exec_stream!(
-            "sqlx.execute_many",
+            crate::Operation::ExecuteMany,
             sql,
             attrs,
-            (&mut self.inner).execute_many(query)
+            query.execute_many(&mut *self.inner),
+            |_| false,
+            Some(&self.idle),
+            Some(self.connection_id)
         )
     }
 
     fn fetch<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::stream::BoxStream<'e, Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>>
+    ) -> futures_core::stream::BoxStream<
+        'e,
+        Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>,
+    >
     where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_stream!("sqlx.fetch", sql, attrs, (&mut self.inner).fetch(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        crate::exec_stream!(
+            crate::Operation::Fetch,
+            sql,
+            attrs,
+            crate::stream::measure_rows::<DB, _>(query.fetch(&mut *self.inner), attrs),
+            |_| true,
+            Some(&self.idle),
+            Some(self.connection_id)
+        )
     }
 
     fn fetch_all<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<Vec<<Self::Database as sqlx::Database>::Row>, sqlx::Error>,
     >
@@ -193,15 +507,22 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_fut_rows!(sql, attrs, (&mut self.inner).fetch_all(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        crate::exec_fut_rows!(
+            sql,
+            attrs,
+            query.fetch_all(&mut *self.inner),
+            Some(&self.idle),
+            Some(self.connection_id)
+        )
     }
 
     fn fetch_many<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::stream::BoxStream<
+    ) -> futures_core::stream::BoxStream<
         'e,
         Result<
             sqlx::Either<
@@ -215,33 +536,47 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
         crate::exec_stream!(
-            "sqlx.fetch_many",
+            crate::Operation::FetchMany,
             sql,
             attrs,
-            (&mut self.inner).fetch_many(query)
+            crate::stream::measure_results::<DB, _>(query.fetch_many(&mut *self.inner), attrs),
+            sqlx::Either::is_right,
+            Some(&self.idle),
+            Some(self.connection_id)
         )
     }
 
     fn fetch_one<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<'e, Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>>
+    ) -> futures_core::future::BoxFuture<
+        'e,
+        Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>,
+    >
     where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_fut_one!(sql, attrs, (&mut self.inner).fetch_one(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        crate::exec_fut_one!(
+            sql,
+            attrs,
+            query.fetch_one(&mut *self.inner),
+            Some(&self.idle),
+            Some(self.connection_id)
+        )
     }
 
     fn fetch_optional<'e, 'q: 'e, E>(
         self,
         query: E,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<Option<<Self::Database as sqlx::Database>::Row>, sqlx::Error>,
     >
@@ -249,15 +584,22 @@ where
         E: 'q + sqlx::Execute<'q, Self::Database>,
         'c: 'e,
     {
-        let sql = query.sql();
         let attrs = &self.attributes;
-        crate::exec_fut_opt!(sql, attrs, (&mut self.inner).fetch_optional(query))
+        let query = crate::span::Query::new(query, attrs);
+        let sql = &query.sql();
+        crate::exec_fut_opt!(
+            sql,
+            attrs,
+            query.fetch_optional(&mut *self.inner),
+            Some(&self.idle),
+            Some(self.connection_id)
+        )
     }
 
     fn prepare<'e, 'q: 'e>(
         self,
         query: &'q str,
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<<Self::Database as sqlx::Database>::Statement<'q>, sqlx::Error>,
     >
@@ -266,10 +608,12 @@ where
     {
         let attrs = &self.attributes;
         crate::exec_fut!(
-            "sqlx.prepare",
+            crate::Operation::Prepare,
             query,
             attrs,
-            (&mut self.inner).prepare(query)
+            crate::span::prepared(query, (&mut self.inner).prepare(query)),
+            Some(&self.idle),
+            Some(self.connection_id)
         )
     }
 
@@ -277,7 +621,7 @@ where
         self,
         sql: &'q str,
         parameters: &'e [<Self::Database as sqlx::Database>::TypeInfo],
-    ) -> futures::future::BoxFuture<
+    ) -> futures_core::future::BoxFuture<
         'e,
         Result<<Self::Database as sqlx::Database>::Statement<'q>, sqlx::Error>,
     >
@@ -286,10 +630,12 @@ where
     {
         let attrs = &self.attributes;
         crate::exec_fut!(
-            "sqlx.prepare_with",
+            crate::Operation::PrepareWith,
             sql,
             attrs,
-            (&mut self.inner).prepare_with(sql, parameters)
+            crate::span::prepared(sql, (&mut self.inner).prepare_with(sql, parameters)),
+            Some(&self.idle),
+            Some(self.connection_id)
         )
     }
 }
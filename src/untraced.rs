@@ -0,0 +1,199 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+thread_local! {
+    static CURRENT: RefCell<bool> = const { RefCell::new(false) };
+}
+
+/// Runs `future` without tracing the database operations started within it.
+///
+/// This silences a specific block, e.g. a tight migration loop, without
+/// switching its code over to the raw SQLx pool returned by
+/// [`Pool::inner`](crate::Pool::inner). The operations get disabled spans and
+/// are not reported to [`on_span_complete`](crate::PoolBuilder::on_span_complete)
+/// callbacks; they still run, and still honor deadlines and statement
+/// timeouts.
+///
+/// Like [`with_deadline`](crate::with_deadline), the flag follows the future
+/// across threads, as it is installed each time the future is polled, but not
+/// into tasks spawned from it.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// sqlx_tracing::untraced(async {
+///     for row in rows {
+///         sqlx::query("INSERT INTO items VALUES (?)").bind(row).execute(&pool).await?;
+///     }
+///     Ok::<_, sqlx::Error>(())
+/// })
+/// .await?;
+/// ```
+pub fn untraced<F: Future>(future: F) -> Untraced<F> {
+    Untraced { inner: future }
+}
+
+pin_project_lite::pin_project! {
+    /// Future returned by [`untraced`].
+    #[derive(Debug)]
+    pub struct Untraced<F> {
+        #[pin]
+        inner: F,
+    }
+}
+
+impl<F: Future> Future for Untraced<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let _scope = crate::scoped::install(&CURRENT, true);
+        self.project().inner.poll(cx)
+    }
+}
+
+/// Returns whether the current operation runs within [`untraced`], or
+/// always when instrumentation is compiled out with the `disabled` feature.
+pub(crate) fn active() -> bool {
+    cfg!(feature = "disabled") || crate::scoped::current(&CURRENT)
+}
+
+/// Helper macro implementing `sqlx::Executor` for a traced executor when
+/// instrumentation is compiled out with the `disabled` feature.
+///
+/// Every operation is handed straight to the SQLx executor returned by
+/// `$inner`, evaluated with `$this` bound to the traced executor: there is no
+/// span, query rewriting, writer queue or explain sampling, nor any boxing
+/// beyond SQLx's own.
+#[cfg(feature = "disabled")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! passthrough_executor {
+    (impl<$executor:lifetime $(, $lifetime:lifetime)*> for $ty:ty, |$this:ident| $inner:expr) => {
+        impl<$executor, $($lifetime,)* DB> sqlx::Executor<$executor> for $ty
+        where
+            DB: $crate::prelude::Database + sqlx::Database,
+            for<'a> &'a mut DB::Connection: sqlx::Executor<'a, Database = DB>,
+        {
+            type Database = DB;
+
+            fn fetch_many<'e, 'q: 'e, E>(
+                self,
+                query: E,
+            ) -> futures_core::stream::BoxStream<
+                'e,
+                Result<sqlx::Either<DB::QueryResult, DB::Row>, sqlx::Error>,
+            >
+            where
+                $executor: 'e,
+                E: 'q + sqlx::Execute<'q, DB>,
+            {
+                let $this = self;
+                $inner.fetch_many(query)
+            }
+
+            fn fetch_optional<'e, 'q: 'e, E>(
+                self,
+                query: E,
+            ) -> futures_core::future::BoxFuture<'e, Result<Option<DB::Row>, sqlx::Error>>
+            where
+                $executor: 'e,
+                E: 'q + sqlx::Execute<'q, DB>,
+            {
+                let $this = self;
+                $inner.fetch_optional(query)
+            }
+
+            fn prepare_with<'e, 'q: 'e>(
+                self,
+                sql: &'q str,
+                parameters: &'e [DB::TypeInfo],
+            ) -> futures_core::future::BoxFuture<'e, Result<DB::Statement<'q>, sqlx::Error>>
+            where
+                $executor: 'e,
+            {
+                let $this = self;
+                $inner.prepare_with(sql, parameters)
+            }
+
+            #[doc(hidden)]
+            fn describe<'e, 'q: 'e>(
+                self,
+                sql: &'q str,
+            ) -> futures_core::future::BoxFuture<'e, Result<sqlx::Describe<DB>, sqlx::Error>>
+            where
+                $executor: 'e,
+            {
+                let $this = self;
+                $inner.describe(sql)
+            }
+        }
+    };
+}
+
+/// Helper macros standing in for the `exec_*` macros of the `span` module
+/// when instrumentation is compiled out with the `disabled` feature.
+///
+/// They return the future or stream of the operation as is, only boxed like
+/// the instrumented ones; the remaining arguments are evaluated and dropped.
+#[cfg(feature = "disabled")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! exec_fut {
+    (@record $record:expr; $operation:expr, $sql:expr, $attrs:expr, $fut:expr) => {{
+        let _ = ($operation, $sql, &$attrs);
+        Box::pin($fut)
+    }};
+    ($operation:expr, $sql:expr, $attrs:expr, $fut:expr $(, $extra:expr)*) => {{
+        let _ = ($operation, $sql, &$attrs $(, $extra)*);
+        Box::pin($fut)
+    }};
+}
+
+#[cfg(feature = "disabled")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! exec_fut_result {
+    ($sql:expr, $attrs:expr, $fut:expr $(, $extra:expr)*) => {{
+        let _ = ($sql, &$attrs $(, $extra)*);
+        Box::pin($fut)
+    }};
+}
+
+#[cfg(feature = "disabled")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! exec_fut_rows {
+    ($($args:tt)*) => {
+        $crate::exec_fut_result!($($args)*)
+    };
+}
+
+#[cfg(feature = "disabled")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! exec_fut_one {
+    ($($args:tt)*) => {
+        $crate::exec_fut_result!($($args)*)
+    };
+}
+
+#[cfg(feature = "disabled")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! exec_fut_opt {
+    ($($args:tt)*) => {
+        $crate::exec_fut_result!($($args)*)
+    };
+}
+
+#[cfg(feature = "disabled")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! exec_stream {
+    ($operation:expr, $sql:expr, $attrs:expr, $stream:expr, $is_row:expr $(, $extra:expr)*) => {{
+        let _ = ($operation, $sql, &$attrs $(, $extra)*);
+        Box::pin($stream)
+    }};
+}
@@ -0,0 +1,122 @@
+/// Error returned by [`PoolBuilder::try_build`](crate::PoolBuilder::try_build)
+/// for an invalid or conflicting configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BuildError {
+    /// The name recorded as `peer.service` is empty.
+    EmptyName,
+    /// The pool name recorded as `db.client.pool.name` is empty.
+    EmptyPoolName,
+    /// The system name overriding `db.system.name` is empty.
+    EmptySystemName,
+    /// An operation given to
+    /// [`with_query_text_operations`](crate::PoolBuilder::with_query_text_operations)
+    /// is not a single SQL keyword, so it never matches a statement.
+    InvalidOperation(String),
+    /// Query text operations are set while query text recording is disabled.
+    OperationsWithoutQueryText,
+    /// The transaction idle threshold is zero, which warns on every
+    /// statement of every transaction.
+    ZeroIdleThreshold,
+    /// The statement timeout budget is zero, which Postgres takes as no
+    /// timeout at all.
+    ZeroStatementTimeout,
+    /// The slow acquisition threshold is zero, which reports every
+    /// acquisition.
+    ZeroAcquireThreshold,
+    /// The error detail rate limit has a zero period, which does not limit
+    /// anything.
+    ZeroRateLimitPeriod,
+    /// The ping query is empty.
+    EmptyPingQuery,
+    /// Baggage keys are set while baggage recording is disabled.
+    #[cfg(feature = "opentelemetry")]
+    BaggageKeysWithoutRecording,
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyName => f.write_str("the pool's peer.service name is empty"),
+            Self::EmptyPoolName => f.write_str("the pool name is empty"),
+            Self::EmptySystemName => f.write_str("the db.system.name override is empty"),
+            Self::InvalidOperation(operation) => write!(
+                f,
+                "query text operation {operation:?} is not a single SQL keyword"
+            ),
+            Self::OperationsWithoutQueryText => {
+                f.write_str("query text operations are set but query text recording is disabled")
+            }
+            Self::ZeroIdleThreshold => f.write_str("the transaction idle threshold is zero"),
+            Self::ZeroStatementTimeout => f.write_str("the statement timeout budget is zero"),
+            Self::ZeroAcquireThreshold => f.write_str("the slow acquisition threshold is zero"),
+            Self::ZeroRateLimitPeriod => f.write_str("the error detail rate limit period is zero"),
+            Self::EmptyPingQuery => f.write_str("the ping query is empty"),
+            #[cfg(feature = "opentelemetry")]
+            Self::BaggageKeysWithoutRecording => {
+                f.write_str("baggage keys are set but baggage recording is disabled")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Checks the `attributes` and `config` of a pool for invalid or conflicting
+/// options.
+pub(crate) fn validate(
+    attributes: &crate::Attributes,
+    config: &crate::config::Config,
+) -> Result<(), BuildError> {
+    if attributes.name.as_deref().is_some_and(str::is_empty) {
+        return Err(BuildError::EmptyName);
+    }
+    if attributes.pool_name.as_deref().is_some_and(str::is_empty) {
+        return Err(BuildError::EmptyPoolName);
+    }
+    if attributes.system_name.as_deref().is_some_and(str::is_empty) {
+        return Err(BuildError::EmptySystemName);
+    }
+    if let Some(operations) = &config.query_text_operations {
+        if !config.record_query_text {
+            return Err(BuildError::OperationsWithoutQueryText);
+        }
+        if let Some(operation) = operations.iter().find(|operation| {
+            operation.is_empty() || !operation.chars().all(|c| c.is_ascii_alphabetic())
+        }) {
+            return Err(BuildError::InvalidOperation(operation.clone()));
+        }
+    }
+    if config
+        .transaction_idle_threshold
+        .is_some_and(|threshold| threshold.is_zero())
+    {
+        return Err(BuildError::ZeroIdleThreshold);
+    }
+    if let Some(crate::StatementTimeout::Budget(budget)) = config.statement_timeout
+        && budget.is_zero()
+    {
+        return Err(BuildError::ZeroStatementTimeout);
+    }
+    if config
+        .acquire_slow_threshold
+        .is_some_and(|threshold| threshold.is_zero())
+    {
+        return Err(BuildError::ZeroAcquireThreshold);
+    }
+    if config.error_details().zero_period() {
+        return Err(BuildError::ZeroRateLimitPeriod);
+    }
+    if config
+        .ping_query
+        .as_deref()
+        .is_some_and(|query| query.trim().is_empty())
+    {
+        return Err(BuildError::EmptyPingQuery);
+    }
+    #[cfg(feature = "opentelemetry")]
+    if config.baggage_keys.is_some() && !config.record_baggage {
+        return Err(BuildError::BaggageKeysWithoutRecording);
+    }
+    Ok(())
+}
@@ -0,0 +1,48 @@
+#![cfg(all(feature = "sqlite", feature = "runtime-async-std"))]
+
+use sqlx::Sqlite;
+
+#[async_std::test]
+async fn transaction_commit() {
+    let pool = sqlx::pool::PoolOptions::<Sqlite>::new()
+        .max_connections(1)
+        .connect(":memory:")
+        .await
+        .map(sqlx_tracing::Pool::from)
+        .unwrap();
+
+    sqlx::query("CREATE TABLE test_commit (id INTEGER PRIMARY KEY, value TEXT NOT NULL)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query("INSERT INTO test_commit (value) VALUES ('hello')")
+        .execute(&mut tx.executor())
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let count: (i32,) = sqlx::query_as("SELECT COUNT(*) FROM test_commit")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count.0, 1);
+}
+
+#[async_std::test]
+async fn pool_close_with_timeout() {
+    let pool = sqlx::SqlitePool::connect(":memory:")
+        .await
+        .map(sqlx_tracing::Pool::from)
+        .unwrap();
+
+    // The timeout relies on a runtime-agnostic timer.
+    let conn = pool.acquire().await.unwrap();
+    assert!(
+        !pool
+            .close_with_timeout(std::time::Duration::from_millis(10))
+            .await
+    );
+    drop(conn);
+}
@@ -0,0 +1,152 @@
+//! Test helper capturing the spans and events emitted while it is installed,
+//! along with the values recorded on their fields.
+
+// Each test crate only uses part of the helper.
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::DefaultGuard;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// A span or event, with the values recorded on its fields so far.
+#[derive(Clone, Debug, Default)]
+pub struct Captured {
+    pub name: String,
+    /// Name of the parent span, if any.
+    pub parent: Option<String>,
+    pub fields: BTreeMap<String, String>,
+}
+
+impl Captured {
+    /// Returns the value recorded on `field`, if any.
+    pub fn field(&self, field: &str) -> Option<&str> {
+        self.fields.get(field).map(String::as_str)
+    }
+
+    /// Returns the value recorded on `field`, parsed as a number.
+    pub fn number(&self, field: &str) -> u64 {
+        let value = self.field(field);
+        value
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_else(|| panic!("{}: {field} is not a number: {value:?}", self.name))
+    }
+}
+
+#[derive(Default)]
+struct Captures {
+    spans: Vec<Captured>,
+    events: Vec<Captured>,
+}
+
+/// Captures the spans and events of the current thread until dropped.
+///
+/// Tests run on a current-thread runtime, so this also covers the tasks they
+/// spawn.
+pub struct Capture {
+    captures: Arc<Mutex<Captures>>,
+    _guard: DefaultGuard,
+}
+
+impl Capture {
+    pub fn install() -> Self {
+        let captures = Arc::new(Mutex::new(Captures::default()));
+        let layer = CaptureLayer(captures.clone());
+        let subscriber = tracing_subscriber::registry().with(layer);
+        Self {
+            captures,
+            _guard: tracing::subscriber::set_default(subscriber),
+        }
+    }
+
+    /// Returns the spans named `name`, in creation order.
+    pub fn spans(&self, name: &str) -> Vec<Captured> {
+        let captures = self.captures.lock().unwrap();
+        captures
+            .spans
+            .iter()
+            .filter(|span| span.name == name)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the last span named `name`.
+    pub fn span(&self, name: &str) -> Captured {
+        self.spans(name)
+            .pop()
+            .unwrap_or_else(|| panic!("no {name} span"))
+    }
+
+    /// Returns the events named `name`, in emission order.
+    pub fn events(&self, name: &str) -> Vec<Captured> {
+        let captures = self.captures.lock().unwrap();
+        captures
+            .events
+            .iter()
+            .filter(|event| event.name == name)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Position of a span in [`Captures::spans`], stored in its extensions.
+struct Index(usize);
+
+struct CaptureLayer(Arc<Mutex<Captures>>);
+
+impl<S> Layer<S> for CaptureLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("span is registered");
+        let mut captured = Captured {
+            name: attrs.metadata().name().to_owned(),
+            parent: span.parent().map(|parent| parent.name().to_owned()),
+            fields: BTreeMap::new(),
+        };
+        attrs.record(&mut Fields(&mut captured.fields));
+        let mut captures = self.0.lock().unwrap();
+        span.extensions_mut().insert(Index(captures.spans.len()));
+        captures.spans.push(captured);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("span is registered");
+        let extensions = span.extensions();
+        let Some(Index(index)) = extensions.get::<Index>() else {
+            return;
+        };
+        let mut captures = self.0.lock().unwrap();
+        values.record(&mut Fields(&mut captures.spans[*index].fields));
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut captured = Captured {
+            name: event.metadata().name().to_owned(),
+            parent: ctx.event_span(event).map(|span| span.name().to_owned()),
+            fields: BTreeMap::new(),
+        };
+        event.record(&mut Fields(&mut captured.fields));
+        self.0.lock().unwrap().events.push(captured);
+    }
+}
+
+/// Records field values as strings, without quoting string values.
+struct Fields<'a>(&'a mut BTreeMap<String, String>);
+
+impl Visit for Fields<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), value.to_owned());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_owned(), format!("{value:?}"));
+    }
+}
@@ -36,7 +36,7 @@ pub async fn should_trace<'c, DB, E>(
     let entry = scope_span.first_span().unwrap();
     assert_eq!(entry.name, name);
     let next = traces
-        .find_child(&entry.span_id, "sqlx.fetch_optional")
+        .find_child(&entry.span_id, sqlx_tracing::names::FETCH_OPTIONAL)
         .unwrap();
     assert_eq!(next.string_attribute("db.system.name").unwrap(), system);
     assert_eq!(next.string_attribute("db.query.text").unwrap(), "select 1");
@@ -0,0 +1,76 @@
+#![cfg(all(feature = "sqlite", feature = "disabled"))]
+
+mod capture;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn operations_run_without_spans() {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = records.clone();
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .on_span_complete(move |record| sink.lock().unwrap().push(record.clone()))
+        .build();
+
+    sqlx::query("CREATE TABLE items (id INTEGER)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query("INSERT INTO items VALUES (1), (2)")
+        .execute(&mut tx.executor())
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM items")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+
+    // Nothing is traced, so no statement record completes.
+    assert!(records.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn statements_go_straight_to_sqlx() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        // Would break every statement if it were applied.
+        .with_query_rewriter(|_| "not a statement".into())
+        .build();
+
+    let one: i64 = sqlx::query_scalar("SELECT 1")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(one, 1);
+
+    // Neither are deadlines enforced on statements.
+    let deadline = Instant::now() - Duration::from_millis(1);
+    sqlx_tracing::with_deadline(deadline, sqlx::query("SELECT 1").execute(&pool))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn no_events_are_emitted() {
+    let capture = capture::Capture::install();
+
+    sqlx_tracing::record::broken(&sqlx::Error::WorkerCrashed, None);
+    // Dropped with an open connection, without being closed.
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::Pool::from(pool);
+    sqlx::query("SELECT 1").execute(&pool).await.unwrap();
+    assert!(pool.size() > 0);
+    drop(pool);
+
+    assert!(
+        capture
+            .events(sqlx_tracing::names::CONNECTION_BROKEN)
+            .is_empty()
+    );
+    assert!(capture.events(sqlx_tracing::names::POOL_DROPPED).is_empty());
+}
@@ -0,0 +1,108 @@
+#![cfg(all(feature = "sqlite", feature = "metrics", not(feature = "disabled")))]
+
+// The meter provider is global, so the metrics of this test are kept apart
+// from those of the other test binaries.
+
+use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData};
+use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+
+#[tokio::test]
+async fn operation_errors_metric() {
+    let exporter = InMemoryMetricExporter::default();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    opentelemetry::global::set_meter_provider(provider.clone());
+
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_pool_name("errors")
+        .build();
+    sqlx::query("CREATE TABLE users (id INTEGER PRIMARY KEY)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Errors of statements on connections are counted with the pool's.
+    let mut conn = pool.acquire().await.unwrap();
+    assert!(
+        sqlx::query("SELECT * FROM missing_table")
+            .fetch_all(&mut conn)
+            .await
+            .is_err()
+    );
+    drop(conn);
+    sqlx::query("INSERT INTO users VALUES (1)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert!(
+        sqlx::query("INSERT INTO users VALUES (1)")
+            .execute(&pool)
+            .await
+            .is_err()
+    );
+
+    // Expected errors are not counted.
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let expected = sqlx_tracing::PoolBuilder::from(pool)
+        .with_pool_name("expected")
+        .with_error_status(|err| match err.as_database_error() {
+            Some(db_err) if db_err.is_unique_violation() => sqlx_tracing::ErrorStatus::Ok,
+            _ => sqlx_tracing::ErrorStatus::Error,
+        })
+        .build();
+    sqlx::query("CREATE TABLE users (id INTEGER PRIMARY KEY)")
+        .execute(&expected)
+        .await
+        .unwrap();
+    for _ in 0..2 {
+        let _ = sqlx::query("INSERT INTO users VALUES (1)")
+            .execute(&expected)
+            .await;
+    }
+    assert!(
+        sqlx::query("SELECT * FROM missing_table")
+            .fetch_all(&expected)
+            .await
+            .is_err()
+    );
+
+    provider.force_flush().unwrap();
+    let metrics = exporter.get_finished_metrics().unwrap();
+    let errors = metrics
+        .iter()
+        .flat_map(|metrics| metrics.scope_metrics())
+        .flat_map(|scope| scope.metrics())
+        .find(|metric| metric.name() == "db.client.operation.errors")
+        .expect("no db.client.operation.errors metric");
+    let AggregatedMetrics::U64(MetricData::Sum(sum)) = errors.data() else {
+        panic!("unexpected data: {:?}", errors.data());
+    };
+    let mut counts = sum
+        .data_points()
+        .map(|point| {
+            let attribute = |key: &str| {
+                point
+                    .attributes()
+                    .find(|attribute| attribute.key.as_str() == key)
+                    .map(|attribute| attribute.value.to_string())
+                    .unwrap()
+            };
+            (
+                attribute("db.client.connection.pool.name"),
+                attribute("error.category"),
+                point.value(),
+            )
+        })
+        .collect::<Vec<_>>();
+    counts.sort();
+    assert_eq!(
+        counts,
+        [
+            ("errors".to_owned(), "constraint".to_owned(), 1),
+            ("errors".to_owned(), "other".to_owned(), 1),
+            ("expected".to_owned(), "other".to_owned(), 1),
+        ]
+    );
+}
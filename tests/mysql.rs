@@ -0,0 +1,66 @@
+#![cfg(feature = "mysql")]
+
+use std::time::Duration;
+
+use sqlx::MySql;
+use testcontainers::{
+    GenericImage, ImageExt,
+    core::{ContainerPort, WaitFor},
+    runners::AsyncRunner,
+};
+
+#[derive(Debug)]
+struct MySqlContainer {
+    container: testcontainers::ContainerAsync<testcontainers::GenericImage>,
+}
+
+impl MySqlContainer {
+    async fn create() -> Self {
+        let container = GenericImage::new("mysql", "8.4")
+            .with_wait_for(WaitFor::message_on_stderr(
+                "port: 3306  MySQL Community Server",
+            ))
+            .with_exposed_port(ContainerPort::Tcp(3306))
+            .with_env_var("MYSQL_ALLOW_EMPTY_PASSWORD", "yes")
+            .with_env_var("MYSQL_DATABASE", "test")
+            .with_startup_timeout(Duration::from_secs(120))
+            .start()
+            .await
+            .expect("starting a mysql database");
+
+        Self { container }
+    }
+
+    async fn builder(&self) -> sqlx_tracing::PoolBuilder<MySql> {
+        let port = self.container.get_host_port_ipv4(3306).await.unwrap();
+        let url = format!("mysql://root@localhost:{port}/test");
+        sqlx::MySqlPool::connect(&url)
+            .await
+            .map(sqlx_tracing::PoolBuilder::from)
+            .unwrap()
+    }
+}
+
+#[tokio::test]
+async fn result_metadata_recording() {
+    let container = MySqlContainer::create().await;
+    let pool = container
+        .builder()
+        .await
+        .with_result_metadata_recording(true)
+        .build();
+
+    sqlx::query(
+        "CREATE TABLE test_insert_id (id INT AUTO_INCREMENT PRIMARY KEY, value TEXT NOT NULL)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // The execute span records `db.mysql.last_insert_id`.
+    let result = sqlx::query("INSERT INTO test_insert_id (value) VALUES ('hello')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(result.last_insert_id(), 1);
+}
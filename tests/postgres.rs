@@ -9,8 +9,11 @@ use testcontainers::{
     runners::AsyncRunner,
 };
 
+mod capture;
 mod common;
 
+use capture::Capture;
+
 #[derive(Debug)]
 struct PostgresContainer {
     container: testcontainers::ContainerAsync<testcontainers::GenericImage>,
@@ -26,6 +29,8 @@ impl PostgresContainer {
             .with_env_var("POSTGRES_USER", "postgres")
             .with_env_var("POSTGRES_DB", "postgres")
             .with_env_var("POSTGRES_HOST_AUTH_METHOD", "trust")
+            // Allow two-phase commit
+            .with_cmd(["postgres", "-c", "max_prepared_transactions=10"])
             .with_startup_timeout(Duration::from_secs(60))
             .start()
             .await
@@ -300,3 +305,475 @@ async fn transaction_drop_rolls_back() {
         .unwrap();
     assert_eq!(count.0, 0);
 }
+
+#[tokio::test]
+async fn pool_from_options() {
+    let container = PostgresContainer::create().await;
+    let port = container.container.get_host_port_ipv4(5432).await.unwrap();
+
+    let options = sqlx::postgres::PgConnectOptions::new()
+        .host("localhost")
+        .port(port)
+        .username("postgres")
+        .database("postgres");
+    let pool = sqlx_tracing::Pool::from_options(options, sqlx::postgres::PgPoolOptions::new());
+
+    let result: (i32,) = sqlx::query_as("SELECT 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(result.0, 1);
+}
+
+#[tokio::test]
+async fn bulk_copy_loads_rows() {
+    let container = PostgresContainer::create().await;
+    let pool = container.client().await;
+
+    sqlx::query("CREATE TABLE test_copy (id INTEGER NOT NULL, value TEXT)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let rows = vec![
+        [Some("1"), Some("tab\there")],
+        [Some("2"), Some("back\\slash")],
+        [Some("3"), None],
+    ];
+    let copied = pool
+        .bulk_copy("test_copy", &["id", "value"], rows)
+        .await
+        .unwrap();
+    assert_eq!(copied, 3);
+
+    let values: Vec<(Option<String>,)> = sqlx::query_as("SELECT value FROM test_copy ORDER BY id")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        values,
+        vec![
+            (Some("tab\there".to_string()),),
+            (Some("back\\slash".to_string()),),
+            (None,),
+        ]
+    );
+
+    // Table and column names are quoted identifiers.
+    sqlx::query(r#"CREATE TABLE "Test_Copy" ("Value" TEXT, "dotted.name" TEXT)"#)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let copied = pool
+        .bulk_copy(
+            "public.Test_Copy",
+            &["Value", "dotted.name"],
+            [[Some("quoted"), Some("whole")]],
+        )
+        .await
+        .unwrap();
+    assert_eq!(copied, 1);
+}
+
+#[tokio::test]
+async fn acquire_with_ssl_disabled() {
+    let container = PostgresContainer::create().await;
+    let port = container.container.get_host_port_ipv4(5432).await.unwrap();
+
+    let options = sqlx::postgres::PgConnectOptions::new()
+        .host("localhost")
+        .port(port)
+        .username("postgres")
+        .database("postgres")
+        .ssl_mode(sqlx::postgres::PgSslMode::Disable);
+    let pool = sqlx_tracing::Pool::from_options(options, sqlx::postgres::PgPoolOptions::new());
+
+    let capture = Capture::install();
+    let mut conn = pool.acquire().await.unwrap();
+    conn.ping().await.unwrap();
+
+    let span = capture.span("sqlx.pool.acquire");
+    assert_eq!(span.field("db.ssl_mode"), Some("disable"));
+    assert_eq!(span.field("tls.established"), Some("false"));
+}
+
+#[tokio::test]
+async fn terminated_connection_is_broken() {
+    let container = PostgresContainer::create().await;
+    let pool = container.client().await;
+
+    let mut conn = pool.acquire().await.unwrap();
+    let (pid,): (i32,) = sqlx::query_as("SELECT pg_backend_pid()")
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+
+    // Terminate the backend from another connection; the next statement on
+    // `conn` fails and emits a `sqlx.connection.broken` event.
+    sqlx::query("SELECT pg_terminate_backend($1)")
+        .bind(pid)
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert!(sqlx::query("SELECT 1").execute(&mut conn).await.is_err());
+}
+
+#[tokio::test]
+async fn explain_sampling() {
+    let container = PostgresContainer::create().await;
+    let port = container.container.get_host_port_ipv4(5432).await.unwrap();
+    let url = format!("postgres://postgres@localhost:{port}/postgres");
+    let pool = sqlx::PgPool::connect(&url).await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_explain_sampling(2)
+        .with_explain_analyze(true)
+        .build();
+
+    sqlx::query("CREATE TABLE test_explain AS SELECT generate_series(1, 100) AS id")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let capture = Capture::install();
+    // Only `SELECT` statements count towards the rate, so the first one is
+    // sampled despite the `UPDATE` before it, and the second one is not.
+    sqlx::query("UPDATE test_explain SET id = id WHERE id = 0 RETURNING id")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    for _ in 0..2 {
+        let rows: Vec<(i32,)> = sqlx::query_as("SELECT id FROM test_explain WHERE id > $1")
+            .bind(90)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 10);
+    }
+
+    // The plan is explained in the background, on a `sqlx.explain` span
+    // recording the 100 rows examined by the scan next to the 10 returned.
+    let span = loop {
+        let spans = capture.spans("sqlx.explain");
+        if let Some(span) = spans.first()
+            && span.field("db.postgres.rows_examined").is_some()
+        {
+            assert_eq!(spans.len(), 1);
+            break span.clone();
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    };
+    assert_eq!(span.number("db.postgres.rows_examined"), 100);
+    assert_eq!(span.number("db.response.returned_rows"), 10);
+    assert!(span.field("db.postgres.plan_cost").is_some());
+    assert!(span.field("db.postgres.rows_estimated").is_some());
+}
+
+#[tokio::test]
+async fn explain_sampling_rewritten_sql() {
+    let container = PostgresContainer::create().await;
+    let port = container.container.get_host_port_ipv4(5432).await.unwrap();
+    let url = format!("postgres://postgres@localhost:{port}/postgres");
+    let pool = sqlx::PgPool::connect(&url).await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_explain_sampling(1)
+        .with_query_rewriter(|sql| sql.replace("explain_source", "explain_target").into())
+        .build();
+
+    sqlx::query("CREATE TABLE explain_target AS SELECT generate_series(1, 100) AS id")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let capture = Capture::install();
+    let rows: Vec<(i32,)> = sqlx::query_as("SELECT id FROM explain_source WHERE id > $1")
+        .bind(90)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 10);
+
+    // The rewritten statement is explained, the original table not existing.
+    let span = loop {
+        let spans = capture.spans("sqlx.explain");
+        if let Some(span) = spans.first()
+            && (span.field("db.postgres.rows_estimated").is_some()
+                || span.field("error.type").is_some())
+        {
+            break span.clone();
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    };
+    assert!(
+        span.field("db.query.text")
+            .unwrap()
+            .contains("explain_target")
+    );
+    assert!(span.field("error.type").is_none());
+}
+
+#[tokio::test]
+async fn listener_notifications() {
+    use futures::TryStreamExt;
+
+    let container = PostgresContainer::create().await;
+    let pool = container.client().await;
+
+    let mut notifications = pool
+        .listener()
+        .await
+        .unwrap()
+        .notifications("events")
+        .await
+        .unwrap();
+
+    // Each notification gets its own `sqlx.notification` span, recording the
+    // delivery latency from the embedded timestamp.
+    sqlx::query(
+        "SELECT pg_notify('events', json_build_object('timestamp', extract(epoch from clock_timestamp()))::text)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let notification = notifications.try_next().await.unwrap().unwrap();
+    assert_eq!(notification.channel(), "events");
+    assert!(notification.payload().contains("timestamp"));
+}
+
+#[tokio::test]
+async fn maintenance_helpers() {
+    let container = PostgresContainer::create().await;
+    let pool = container.client().await;
+
+    sqlx::query("CREATE TABLE test_maintenance (id INTEGER PRIMARY KEY)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Each helper gets its own `sqlx.maintenance.*` span.
+    pool.vacuum(Some("test_maintenance")).await.unwrap();
+    pool.analyze(None).await.unwrap();
+    pool.reindex("test_maintenance").await.unwrap();
+
+    // Table names are quoted identifiers, case-sensitive and never run as SQL.
+    sqlx::query(r#"CREATE TABLE "Test_Maintenance" (id INTEGER PRIMARY KEY)"#)
+        .execute(&pool)
+        .await
+        .unwrap();
+    pool.vacuum(Some("public.Test_Maintenance")).await.unwrap();
+    pool.reindex("Test_Maintenance").await.unwrap();
+    assert!(
+        pool.analyze(Some("test_maintenance; DROP TABLE test_maintenance"))
+            .await
+            .is_err()
+    );
+    pool.reindex("test_maintenance").await.unwrap();
+}
+
+#[tokio::test]
+async fn transaction_statement_timeout() {
+    use std::time::{Duration, Instant};
+
+    let container = PostgresContainer::create().await;
+    let port = container.container.get_host_port_ipv4(5432).await.unwrap();
+    let url = format!("postgres://postgres@localhost:{port}/postgres");
+    let pool = sqlx::PgPool::connect(&url).await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_statement_timeout(sqlx_tracing::StatementTimeout::Budget(Duration::from_secs(
+            5,
+        )))
+        .build();
+
+    // The budget is shortened to the time left before the deadline, and the
+    // server enforces it.
+    let deadline = Instant::now() + Duration::from_millis(500);
+    let timeout = sqlx_tracing::with_deadline(deadline, async {
+        let mut tx = pool.begin().await.unwrap();
+        let (timeout,): (String,) = sqlx::query_as("SHOW statement_timeout")
+            .fetch_one(&mut tx.executor())
+            .await
+            .unwrap();
+        tx.rollback().await.unwrap();
+        timeout
+    })
+    .await;
+    assert_ne!(timeout, "5s");
+    assert_ne!(timeout, "0");
+
+    // The timeout is local to the transaction.
+    let mut tx = pool.begin().await.unwrap();
+    let (timeout,): (String,) = sqlx::query_as("SHOW statement_timeout")
+        .fetch_one(&mut tx.executor())
+        .await
+        .unwrap();
+    assert_eq!(timeout, "5s");
+    let err = sqlx::query("SELECT pg_sleep(10)")
+        .execute(&mut tx.executor())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.as_database_error()
+            .and_then(|err| err.code())
+            .as_deref(),
+        Some("57014")
+    );
+    tx.rollback().await.unwrap();
+    let (timeout,): (String,) = sqlx::query_as("SHOW statement_timeout")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(timeout, "0");
+}
+
+#[tokio::test]
+async fn candidate_hosts() {
+    // A comma-separated list of hosts lists failover candidates, the first
+    // one being recorded as the host.
+    let options = sqlx::postgres::PgConnectOptions::new()
+        .host("primary.db,replica.db")
+        .username("postgres");
+    let pool = sqlx_tracing::Pool::from_options(options, sqlx::postgres::PgPoolOptions::new());
+    assert_eq!(pool.attributes().host(), Some("primary.db"));
+    assert_eq!(
+        pool.attributes().hosts().collect::<Vec<_>>(),
+        ["primary.db", "replica.db"]
+    );
+}
+
+#[tokio::test]
+async fn server_address_recording() {
+    let container = PostgresContainer::create().await;
+    let port = container.container.get_host_port_ipv4(5432).await.unwrap();
+    let url = format!("postgres://postgres@localhost:{port}/postgres");
+    let pool = sqlx_tracing::PoolOptions::<Postgres>::new()
+        .with_server_address_recording(true)
+        .after_connect(|conn, _meta| {
+            Box::pin(async move {
+                sqlx::query("SET application_name = 'failover'")
+                    .execute(conn)
+                    .await
+                    .map(|_| ())
+            })
+        })
+        .connect(&url)
+        .await
+        .unwrap()
+        .with_hosts(["localhost", "replica.local"])
+        .build();
+    assert_eq!(
+        pool.attributes().hosts().collect::<Vec<_>>(),
+        ["localhost", "replica.local"]
+    );
+
+    // The user callback still runs after the address was queried.
+    let (name,): (String,) = sqlx::query_as("SHOW application_name")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(name, "failover");
+}
+
+#[tokio::test]
+async fn statement_cache_capacity() {
+    let container = PostgresContainer::create().await;
+    let port = container.container.get_host_port_ipv4(5432).await.unwrap();
+    let url = format!("postgres://postgres@localhost:{port}/postgres");
+
+    // SQLx caches 100 statements by default.
+    let pool = sqlx_tracing::PoolOptions::<Postgres>::new()
+        .connect(&url)
+        .await
+        .unwrap()
+        .build();
+    assert_eq!(pool.attributes().statement_cache_capacity(), Some(100));
+
+    let pool = sqlx_tracing::PoolOptions::<Postgres>::new()
+        .connect(&format!("{url}?statement-cache-capacity=2"))
+        .await
+        .unwrap()
+        .build();
+    assert_eq!(pool.attributes().statement_cache_capacity(), Some(2));
+    for value in 1..=3 {
+        let got: i32 = sqlx::query_scalar(&format!("SELECT {value}"))
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(got, value);
+    }
+}
+
+#[tokio::test]
+async fn two_phase_commit() {
+    let container = PostgresContainer::create().await;
+    let pool = container.client().await;
+    sqlx::query("CREATE TABLE test_twophase (id INT)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query("INSERT INTO test_twophase VALUES (1)")
+        .execute(&mut tx.executor())
+        .await
+        .unwrap();
+    tx.prepare_twophase("it's-1").await.unwrap();
+
+    // The prepared transaction is not visible until committed.
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM test_twophase")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+    pool.commit_prepared("it's-1").await.unwrap();
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM test_twophase")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query("INSERT INTO test_twophase VALUES (2)")
+        .execute(&mut tx.executor())
+        .await
+        .unwrap();
+    tx.prepare_twophase("gid-2").await.unwrap();
+    pool.rollback_prepared("gid-2").await.unwrap();
+    assert!(pool.rollback_prepared("gid-2").await.is_err());
+
+    // Savepoints cannot be prepared.
+    let mut tx = pool.begin().await.unwrap();
+    let savepoint = tx.begin().await.unwrap();
+    assert!(matches!(
+        savepoint.prepare_twophase("gid-3").await,
+        Err(sqlx::Error::InvalidArgument(_))
+    ));
+}
+
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn test_database() {
+    use sqlx_tracing::testing::TestDatabase;
+
+    let container = PostgresContainer::create().await;
+    let port = container.container.get_host_port_ipv4(5432).await.unwrap();
+    let url = format!("postgres://postgres@localhost:{port}/postgres");
+
+    // Each fixture gets its own schema, so their tables don't collide.
+    let first = TestDatabase::postgres(&url).await.unwrap();
+    let second = TestDatabase::postgres(&url).await.unwrap();
+    for db in [&first, &second] {
+        sqlx::query("CREATE TABLE users (name TEXT)")
+            .execute(db.pool())
+            .await
+            .unwrap();
+    }
+    let name = first.name().to_owned();
+    first.teardown().await.unwrap();
+    second.teardown().await.unwrap();
+
+    let pool = container.client().await;
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM information_schema.schemata WHERE schema_name = $1)",
+    )
+    .bind(name)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!(!exists);
+}
@@ -1,9 +1,31 @@
 #![cfg(feature = "sqlite")]
 
 use sqlx::Sqlite;
+use sqlx_tracing::sqlite::SqliteValue;
 
+mod capture;
 mod common;
 
+use capture::Capture;
+
+/// Returns an in-memory pool limited to a single connection, so that all
+/// statements see the same database.
+async fn memory_pool() -> sqlx::SqlitePool {
+    sqlx::pool::PoolOptions::<Sqlite>::new()
+        .max_connections(1)
+        .connect(":memory:")
+        .await
+        .unwrap()
+}
+
+/// Returns an in-memory pool traced with the default attributes.
+async fn traced_pool() -> sqlx_tracing::Pool<Sqlite> {
+    sqlx::SqlitePool::connect(":memory:")
+        .await
+        .map(sqlx_tracing::Pool::from)
+        .unwrap()
+}
+
 #[tokio::test]
 async fn execute() {
     let observability = opentelemetry_testing::ObservabilityContainer::create().await;
@@ -276,3 +298,2749 @@ async fn transaction_drop_rolls_back() {
         .unwrap();
     assert_eq!(count.0, 0);
 }
+
+#[tokio::test]
+async fn pool_options_run_user_hooks() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let connected = Arc::new(AtomicUsize::new(0));
+    let acquired = Arc::new(AtomicUsize::new(0));
+
+    let pool = {
+        let connected = connected.clone();
+        let acquired = acquired.clone();
+        sqlx_tracing::PoolOptions::<Sqlite>::from(sqlx::pool::PoolOptions::new().max_connections(1))
+            .after_connect(move |_conn, _meta| {
+                connected.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Ok(()) })
+            })
+            .before_acquire(move |_conn, _meta| {
+                acquired.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Ok(true) })
+            })
+            .connect(":memory:")
+            .await
+            .unwrap()
+            .build()
+    };
+
+    // The first connection is opened by `connect`, then reused by every acquire.
+    for _ in 0..2 {
+        let mut conn = pool.acquire().await.unwrap();
+        conn.ping().await.unwrap();
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    assert_eq!(connected.load(Ordering::SeqCst), 1);
+    assert!(acquired.load(Ordering::SeqCst) >= 1);
+}
+
+#[tokio::test]
+async fn connection_close() {
+    let pool = memory_pool().await;
+    let pool = sqlx_tracing::Pool::from(pool);
+
+    let conn = pool.acquire().await.unwrap();
+    conn.close().await.unwrap();
+
+    // The pool should open a new connection in place of the closed one.
+    let mut conn = pool.acquire().await.unwrap();
+    conn.ping().await.unwrap();
+}
+
+#[tokio::test]
+async fn pool_options_discard_rejected_connections() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let connected = Arc::new(AtomicUsize::new(0));
+
+    let pool = {
+        let connected = connected.clone();
+        sqlx_tracing::PoolOptions::<Sqlite>::from(sqlx::pool::PoolOptions::new().max_connections(1))
+            .after_connect(move |_conn, _meta| {
+                connected.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Ok(()) })
+            })
+            .after_release(|_conn, _meta| Box::pin(async { Ok(false) }))
+            .connect(":memory:")
+            .await
+            .unwrap()
+            .build()
+    };
+
+    // Every released connection is rejected, so each acquire opens a new one.
+    for _ in 0..2 {
+        let mut conn = pool.acquire().await.unwrap();
+        conn.ping().await.unwrap();
+        drop(conn);
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    assert!(connected.load(Ordering::SeqCst) >= 2);
+}
+
+#[tokio::test]
+async fn transaction_idle_threshold() {
+    let pool = memory_pool().await;
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_transaction_idle_threshold(std::time::Duration::from_millis(10))
+        .build();
+
+    sqlx::query("CREATE TABLE test_idle (id INTEGER PRIMARY KEY, value TEXT NOT NULL)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Stay idle between statements longer than the threshold.
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query("INSERT INTO test_idle (value) VALUES ('first')")
+        .execute(&mut tx.executor())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    sqlx::query("INSERT INTO test_idle (value) VALUES ('second')")
+        .execute(&mut tx.executor())
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let count: (i32,) = sqlx::query_as("SELECT COUNT(*) FROM test_idle")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count.0, 2);
+}
+
+#[tokio::test]
+async fn try_begin_returns_transaction() {
+    let pool = sqlx::pool::PoolOptions::<Sqlite>::new()
+        .max_connections(1)
+        .min_connections(1)
+        .connect(":memory:")
+        .await
+        .unwrap();
+    let pool = sqlx_tracing::Pool::from(pool);
+
+    // Give the pool a moment to establish its min_connections.
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    // try_begin should succeed when a connection is idle.
+    let tx = pool.try_begin().await.unwrap();
+    assert!(tx.is_some());
+
+    // With the only connection held by the transaction, try_begin should return None.
+    let second = pool.try_begin().await.unwrap();
+    assert!(second.is_none());
+}
+
+#[tokio::test]
+async fn try_begin_span() {
+    let pool = sqlx::pool::PoolOptions::<Sqlite>::new()
+        .max_connections(1)
+        .connect(":memory:")
+        .await
+        .unwrap();
+    let pool = sqlx_tracing::Pool::from(pool);
+
+    let capture = Capture::install();
+    let tx = pool.try_begin().await.unwrap();
+    assert!(tx.is_some());
+    assert!(pool.try_begin().await.unwrap().is_none());
+
+    // Both attempts are traced like `begin`, with their outcome.
+    let spans = capture.spans("sqlx.transaction.begin");
+    let outcomes = spans
+        .iter()
+        .map(|span| span.field("db.client.connection.acquire_outcome"))
+        .collect::<Vec<_>>();
+    assert_eq!(outcomes, [Some("acquired"), Some("unavailable")]);
+    assert_eq!(spans[1].field("pool.pending_acquires"), Some("0"));
+
+    let outcomes = pool.acquire_outcomes();
+    assert_eq!(outcomes.immediate, 1);
+    assert_eq!(outcomes.unavailable, 1);
+}
+
+#[tokio::test]
+async fn transaction_span_parents_application_spans() {
+    use tracing::Instrument;
+
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::Pool::from(pool);
+
+    let mut tx = pool.begin().await.unwrap();
+    let child = tracing::info_span!(parent: tx.span(), "checkout");
+    let result: (i32,) = sqlx::query_as("SELECT 1")
+        .fetch_one(&mut tx.executor())
+        .instrument(child)
+        .await
+        .unwrap();
+    assert_eq!(result.0, 1);
+    tx.commit().await.unwrap();
+}
+
+#[cfg(feature = "opentelemetry")]
+#[tokio::test]
+async fn trace_ids_without_subscriber() {
+    // Without a tracing-opentelemetry layer there is no trace to correlate with.
+    assert!(sqlx_tracing::current_trace_id().is_none());
+    assert!(sqlx_tracing::current_span_id().is_none());
+}
+
+#[tokio::test]
+async fn builder_disables_sqlx_logging() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_sqlx_logging(false)
+        .build();
+
+    let result: (i32,) = sqlx::query_as("SELECT 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(result.0, 1);
+
+    let options = pool.inner().connect_options();
+    assert_eq!(
+        format!("{options:?}"),
+        format!(
+            "{:?}",
+            sqlx::ConnectOptions::disable_statement_logging((*options).clone())
+        )
+    );
+}
+
+#[tokio::test]
+async fn sqlx_logging_reopens_idle_connections() {
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(2)
+        .min_connections(1)
+        .connect(":memory:")
+        .await
+        .unwrap();
+    // Give the pool a moment to establish its min_connections.
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert!(pool.num_idle() > 0);
+
+    // Connections opened with the previous settings are not reused.
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_sqlx_logging(false)
+        .build();
+    assert_eq!(pool.num_idle(), 0);
+
+    let result: (i32,) = sqlx::query_as("SELECT 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(result.0, 1);
+}
+
+#[tokio::test]
+async fn pool_from_options() {
+    let options: sqlx::sqlite::SqliteConnectOptions = ":memory:".parse().unwrap();
+    let pool = sqlx_tracing::Pool::from_options(options, sqlx::sqlite::SqlitePoolOptions::new());
+
+    let result: (i32,) = sqlx::query_as("SELECT 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(result.0, 1);
+}
+
+#[tokio::test]
+async fn nested_transaction_rollback() {
+    let pool = memory_pool().await;
+    let pool = sqlx_tracing::Pool::from(pool);
+
+    sqlx::query("CREATE TABLE test_nested (id INTEGER PRIMARY KEY, value TEXT NOT NULL)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query("INSERT INTO test_nested (value) VALUES ('outer')")
+        .execute(&mut tx.executor())
+        .await
+        .unwrap();
+
+    // Roll back the savepoint only; the outer insert should survive.
+    let mut nested = tx.begin().await.unwrap();
+    sqlx::query("INSERT INTO test_nested (value) VALUES ('inner')")
+        .execute(&mut nested.executor())
+        .await
+        .unwrap();
+    nested.rollback().await.unwrap();
+    tx.commit().await.unwrap();
+
+    let values: Vec<(String,)> = sqlx::query_as("SELECT value FROM test_nested")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(values, vec![("outer".to_string(),)]);
+}
+
+#[tokio::test]
+async fn pool_close_with_timeout() {
+    let pool = memory_pool().await;
+    let pool = sqlx_tracing::Pool::from(pool);
+
+    // A checked out connection keeps the pool from closing in time.
+    let conn = pool.acquire().await.unwrap();
+    let closed = pool
+        .close_with_timeout(std::time::Duration::from_millis(50))
+        .await;
+    assert!(!closed);
+    assert!(pool.is_closed());
+
+    // Once returned, closing completes within the deadline.
+    drop(conn);
+    let closed = pool
+        .close_with_timeout(std::time::Duration::from_secs(1))
+        .await;
+    assert!(closed);
+}
+
+#[tokio::test]
+async fn insert_many_chunks_rows() {
+    let pool = sqlx_tracing::Pool::from(memory_pool().await);
+
+    sqlx::query("CREATE TABLE test_bulk (id INTEGER NOT NULL, value TEXT NOT NULL)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Enough rows to need more than one statement with two columns per row.
+    let rows = (0..20_000).map(|i| (i, format!("value-{i}")));
+    let result = pool
+        .insert_many(
+            "test_bulk",
+            &["id", "value"],
+            rows,
+            |mut row, (id, value)| {
+                row.push_bind(id).push_bind(value);
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.rows_affected(), 20_000);
+
+    let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM test_bulk")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count.0, 20_000);
+}
+
+#[tokio::test]
+async fn connection_string_recording() {
+    let pool = memory_pool().await;
+    let expected = format!(
+        "sqlite://{}",
+        pool.connect_options().get_filename().display()
+    );
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_connection_string_recording(true)
+        .build();
+
+    let capture = Capture::install();
+    let mut conn = pool.acquire().await.unwrap();
+    conn.ping().await.unwrap();
+
+    // Lifecycle spans record `db.connection_string`, but statements do not.
+    for name in ["sqlx.pool.acquire", "sqlx.connection.ping"] {
+        let span = capture.span(name);
+        assert_eq!(span.field("db.connection_string"), Some(expected.as_str()));
+    }
+    sqlx::query("SELECT 1").execute(&mut conn).await.unwrap();
+    assert_eq!(
+        capture.span("sqlx.execute").field("db.connection_string"),
+        None
+    );
+}
+
+#[tokio::test]
+async fn fetch_stream_consumed_and_dropped() {
+    use futures::TryStreamExt;
+
+    let pool = traced_pool().await;
+    let capture = Capture::install();
+
+    // A fully consumed stream records its time to first row and total duration.
+    let rows: Vec<(i32,)> = sqlx::query_as::<_, (i32,)>("SELECT 1 UNION ALL SELECT 2")
+        .fetch(&pool)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![(1,), (2,)]);
+
+    // A stream dropped early still records its duration.
+    let mut stream = sqlx::query_as::<_, (i32,)>("SELECT 1 UNION ALL SELECT 2").fetch(&pool);
+    assert_eq!(stream.try_next().await.unwrap(), Some((1,)));
+    drop(stream);
+
+    let spans = capture.spans("sqlx.fetch_many");
+    assert_eq!(spans.len(), 2);
+    for span in &spans {
+        let first_row = span.number("db.response.time_to_first_row_ms");
+        assert!(first_row <= span.number("db.response.stream_duration_ms"));
+    }
+}
+
+#[tokio::test]
+async fn fetch_stream_slow_consumer() {
+    use futures::TryStreamExt;
+
+    let pool = traced_pool().await;
+    let capture = Capture::install();
+
+    // Time spent between polls is recorded as consumer wait, not database wait.
+    let mut stream = sqlx::query_as::<_, (i32,)>("SELECT 1 UNION ALL SELECT 2").fetch(&pool);
+    let mut values = Vec::new();
+    while let Some((value,)) = stream.try_next().await.unwrap() {
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        values.push(value);
+    }
+    assert_eq!(values, vec![1, 2]);
+
+    let span = capture.span("sqlx.fetch_many");
+    assert!(span.number("db.response.consumer_wait_ms") >= 40);
+    assert!(span.number("db.response.database_wait_ms") < 40);
+    assert!(span.field("db.response.rows_per_sec").is_some());
+}
+
+#[tokio::test]
+async fn fetch_stream_progress_events() {
+    use futures::TryStreamExt;
+
+    let pool = sqlx_tracing::PoolBuilder::from(memory_pool().await)
+        .with_stream_progress_interval(2)
+        .build();
+    let capture = Capture::install();
+
+    let rows: Vec<(i32,)> = sqlx::query_as::<_, (i32,)>(
+        "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n WHERE x < 5) SELECT x FROM n",
+    )
+    .fetch(&pool)
+    .try_collect()
+    .await
+    .unwrap();
+    assert_eq!(rows.len(), 5);
+
+    // A progress event is emitted within the span after the second and
+    // fourth rows.
+    let events = capture.events("sqlx.stream.progress");
+    let counts: Vec<_> = events
+        .iter()
+        .map(|event| event.field("db.response.returned_rows"))
+        .collect();
+    assert_eq!(counts, [Some("2"), Some("4")]);
+    assert!(
+        events
+            .iter()
+            .all(|event| event.parent.as_deref() == Some("sqlx.fetch_many"))
+    );
+}
+
+#[tokio::test]
+async fn query_text_operations() {
+    let pool = sqlx_tracing::PoolBuilder::from(memory_pool().await)
+        .with_query_text_operations(["select", "create"])
+        .build();
+    let capture = Capture::install();
+
+    let create = "CREATE TABLE test_ops (id INTEGER PRIMARY KEY, value TEXT NOT NULL)";
+    sqlx::query(create).execute(&pool).await.unwrap();
+    // The insert carries data values, so its query text is not recorded.
+    sqlx::query("/* seed */ INSERT INTO test_ops (value) VALUES ('secret')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let select = "SELECT COUNT(*) FROM test_ops";
+    let count: (i32,) = sqlx::query_as(select).fetch_one(&pool).await.unwrap();
+    assert_eq!(count.0, 1);
+
+    let texts: Vec<_> = capture
+        .spans("sqlx.execute")
+        .iter()
+        .map(|span| span.field("db.query.text").map(str::to_owned))
+        .collect();
+    assert_eq!(texts, [Some(create.to_owned()), None]);
+    let span = capture.span("sqlx.fetch_optional");
+    assert_eq!(span.field("db.query.text"), Some(select));
+}
+
+#[tokio::test]
+async fn column_decode_error() {
+    let pool = traced_pool().await;
+    let capture = Capture::install();
+
+    // Decoding text as an integer fails; the span records the column and SQL type.
+    let err = pool
+        .fetch_one_as::<(i32,)>("SELECT 'text' AS value")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, sqlx::Error::ColumnDecode { .. }));
+
+    let span = capture.span("sqlx.fetch_one");
+    assert_eq!(span.field("db.error.column"), Some("0"));
+    assert_eq!(span.field("db.error.source_type"), Some("TEXT"));
+}
+
+#[tokio::test]
+async fn retry_busy_waits_for_lock() {
+    let path = std::env::temp_dir().join(format!("sqlx-tracing-busy-{}.db", std::process::id()));
+    let options = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(&path)
+        .create_if_missing(true)
+        .busy_timeout(std::time::Duration::ZERO);
+    let pool = sqlx_tracing::Pool::from_options(
+        options,
+        sqlx::sqlite::SqlitePoolOptions::new().max_connections(2),
+    );
+
+    sqlx::query("CREATE TABLE IF NOT EXISTS test_busy (id INTEGER PRIMARY KEY)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Hold the write lock on one connection and release it shortly after.
+    let mut locker = pool.acquire().await.unwrap();
+    sqlx::query("BEGIN IMMEDIATE")
+        .execute(&mut locker)
+        .await
+        .unwrap();
+    let release = tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        sqlx::query("COMMIT").execute(&mut locker).await.unwrap();
+    });
+
+    let retry = sqlx_tracing::sqlite::BusyRetry::default().with_max_retries(20);
+    let result = pool
+        .retry_busy(&retry, || {
+            sqlx::query("INSERT INTO test_busy DEFAULT VALUES").execute(&pool)
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.rows_affected(), 1);
+
+    release.await.unwrap();
+    pool.close().await;
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn attached_schema_recording() {
+    let pool = sqlx_tracing::PoolBuilder::from(memory_pool().await)
+        .with_schema_recording(true)
+        .build();
+    let capture = Capture::install();
+
+    sqlx::query("ATTACH DATABASE ':memory:' AS aux")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("CREATE TABLE aux.test_attach (id INTEGER PRIMARY KEY)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO aux.test_attach DEFAULT VALUES")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let count: (i32,) = sqlx::query_as("SELECT COUNT(*) FROM aux.test_attach")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count.0, 1);
+
+    // Statements on a qualified table record its schema as `db.namespace`.
+    let namespaces: Vec<_> = capture
+        .spans("sqlx.execute")
+        .iter()
+        .map(|span| span.field("db.namespace").map(str::to_owned))
+        .collect();
+    assert_eq!(namespaces, [None, Some("aux".into()), Some("aux".into())]);
+    let span = capture.span("sqlx.fetch_optional");
+    assert_eq!(span.field("db.namespace"), Some("aux"));
+}
+
+#[tokio::test]
+async fn result_metadata_recording() {
+    let pool = sqlx_tracing::PoolBuilder::from(memory_pool().await)
+        .with_result_metadata_recording(true)
+        .build();
+
+    sqlx::query("CREATE TABLE test_rowid (id INTEGER PRIMARY KEY, value TEXT NOT NULL)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let capture = Capture::install();
+    let result = sqlx::query("INSERT INTO test_rowid (value) VALUES ('hello')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(result.last_insert_rowid(), 1);
+    let span = capture.span("sqlx.execute");
+    assert_eq!(span.field("db.sqlite.last_insert_rowid"), Some("1"));
+}
+
+#[tokio::test]
+async fn pending_acquires() {
+    let pool = sqlx_tracing::Pool::from(memory_pool().await);
+    let capture = Capture::install();
+
+    // While the only connection is held, later acquires record the number of
+    // acquisitions already waiting as `pool.pending_acquires`.
+    let conn = pool.acquire().await.unwrap();
+    let waiters: Vec<_> = (0..2)
+        .map(|_| {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                let mut conn = pool.acquire().await.unwrap();
+                conn.ping().await.unwrap();
+            })
+        })
+        .collect();
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    drop(conn);
+    for waiter in waiters {
+        waiter.await.unwrap();
+    }
+
+    let pending: Vec<_> = capture
+        .spans("sqlx.pool.acquire")
+        .iter()
+        .map(|span| span.number("pool.pending_acquires"))
+        .collect();
+    assert_eq!(pending, [0, 0, 1]);
+}
+
+#[tokio::test]
+async fn with_context_tags_queries() {
+    let pool = traced_pool().await;
+    let capture = Capture::install();
+
+    // Every span created within the future records `sqlx.context`, and nested
+    // contexts extend the enclosing one.
+    let count = sqlx_tracing::with_context([("tenant.id", "42")], async {
+        let mut conn = pool.acquire().await.unwrap();
+        sqlx_tracing::with_context([("request.id", "abc")], async {
+            sqlx::query_as::<_, (i32,)>("SELECT 1")
+                .fetch_one(&mut conn)
+                .await
+                .unwrap()
+        })
+        .await
+    })
+    .await;
+    assert_eq!(count.0, 1);
+
+    let span = capture.span("sqlx.pool.acquire");
+    assert_eq!(span.field("sqlx.context"), Some("tenant.id=42"));
+    let span = capture.span("sqlx.fetch_optional");
+    assert_eq!(
+        span.field("sqlx.context"),
+        Some("db.transaction.active=false,tenant.id=42,request.id=abc")
+    );
+}
+
+#[tokio::test]
+async fn system_name_override() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+
+    // Spans record the overridden `db.system.name`, which must not be empty.
+    let res = sqlx_tracing::PoolBuilder::from(pool.clone())
+        .with_system_name("")
+        .try_build();
+    assert_eq!(res.unwrap_err(), sqlx_tracing::BuildError::EmptySystemName);
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_system_name("libsql")
+        .try_build()
+        .unwrap();
+    let row: (i32,) = sqlx::query_as("SELECT 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(row.0, 1);
+}
+
+#[tokio::test]
+async fn upstream_address() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let server = pool.connect_options().get_filename().display().to_string();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_upstream_address("primary.internal:5432")
+        .build();
+    let capture = Capture::install();
+
+    // Spans record the proxy and the upstream address next to the
+    // extensions.
+    let row: (i32,) = sqlx::query_as("SELECT 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(row.0, 1);
+    let span = capture.span("sqlx.fetch_optional");
+    let expected = format!(
+        "server.address={server},db.upstream.address=primary.internal:5432,db.transaction.active=false"
+    );
+    assert_eq!(span.field("sqlx.context"), Some(expected.as_str()));
+}
+
+#[tokio::test]
+async fn comment_tags() {
+    let pool = traced_pool().await;
+    let capture = Capture::install();
+
+    // The tags of the leading comment are recorded, and the statement still
+    // runs with its comment.
+    let statement = "/* application:billing,controller:invoices */ SELECT 1";
+    let row: (i32,) = sqlx::query_as(statement).fetch_one(&pool).await.unwrap();
+    assert_eq!(row.0, 1);
+    let span = capture.span("sqlx.fetch_optional");
+    assert_eq!(
+        span.field("sqlx.context"),
+        Some("db.transaction.active=false,application=billing,controller=invoices")
+    );
+    assert_eq!(span.field("db.query.text"), Some(statement));
+}
+
+#[tokio::test]
+async fn error_detail_rate_limit() {
+    use std::time::Duration;
+
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let res = sqlx_tracing::PoolBuilder::from(pool.clone())
+        .with_error_detail_rate_limit(1, Duration::ZERO)
+        .try_build();
+    assert_eq!(
+        res.unwrap_err(),
+        sqlx_tracing::BuildError::ZeroRateLimitPeriod
+    );
+
+    // Only the first of the identical errors records its details, but every
+    // query still fails with the full error.
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_error_detail_rate_limit(1, Duration::from_secs(60))
+        .build();
+    for _ in 0..3 {
+        let err = sqlx::query("SELECT * FROM missing_table")
+            .execute(&pool)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("missing_table"));
+    }
+}
+
+#[test]
+fn operation_span_names() {
+    use sqlx_tracing::Operation;
+
+    // Every operation maps to its own span name and back.
+    for operation in Operation::ALL {
+        assert_eq!(
+            Operation::from_span_name(operation.span_name()),
+            Some(operation)
+        );
+    }
+    assert_eq!(
+        Operation::FetchOne.span_name(),
+        sqlx_tracing::names::FETCH_ONE
+    );
+    assert_eq!(
+        Operation::from_span_name(sqlx_tracing::names::STREAM_PROGRESS),
+        None
+    );
+}
+
+#[test]
+fn operation_names() {
+    use sqlx_tracing::Operation;
+
+    // Lifecycle spans record their operation without the `sqlx.` prefix.
+    assert_eq!(Operation::Acquire.operation_name(), "pool.acquire");
+    assert_eq!(Operation::Begin.operation_name(), "transaction.begin");
+    assert_eq!(Operation::Commit.operation_name(), "transaction.commit");
+    assert_eq!(Operation::Ping.operation_name(), "connection.ping");
+    let mut names: Vec<_> = Operation::ALL
+        .into_iter()
+        .map(Operation::operation_name)
+        .collect();
+    assert!(names.iter().all(|name| !name.starts_with("sqlx.")));
+    names.sort_unstable();
+    names.dedup();
+    assert_eq!(names.len(), Operation::ALL.len());
+}
+
+#[tokio::test]
+async fn try_build_validates() {
+    use sqlx_tracing::BuildError;
+
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+
+    // Conflicting and invalid options are reported, valid ones build.
+    let res = sqlx_tracing::PoolBuilder::from(pool.clone())
+        .with_query_text_recording(false)
+        .with_query_text_operations(["SELECT"])
+        .try_build();
+    assert_eq!(res.unwrap_err(), BuildError::OperationsWithoutQueryText);
+    let res = sqlx_tracing::PoolBuilder::from(pool.clone())
+        .with_query_text_operations(["SELECT *"])
+        .try_build();
+    assert_eq!(
+        res.unwrap_err(),
+        BuildError::InvalidOperation("SELECT *".into())
+    );
+    let res = sqlx_tracing::PoolBuilder::from(pool.clone())
+        .with_name("")
+        .try_build();
+    assert_eq!(res.unwrap_err(), BuildError::EmptyName);
+    let res = sqlx_tracing::PoolBuilder::from(pool.clone())
+        .with_statement_timeout(sqlx_tracing::StatementTimeout::Budget(
+            std::time::Duration::ZERO,
+        ))
+        .try_build();
+    assert_eq!(res.unwrap_err(), BuildError::ZeroStatementTimeout);
+    sqlx_tracing::PoolBuilder::from(pool)
+        .with_name("api")
+        .with_query_text_operations(["select"])
+        .try_build()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn acquire_outcomes() {
+    let pool = sqlx::pool::PoolOptions::<Sqlite>::new()
+        .max_connections(1)
+        .acquire_timeout(std::time::Duration::from_millis(50))
+        .connect(":memory:")
+        .await
+        .unwrap();
+    let pool = sqlx_tracing::Pool::from(pool);
+
+    // The idle connection is handed out immediately, and the next acquisition
+    // times out while it is held.
+    let conn = pool.acquire().await.unwrap();
+    assert!(pool.acquire().await.is_err());
+    drop(conn);
+    pool.close().await;
+    assert!(pool.acquire().await.is_err());
+
+    let outcomes = pool.acquire_outcomes();
+    assert_eq!(outcomes.immediate, 1);
+    assert_eq!(outcomes.timed_out, 1);
+    assert_eq!(outcomes.pool_closed, 1);
+}
+
+#[tokio::test]
+async fn acquire_error_reasons() {
+    use sqlx_tracing::AcquireErrorReason;
+
+    let pool = sqlx::pool::PoolOptions::<Sqlite>::new()
+        .max_connections(1)
+        .acquire_timeout(std::time::Duration::from_millis(50))
+        .connect(":memory:")
+        .await
+        .unwrap();
+    let pool = sqlx_tracing::Pool::from(pool);
+
+    let conn = pool.acquire().await.unwrap();
+    let err = pool.acquire().await.unwrap_err();
+    assert_eq!(AcquireErrorReason::of(&err), AcquireErrorReason::TimedOut);
+    let deadline = std::time::Instant::now() + std::time::Duration::from_millis(10);
+    let err = sqlx_tracing::with_deadline(deadline, pool.acquire())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        AcquireErrorReason::of(&err),
+        AcquireErrorReason::DeadlineExceeded
+    );
+    drop(conn);
+    pool.close().await;
+    let err = pool.acquire().await.unwrap_err();
+    assert_eq!(AcquireErrorReason::of(&err), AcquireErrorReason::PoolClosed);
+    assert_eq!(AcquireErrorReason::PoolClosed.to_string(), "pool_closed");
+
+    // Opening a database in a missing directory fails to connect.
+    let options = sqlx::sqlite::SqliteConnectOptions::new().filename("/nonexistent/dir/test.db");
+    let pool = sqlx_tracing::Pool::from(
+        sqlx::pool::PoolOptions::<Sqlite>::new().connect_lazy_with(options),
+    );
+    let err = pool.acquire().await.unwrap_err();
+    assert_eq!(AcquireErrorReason::of(&err), AcquireErrorReason::Connect);
+}
+
+#[tokio::test]
+async fn deadline_bounds_queries() {
+    use std::time::{Duration, Instant};
+
+    let pool = traced_pool().await;
+
+    // Queries within the budget succeed and record the time left.
+    let deadline = Instant::now() + Duration::from_secs(60);
+    let row = sqlx_tracing::with_deadline(deadline, async {
+        sqlx::query_as::<_, (i32,)>("SELECT 1")
+            .fetch_one(&pool)
+            .await
+    })
+    .await
+    .unwrap();
+    assert_eq!(row.0, 1);
+
+    // Once the budget is spent, queries and streams fail fast.
+    let res = sqlx_tracing::with_deadline(Instant::now(), async {
+        sqlx::query("SELECT 1").fetch_optional(&pool).await
+    })
+    .await;
+    assert!(res.is_err_and(|e| sqlx_tracing::DeadlineExceeded::is(&e)));
+    let res = sqlx_tracing::with_deadline(Instant::now(), async {
+        futures::TryStreamExt::try_collect::<Vec<_>>(sqlx::query("SELECT 1").fetch(&pool)).await
+    })
+    .await;
+    assert!(res.is_err_and(|e| sqlx_tracing::DeadlineExceeded::is(&e)));
+}
+
+#[cfg(feature = "opentelemetry")]
+#[tokio::test]
+async fn baggage_recording() {
+    use opentelemetry::KeyValue;
+    use opentelemetry::baggage::BaggageExt;
+
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_baggage_recording(true)
+        .with_baggage_keys(["tenant.id"])
+        .build();
+
+    // Spans created while the context is attached record `tenant.id`, but not
+    // `user.email` which is not part of the allowlist.
+    let _guard = opentelemetry::Context::current_with_baggage([
+        KeyValue::new("tenant.id", "42"),
+        KeyValue::new("user.email", "someone@example.com"),
+    ])
+    .attach();
+    let row: (i32,) = sqlx::query_as("SELECT 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(row.0, 1);
+}
+
+#[tokio::test]
+async fn script_runs_statements() {
+    let pool = sqlx_tracing::Pool::from(memory_pool().await);
+
+    let script = "
+        -- seed data; with a comment
+        CREATE TABLE test_script (id INTEGER PRIMARY KEY, value TEXT NOT NULL);
+        INSERT INTO test_script (value) VALUES ('a;b'), ('c');
+        /* block; comment */
+        INSERT INTO test_script (value) VALUES ('it''s');
+    ";
+    let result = pool.execute_script(script).await.unwrap();
+    assert_eq!(result.rows_affected(), 3);
+
+    let values: Vec<(String,)> = sqlx::query_as("SELECT value FROM test_script ORDER BY id")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    let values: Vec<_> = values.into_iter().map(|(value,)| value).collect();
+    assert_eq!(values, ["a;b", "c", "it's"]);
+
+    // Execution stops at the failing statement, which is recorded on the
+    // `sqlx.execute_script` span.
+    let err = pool
+        .execute_script("INSERT INTO test_script (value) VALUES ('d');\nINSERT INTO missing VALUES (1);\nINSERT INTO test_script (value) VALUES ('e');")
+        .await
+        .unwrap_err();
+    assert!(err.as_database_error().is_some());
+    let count: (i32,) = sqlx::query_as("SELECT COUNT(*) FROM test_script")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count.0, 4);
+}
+
+#[tokio::test]
+async fn transaction_script_statements() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_query_rewriter(|sql| match sql.contains("{table}") {
+            true => std::borrow::Cow::Owned(sql.replace("{table}", "test_tx_script")),
+            false => std::borrow::Cow::Borrowed(sql),
+        })
+        .build();
+
+    // Script statements go through the rewriter and are tracked by the
+    // transaction like any other statement.
+    let mut tx = pool.begin().await.unwrap();
+    tx.execute_script(
+        "CREATE TABLE {table} (value TEXT); INSERT INTO {table} VALUES ('a'); INSERT INTO {table} VALUES ('b')",
+    )
+    .await
+    .unwrap();
+    let report = tx.commit_with_report().await.unwrap();
+    assert_eq!(report.statements, 3);
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM test_tx_script")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+}
+
+#[tokio::test]
+async fn connection_lease_spans() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_connection_lease_spans(true)
+        .build();
+
+    // The `sqlx.connection.lease` span stays open while the connection is
+    // held and parents the queries executed on it.
+    let mut conn = pool.acquire().await.unwrap();
+    let row: (i32,) = sqlx::query_as("SELECT 1")
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(row.0, 1);
+    conn.ping().await.unwrap();
+    drop(conn);
+}
+
+#[tokio::test]
+async fn parameter_count() {
+    let pool = traced_pool().await;
+
+    // The span records `db.query.parameter_count` without the values.
+    let row: (i32,) = sqlx::query_as("SELECT COUNT(*) FROM (SELECT 1 AS id) WHERE id IN (?, ?, ?)")
+        .bind(1)
+        .bind(2)
+        .bind(3)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(row.0, 1);
+}
+
+#[tokio::test]
+async fn prepared_statement_id() {
+    use sqlx::{Executor, Statement};
+
+    let pool = traced_pool().await;
+
+    // The prepare span and the execution of the prepared statement record the
+    // same `db.statement.id`.
+    let statement = (&pool).prepare("SELECT ? + 1").await.unwrap();
+    let row: (i32,) = statement
+        .query_as()
+        .bind(41)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(row.0, 42);
+}
+
+#[tokio::test]
+async fn query_rewriter() {
+    use futures::TryStreamExt;
+    use std::borrow::Cow;
+
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_query_rewriter(|sql: &str| match sql {
+            "SELECT 1" => Cow::Owned("SELECT 2 /* rewritten */".to_string()),
+            _ => Cow::Borrowed(sql),
+        })
+        .build();
+
+    // The rewritten SQL is what gets executed, through every executor.
+    let row: (i32,) = sqlx::query_as("SELECT 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(row.0, 2);
+
+    let mut conn = pool.acquire().await.unwrap();
+    let rows: Vec<(i32,)> = sqlx::query_as::<_, (i32,)>("SELECT 1")
+        .fetch(&mut conn)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(rows, [(2,)]);
+    drop(conn);
+
+    let mut tx = pool.begin().await.unwrap();
+    let row: (i32,) = sqlx::query_as("SELECT 1")
+        .fetch_one(&mut tx.executor())
+        .await
+        .unwrap();
+    assert_eq!(row.0, 2);
+    tx.rollback().await.unwrap();
+
+    // Other statements are left unchanged.
+    let row: (i32,) = sqlx::query_as("SELECT 3").fetch_one(&pool).await.unwrap();
+    assert_eq!(row.0, 3);
+}
+
+#[tokio::test]
+async fn pool_name() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_pool_name("reporting")
+        .build();
+
+    // Every span records `db.client.pool.name`.
+    let mut conn = pool.acquire().await.unwrap();
+    conn.ping().await.unwrap();
+    let row: (i32,) = sqlx::query_as("SELECT 1")
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(row.0, 1);
+}
+
+#[cfg(feature = "tokio-metrics")]
+#[tokio::test]
+async fn task_monitor() {
+    let pool = traced_pool().await;
+
+    // Statement futures are instrumented by the pool's task monitor.
+    let row: (i32,) = sqlx::query_as("SELECT 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(row.0, 1);
+    let metrics = pool.task_monitor().cumulative();
+    assert_eq!(metrics.instrumented_count, 1);
+    assert!(metrics.total_poll_count >= 1);
+}
+
+#[tokio::test]
+async fn shared_attributes() {
+    let attributes = sqlx_tracing::Attributes::builder()
+        .with_name("orders")
+        .with_database("main")
+        .with_extension("deployment.region", "eu-west-1")
+        .with_hosts(["primary.local", "replica.local"])
+        .build();
+    // Attributes built on their own have no pool name until applied to a pool.
+    assert_eq!(attributes.pool_name(), "");
+
+    // The same attribute set is applied to several pools; extensions are
+    // recorded on every span as `sqlx.context`.
+    for _ in 0..2 {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        let pool = sqlx_tracing::PoolBuilder::from(pool)
+            .with_attributes(attributes.clone())
+            .build();
+        assert_eq!(pool.attributes().name(), Some("orders"));
+        assert_eq!(pool.attributes().database(), Some("main"));
+        assert_eq!(
+            pool.attributes().extension("deployment.region"),
+            Some("eu-west-1")
+        );
+        assert_eq!(
+            pool.attributes().hosts().collect::<Vec<_>>(),
+            ["primary.local", "replica.local"]
+        );
+
+        assert!(pool.attributes().pool_name().starts_with("pool-"));
+
+        let row: (i32,) = sqlx::query_as("SELECT 1").fetch_one(&pool).await.unwrap();
+        assert_eq!(row.0, 1);
+    }
+
+    // The recording options of a pool stay with it, not with its attributes.
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let redacted = sqlx_tracing::PoolBuilder::from(pool)
+        .with_query_text_recording(false)
+        .build();
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_attributes(redacted.attributes().clone())
+        .build();
+    assert_ne!(
+        pool.attributes().pool_name(),
+        redacted.attributes().pool_name()
+    );
+    let capture = Capture::install();
+    sqlx::query("SELECT 1").execute(&pool).await.unwrap();
+    assert_eq!(
+        capture.span("sqlx.execute").field("db.query.text"),
+        Some("SELECT 1")
+    );
+}
+
+#[tokio::test]
+async fn span_complete_callback() {
+    use futures::TryStreamExt;
+    use std::sync::{Arc, Mutex};
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = records.clone();
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_pool_name("export")
+        .on_span_complete(move |record| sink.lock().unwrap().push(record.clone()))
+        .build();
+
+    sqlx::query("CREATE TABLE items (id INTEGER)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO items VALUES (1), (2)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let rows: Vec<(i64,)> = sqlx::query_as::<_, (i64,)>("SELECT id FROM items")
+        .fetch(&pool)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert!(
+        sqlx::query("SELECT * FROM missing_table")
+            .fetch_all(&pool)
+            .await
+            .is_err()
+    );
+
+    let records = records.lock().unwrap();
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[1].operation, sqlx_tracing::Operation::Execute);
+    assert_eq!(records[1].rows, Some(2));
+    assert_eq!(records[2].operation, sqlx_tracing::Operation::FetchMany);
+    // The query result `fetch_many` yields after the rows is not counted.
+    assert_eq!(records[2].rows, Some(2));
+    assert!(!records[2].failed);
+    assert_eq!(records[3].operation, sqlx_tracing::Operation::FetchAll);
+    assert!(records[3].failed);
+    assert!(records[3].error_code.is_some());
+    assert_ne!(records[2].fingerprint, records[3].fingerprint);
+    assert!(records.iter().all(|record| record.pool_name == "export"));
+
+    // The records serialize for shipping to custom sinks.
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&records[3]).unwrap();
+        assert_eq!(json["operation"], "sqlx.fetch_all");
+        assert_eq!(json["failed"], true);
+    }
+}
+
+#[tokio::test]
+async fn traced_row_decoding() {
+    use sqlx_tracing::TracedRow;
+
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool).build();
+    let row = sqlx::query("SELECT 1 AS id, 'alice' AS name")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(row.try_get_traced::<i64, _>("id").unwrap(), 1);
+    assert_eq!(row.try_get_traced::<String, _>(1).unwrap(), "alice");
+    // Failures are returned unchanged after being reported.
+    assert!(matches!(
+        row.try_get_traced::<i64, _>("name"),
+        Err(sqlx::Error::ColumnDecode { .. })
+    ));
+    assert!(matches!(
+        row.try_get_traced::<i64, _>("missing"),
+        Err(sqlx::Error::ColumnNotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn ping_customization() {
+    use sqlx_tracing::PingTracing;
+
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let res = sqlx_tracing::PoolBuilder::from(pool.clone())
+        .with_ping_query(" ")
+        .try_build();
+    assert_eq!(res.unwrap_err(), sqlx_tracing::BuildError::EmptyPingQuery);
+
+    for tracing in [PingTracing::Span, PingTracing::Event, PingTracing::Off] {
+        let traced = sqlx_tracing::PoolBuilder::from(pool.clone())
+            .with_ping_query("SELECT 1")
+            .with_ping_tracing(tracing)
+            .build();
+        traced.acquire().await.unwrap().ping().await.unwrap();
+    }
+
+    // The configured query is what checks the connection.
+    let traced = sqlx_tracing::PoolBuilder::from(pool)
+        .with_ping_query("SELECT * FROM missing_table")
+        .with_ping_tracing(PingTracing::Event)
+        .build();
+    assert!(traced.acquire().await.unwrap().ping().await.is_err());
+}
+
+#[tokio::test]
+async fn concurrent_statements() {
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool).build();
+    sqlx::query("CREATE TABLE items (id INTEGER PRIMARY KEY)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Every statement runs, and results keep the order of the statements.
+    let results = pool
+        .execute_concurrent(
+            [1, 2, 1, 3].map(|id| sqlx::query("INSERT INTO items VALUES (?)").bind(id)),
+            2,
+        )
+        .await;
+    assert_eq!(results.len(), 4);
+    assert!(results[0].is_ok() && results[1].is_ok() && results[3].is_ok());
+    assert!(results[2].is_err());
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM items")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 3);
+}
+
+#[tokio::test]
+async fn transaction_statement_context() {
+    let pool = traced_pool().await;
+
+    // Statements in and out of transactions and savepoints are recorded as
+    // such, without getting in the way of the statements themselves.
+    let mut tx = pool.begin().await.unwrap();
+    let row: (i32,) = sqlx::query_as("SELECT 1")
+        .fetch_one(&mut tx.executor())
+        .await
+        .unwrap();
+    assert_eq!(row.0, 1);
+    let mut savepoint = tx.begin().await.unwrap();
+    let row: (i32,) = sqlx::query_as("SELECT 2")
+        .fetch_one(&mut savepoint.executor())
+        .await
+        .unwrap();
+    assert_eq!(row.0, 2);
+    savepoint.commit().await.unwrap();
+    tx.commit().await.unwrap();
+    let row: (i32,) = sqlx::query_as("SELECT 3").fetch_one(&pool).await.unwrap();
+    assert_eq!(row.0, 3);
+}
+
+#[cfg(feature = "metrics")]
+#[tokio::test]
+async fn acquire_wait_time_metric() {
+    use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+
+    let exporter = InMemoryMetricExporter::default();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    opentelemetry::global::set_meter_provider(provider.clone());
+
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool).build();
+    drop(pool.acquire().await.unwrap());
+
+    provider.force_flush().unwrap();
+    let metrics = exporter.get_finished_metrics().unwrap();
+    assert!(
+        metrics
+            .iter()
+            .flat_map(|metrics| metrics.scope_metrics())
+            .flat_map(|scope| scope.metrics())
+            .any(|metric| metric.name() == "db.client.connection.wait_time")
+    );
+}
+
+#[tokio::test]
+async fn acquire_slow_threshold() {
+    use std::time::Duration;
+
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    let res = sqlx_tracing::PoolBuilder::from(pool.clone())
+        .with_acquire_slow_threshold(Duration::ZERO)
+        .try_build();
+    assert_eq!(
+        res.unwrap_err(),
+        sqlx_tracing::BuildError::ZeroAcquireThreshold
+    );
+
+    // Waiting for the only connection crosses the threshold, which is only
+    // reported.
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_acquire_slow_threshold(Duration::from_millis(1))
+        .build();
+    let conn = pool.acquire().await.unwrap();
+    let waiter = tokio::spawn({
+        let pool = pool.clone();
+        async move { pool.acquire().await.map(drop) }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    drop(conn);
+    waiter.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn sqlite_maintenance() {
+    let pool = traced_pool().await;
+
+    pool.maintenance("PRAGMA optimize; VACUUM").await.unwrap();
+    assert!(pool.maintenance("VACUUM missing_schema").await.is_err());
+}
+
+#[tokio::test]
+async fn sqlite_maintenance_helpers() {
+    let pool = traced_pool().await;
+
+    pool.vacuum().await.unwrap();
+    pool.optimize().await.unwrap();
+    assert_eq!(
+        sqlx_tracing::Operation::Vacuum.span_name(),
+        sqlx_tracing::names::VACUUM
+    );
+}
+
+#[tokio::test]
+async fn writer_queue_position() {
+    let pool = sqlx_tracing::Pool::from(memory_pool().await);
+    sqlx::query("CREATE TABLE test_queue (id INTEGER PRIMARY KEY)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let capture = Capture::install();
+    let position = |span: capture::Captured| {
+        let context = span.field("sqlx.context").unwrap_or_default().to_owned();
+        context
+            .split(',')
+            .find_map(|pair| pair.strip_prefix("db.sqlite.queue_position="))
+            .map(str::to_owned)
+    };
+
+    // A write queued behind a transaction holding the only connection
+    // completes once the transaction does, and so does a read, which counts
+    // the writes ahead of it without joining the queue.
+    let tx = pool.begin().await.unwrap();
+    let write = tokio::spawn({
+        let pool = pool.clone();
+        async move {
+            sqlx::query("INSERT INTO test_queue DEFAULT VALUES")
+                .execute(&pool)
+                .await
+        }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    let read = tokio::spawn({
+        let pool = pool.clone();
+        async move {
+            sqlx::query_as::<_, (i32,)>("SELECT COUNT(*) FROM test_queue")
+                .fetch_one(&pool)
+                .await
+        }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    assert!(!write.is_finished());
+    assert!(!read.is_finished());
+    tx.commit().await.unwrap();
+    assert_eq!(write.await.unwrap().unwrap().rows_affected(), 1);
+    read.await.unwrap().unwrap();
+
+    assert_eq!(
+        position(capture.span("sqlx.transaction.begin")).as_deref(),
+        Some("0")
+    );
+    assert_eq!(position(capture.span("sqlx.execute")).as_deref(), Some("1"));
+    assert_eq!(
+        position(capture.span("sqlx.fetch_optional")).as_deref(),
+        Some("2")
+    );
+
+    // Streams and connections leave the queue once done.
+    let rows: Vec<_> = {
+        use futures::TryStreamExt;
+        sqlx::query("SELECT 2")
+            .fetch(&pool)
+            .try_collect()
+            .await
+            .unwrap()
+    };
+    assert_eq!(rows.len(), 1);
+    drop(pool.acquire().await.unwrap());
+    let row: (i32,) = sqlx::query_as("SELECT 3").fetch_one(&pool).await.unwrap();
+    assert_eq!(row.0, 3);
+    assert_eq!(
+        position(capture.span("sqlx.pool.acquire")).as_deref(),
+        Some("0")
+    );
+    assert_eq!(
+        position(capture.span("sqlx.fetch_optional")).as_deref(),
+        Some("0")
+    );
+}
+
+#[tokio::test]
+async fn connection_into_parts() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_name("raw")
+        .build();
+
+    // Drop to raw SQLx and back, keeping the attributes.
+    let conn = pool.acquire().await.unwrap();
+    let (mut raw, attributes) = conn.into_parts();
+    assert_eq!(attributes.name(), Some("raw"));
+    let row: (i32,) = sqlx::query_as("SELECT 1")
+        .fetch_one(&mut *raw)
+        .await
+        .unwrap();
+    assert_eq!(row.0, 1);
+    let mut conn = sqlx_tracing::PoolConnection::from_parts(raw, attributes);
+    let row: (i32,) = sqlx::query_as("SELECT 2")
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(row.0, 2);
+
+    let raw = conn.into_inner();
+    drop(raw);
+    assert_eq!(pool.size(), 1);
+}
+
+#[tokio::test]
+async fn transaction_from_sqlx() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::Pool::from(pool);
+    sqlx::query("CREATE TABLE adopted (id INTEGER PRIMARY KEY)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // A transaction started through raw SQLx keeps its earlier statements
+    // once adopted.
+    let mut raw = pool.inner().begin().await.unwrap();
+    sqlx::query("INSERT INTO adopted (id) VALUES (1)")
+        .execute(&mut *raw)
+        .await
+        .unwrap();
+    let mut tx = sqlx_tracing::Transaction::from_sqlx(raw, pool.attributes().clone());
+    sqlx::query("INSERT INTO adopted (id) VALUES (2)")
+        .execute(&mut tx.executor())
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM adopted")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count.0, 2);
+}
+
+#[tokio::test]
+async fn statement_timeout_unsupported() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_statement_timeout(sqlx_tracing::StatementTimeout::Deadline)
+        .build();
+
+    // SQLite has no statement timeout, so transactions begin as usual.
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(60);
+    sqlx_tracing::with_deadline(deadline, async {
+        let mut tx = pool.begin().await.unwrap();
+        let row: (i32,) = sqlx::query_as("SELECT 1")
+            .fetch_one(&mut tx.executor())
+            .await
+            .unwrap();
+        assert_eq!(row.0, 1);
+        tx.commit().await.unwrap();
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn transaction_label() {
+    let pool = traced_pool().await;
+
+    // Labeled transactions and their savepoints run as usual.
+    let mut tx = pool.begin().await.unwrap();
+    tx.set_label("checkout");
+    let row: (i32,) = sqlx::query_as("SELECT 1")
+        .fetch_one(&mut tx.executor())
+        .await
+        .unwrap();
+    assert_eq!(row.0, 1);
+    let mut savepoint = tx.begin().await.unwrap();
+    let row: (i32,) = sqlx::query_as("SELECT 2")
+        .fetch_one(&mut savepoint.executor())
+        .await
+        .unwrap();
+    assert_eq!(row.0, 2);
+    savepoint.rollback().await.unwrap();
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn redacted_error_details() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_error_detail_recording(false)
+        .with_error_message_hash(true)
+        .build();
+
+    // Redacting the details from spans leaves the error itself intact.
+    let err = sqlx::query("SELECT * FROM missing_table")
+        .execute(&pool)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("missing_table"));
+}
+
+#[tokio::test]
+async fn statement_grouping() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_statement_grouping(true)
+        .build();
+
+    let mut conn = pool.acquire().await.unwrap();
+    for i in 0..5_i64 {
+        let value: i64 = sqlx::query_scalar("SELECT ?")
+            .bind(i)
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(value, i);
+    }
+    let value: i64 = sqlx::query_scalar("SELECT 2")
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(value, 2);
+
+    let mut tx = conn.begin().await.unwrap();
+    for _ in 0..3 {
+        let value: i64 = sqlx::query_scalar("SELECT 1")
+            .fetch_one(&mut tx.executor())
+            .await
+            .unwrap();
+        assert_eq!(value, 1);
+    }
+    tx.commit().await.unwrap();
+
+    // Statements on the pool itself are not tied to a connection.
+    for _ in 0..3 {
+        let value: i64 = sqlx::query_scalar("SELECT 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(value, 1);
+    }
+}
+
+#[tokio::test]
+async fn payload_size_recording() {
+    use futures::TryStreamExt;
+
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_payload_size_recording(true)
+        .build();
+
+    let rows: Vec<(i64, String, Option<Vec<u8>>)> =
+        sqlx::query_as("SELECT 1, 'text', NULL UNION ALL SELECT 2, 'more', x'0102'")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(rows.len(), 2);
+
+    let row: (String,) = sqlx::query_as("SELECT 'abc'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(row.0, "abc");
+
+    let row: Option<(i64,)> = sqlx::query_as("SELECT 1 WHERE 0")
+        .fetch_optional(&pool)
+        .await
+        .unwrap();
+    assert!(row.is_none());
+
+    // Measuring the rows leaves them intact, whether streamed or not.
+    let rows: Vec<(i64, String)> = sqlx::query_as("SELECT 1, 'a' UNION ALL SELECT 2, 'b'")
+        .fetch(&pool)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![(1, "a".to_owned()), (2, "b".to_owned())]);
+
+    let mut conn = pool.acquire().await.unwrap();
+    let results: Vec<_> = sqlx::Executor::fetch_many(&mut conn, "SELECT 'abc'")
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(results.iter().filter(|item| item.is_right()).count(), 1);
+}
+
+#[tokio::test]
+async fn record_helpers() {
+    use tracing::Instrument;
+
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_error_detail_recording(false)
+        .build();
+
+    let span = tracing::info_span!(
+        "custom.fetch",
+        "db.response.returned_rows" = tracing::field::Empty,
+        "error.type" = tracing::field::Empty,
+    );
+    let rows = sqlx::query("SELECT 1 UNION ALL SELECT 2")
+        .fetch_all(pool.inner())
+        .instrument(span.clone())
+        .await
+        .unwrap();
+    span.in_scope(|| sqlx_tracing::record::rows(&rows));
+    assert_eq!(rows.len(), 2);
+
+    let Err(err) = sqlx::query("SELECT * FROM missing_table")
+        .fetch_optional(pool.inner())
+        .instrument(span.clone())
+        .await
+    else {
+        panic!("expected an error");
+    };
+    span.in_scope(|| {
+        sqlx_tracing::record::error(&err, pool.attributes());
+        sqlx_tracing::record::broken(&err, None);
+    });
+    // Recording leaves the error intact.
+    assert!(err.to_string().contains("missing_table"));
+}
+
+#[tokio::test]
+async fn pool_create_span() {
+    use sqlx_tracing::Operation;
+
+    assert_eq!(
+        Operation::from_span_name(sqlx_tracing::names::POOL_CREATE),
+        Some(Operation::CreatePool)
+    );
+
+    let options = sqlx::pool::PoolOptions::new()
+        .max_connections(2)
+        .idle_timeout(None);
+    let pool = sqlx_tracing::PoolOptions::<Sqlite>::from(options)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap()
+        .with_pool_name("created")
+        .build();
+    assert_eq!(pool.attributes().pool_name(), "created");
+    let row: (i32,) = sqlx::query_as("SELECT 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(row.0, 1);
+
+    // The span covers connecting, and records its failure.
+    let err = sqlx_tracing::PoolOptions::<Sqlite>::new()
+        .connect("sqlite:///missing/directory/test.db")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, sqlx::Error::Database(_)));
+}
+
+#[tokio::test]
+async fn prepared_statement_query_text() {
+    use futures::TryStreamExt;
+    use sqlx::{Executor, Statement};
+    use std::sync::{Arc, Mutex};
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = records.clone();
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    // A statement prepared through SQLx directly, outside of the traced
+    // executors.
+    let raw_statement = pool.prepare("SELECT ? + 1").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .on_span_complete(move |record| sink.lock().unwrap().push(record.clone()))
+        .build();
+
+    let statement = (&pool).prepare("SELECT ? + 1").await.unwrap();
+    let rows: Vec<(i32,)> = statement
+        .query_as::<(i32,)>()
+        .bind(1)
+        .fetch(&pool)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(rows, [(2,)]);
+    let mut conn = pool.acquire().await.unwrap();
+    let row: (i32,) = raw_statement
+        .query_as()
+        .bind(2)
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(row.0, 3);
+    let mut tx = conn.begin().await.unwrap();
+    let value: i32 = statement
+        .query_scalar()
+        .bind(3)
+        .fetch_one(&mut tx.executor())
+        .await
+        .unwrap();
+    assert_eq!(value, 4);
+    tx.rollback().await.unwrap();
+    let value: i32 = sqlx::query_scalar("SELECT ? + 1")
+        .bind(4)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(value, 5);
+
+    // The executions record the SQL of the statement, which fingerprints
+    // like the same query run without preparing it.
+    let records = records.lock().unwrap();
+    let fingerprints: Vec<_> = records
+        .iter()
+        .filter(|record| record.operation != sqlx_tracing::Operation::Prepare)
+        .map(|record| record.fingerprint.as_str())
+        .collect();
+    assert_eq!(fingerprints.len(), 4);
+    assert!(
+        fingerprints
+            .iter()
+            .all(|fingerprint| *fingerprint == fingerprints[3])
+    );
+}
+
+#[tokio::test]
+async fn weak_pool() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_pool_name("weak")
+        .build();
+    let weak = pool.downgrade();
+    assert_eq!(weak.attributes().pool_name(), "weak");
+
+    // Upgrading keeps the attributes and the acquisition counters.
+    let upgraded = weak.upgrade().unwrap();
+    assert_eq!(upgraded.attributes().pool_name(), "weak");
+    drop(upgraded.acquire().await.unwrap());
+    assert_eq!(
+        pool.acquire_outcomes().immediate + pool.acquire_outcomes().waited,
+        1
+    );
+    let row: (i32,) = sqlx::query_as("SELECT 1")
+        .fetch_one(&upgraded)
+        .await
+        .unwrap();
+    assert_eq!(row.0, 1);
+
+    // The weak handle doesn't keep the pool alive.
+    drop(upgraded);
+    let raw = pool.inner().clone();
+    drop(pool);
+    assert!(weak.upgrade().is_none());
+    assert!(!raw.is_closed());
+}
+
+#[tokio::test]
+async fn pool_dropped_event() {
+    assert_eq!(sqlx_tracing::names::POOL_DROPPED, "sqlx.pool.dropped");
+
+    // Dropping the last handle with open connections warns, while clones and
+    // closed pools don't.
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool).build();
+    let clone = pool.clone();
+    drop(pool);
+    let row: (i32,) = sqlx::query_as("SELECT 1").fetch_one(&clone).await.unwrap();
+    assert_eq!(row.0, 1);
+    assert!(clone.size() > 0);
+    drop(clone);
+
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool).build();
+    pool.close().await;
+    drop(pool);
+}
+
+#[tokio::test]
+async fn empty_statements() {
+    use std::borrow::Cow;
+    use std::sync::{Arc, Mutex};
+
+    assert_eq!(sqlx_tracing::names::STATEMENT_EMPTY, "sqlx.statement.empty");
+
+    for (behavior, exported) in [
+        (sqlx_tracing::EmptyStatements::Flag, 2),
+        (sqlx_tracing::EmptyStatements::Skip, 1),
+        (sqlx_tracing::EmptyStatements::Trace, 2),
+    ] {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = records.clone();
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        let pool = sqlx_tracing::PoolBuilder::from(pool)
+            .with_empty_statements(behavior)
+            .with_query_rewriter(|sql: &str| match sql {
+                "SELECT 'blank'" => Cow::Owned("  \n".to_owned()),
+                _ => Cow::Borrowed(sql),
+            })
+            .on_span_complete(move |record| sink.lock().unwrap().push(record.clone()))
+            .build();
+
+        // Statements left empty by the rewriter still reach the database.
+        let result = sqlx::query("SELECT 'blank'").execute(&pool).await.unwrap();
+        assert_eq!(result.rows_affected(), 0);
+        let value: i64 = sqlx::query_scalar("SELECT 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(value, 1);
+
+        assert_eq!(records.lock().unwrap().len(), exported, "{behavior:?}");
+    }
+}
+
+#[tokio::test]
+async fn untraced_block() {
+    use std::sync::{Arc, Mutex};
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = records.clone();
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_statement_grouping(true)
+        .on_span_complete(move |record| sink.lock().unwrap().push(record.clone()))
+        .build();
+
+    let mut conn = pool.acquire().await.unwrap();
+    let sum = sqlx_tracing::untraced(async {
+        let mut sum = 0;
+        for i in 0..3_i64 {
+            let value: i64 = sqlx::query_scalar("SELECT ?")
+                .bind(i)
+                .fetch_one(&mut conn)
+                .await?;
+            sum += value;
+        }
+        let mut tx = pool.begin().await?;
+        sqlx::query("SELECT 1").execute(&mut tx.executor()).await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(sum)
+    })
+    .await
+    .unwrap();
+    assert_eq!(sum, 3);
+    assert!(records.lock().unwrap().is_empty());
+
+    // Tracing resumes after the block.
+    let value: i64 = sqlx::query_scalar("SELECT ?")
+        .bind(4_i64)
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(value, 4);
+    assert_eq!(records.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn query_budget() {
+    use futures::TryStreamExt;
+    use sqlx_tracing::{QueryBudget, QueryBudgetExceeded, with_budget};
+
+    assert_eq!(
+        sqlx_tracing::names::QUERY_BUDGET_EXHAUSTED,
+        "sqlx.query_budget.exhausted"
+    );
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool).build();
+
+    // Without enforcement, exhausting the budget only warns.
+    let budget = QueryBudget::new().with_max_statements(2);
+    with_budget(budget, async {
+        for _ in 0..3 {
+            sqlx::query("SELECT 1").execute(&pool).await.unwrap();
+        }
+    })
+    .await;
+
+    // Enforced, the statements started once it is exhausted fail, including
+    // streams and statements of nested budgets.
+    let budget = budget.with_enforcement(true);
+    with_budget(budget, async {
+        let rows: Vec<(i64,)> = sqlx::query_as::<_, (i64,)>("SELECT 1")
+            .fetch(&pool)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(rows, [(1,)]);
+        with_budget(QueryBudget::new(), async {
+            sqlx::query("SELECT 1").execute(&pool).await.unwrap();
+        })
+        .await;
+
+        let err = sqlx::query("SELECT 1").execute(&pool).await.unwrap_err();
+        assert!(QueryBudgetExceeded::is(&err), "{err}");
+        let err = sqlx::query_as::<_, (i64,)>("SELECT 1")
+            .fetch(&pool)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap_err();
+        assert!(QueryBudgetExceeded::is(&err), "{err}");
+    })
+    .await;
+
+    // A spent time budget fails the next statement.
+    let budget = QueryBudget::new()
+        .with_max_duration(std::time::Duration::ZERO)
+        .with_enforcement(true);
+    let err = with_budget(budget, sqlx::query("SELECT 1").execute(&pool))
+        .await
+        .unwrap_err();
+    assert!(QueryBudgetExceeded::is(&err), "{err}");
+
+    // Outside of a budget, statements are not limited.
+    sqlx::query("SELECT 1").execute(&pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn resource_attributes() {
+    let attributes = sqlx_tracing::Attributes::builder()
+        .with_resource_attributes([("service.version", "1.2.3")])
+        .with_ssl_mode("require")
+        .with_tls(true)
+        .build();
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_attributes(attributes)
+        .with_resource_attributes([("host.name", "web-1"), ("service.version", "1.2.4")])
+        .build();
+    assert_eq!(
+        pool.attributes().resource_attributes().collect::<Vec<_>>(),
+        [("host.name", "web-1"), ("service.version", "1.2.4")]
+    );
+
+    let capture = Capture::install();
+    let row: (i32,) = sqlx::query_as("SELECT 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(row.0, 1);
+    let mut conn = pool.acquire().await.unwrap();
+    conn.ping().await.unwrap();
+
+    // The SSL mode and TLS set on the shared attributes are kept.
+    let span = capture.span("sqlx.connection.ping");
+    assert_eq!(span.field("db.ssl_mode"), Some("require"));
+    assert_eq!(span.field("tls.established"), Some("true"));
+}
+
+#[tokio::test]
+async fn cached_pool() {
+    use std::time::Duration;
+
+    assert_eq!(sqlx_tracing::names::CACHE_FETCH, "sqlx.cache.fetch");
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool).build();
+    sqlx::query("CREATE TABLE users (id INTEGER, name TEXT)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO users VALUES (1, 'alice'), (2, 'bob')")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let cached = sqlx_tracing::CachedPool::new(pool, Duration::from_secs(60));
+    let sql = "SELECT name FROM users WHERE id = ?";
+    let name = |id: i64| {
+        cached.fetch(sql, id, move |pool| {
+            sqlx::query_scalar::<_, String>(sql)
+                .bind(id)
+                .fetch_one(pool)
+        })
+    };
+    assert_eq!(name(1).await.unwrap(), "alice");
+
+    // Hits return the cached result, other parameters are fetched.
+    sqlx::query("UPDATE users SET name = 'carol'")
+        .execute(cached.pool())
+        .await
+        .unwrap();
+    assert_eq!(name(1).await.unwrap(), "alice");
+    assert_eq!(name(2).await.unwrap(), "carol");
+
+    cached.invalidate(sql, 1_i64);
+    assert_eq!(name(1).await.unwrap(), "carol");
+
+    // Errors are not cached.
+    let missing = |id: i64| {
+        cached.fetch(sql, id, move |pool| {
+            sqlx::query_scalar::<_, String>(sql)
+                .bind(id)
+                .fetch_one(pool)
+        })
+    };
+    assert!(matches!(missing(3).await, Err(sqlx::Error::RowNotFound)));
+    sqlx::query("INSERT INTO users VALUES (3, 'dave')")
+        .execute(cached.pool())
+        .await
+        .unwrap();
+    assert_eq!(missing(3).await.unwrap(), "dave");
+
+    cached.invalidate_all();
+    let expired = sqlx_tracing::CachedPool::new(cached.pool().clone(), Duration::ZERO);
+    let count = || {
+        expired.fetch("SELECT COUNT(*) FROM users", (), |pool| {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users").fetch_one(pool)
+        })
+    };
+    assert_eq!(count().await.unwrap(), 3);
+    sqlx::query("DELETE FROM users")
+        .execute(expired.pool())
+        .await
+        .unwrap();
+    assert_eq!(count().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn pool_partitions() {
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(8)
+        .connect("sqlite:file:partitions?mode=memory&cache=shared")
+        .await
+        .unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_pool_name("main")
+        .with_resource_attributes([("deployment.region", "eu-west-1")])
+        .build();
+    sqlx::query("CREATE TABLE jobs (id INTEGER)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let interactive = pool.partition("interactive", 4).build();
+    let batch = pool.partition("batch", 1).build();
+    assert_eq!(batch.attributes().pool_name(), "main.batch");
+    assert_eq!(batch.attributes().workload(), Some("batch"));
+    assert_eq!(
+        batch.attributes().resource_attributes().collect::<Vec<_>>(),
+        [("deployment.region", "eu-west-1")]
+    );
+    assert_eq!(pool.attributes().workload(), None);
+
+    // Partitions reach the same database, with their own connection budget.
+    sqlx::query("INSERT INTO jobs VALUES (1)")
+        .execute(&batch)
+        .await
+        .unwrap();
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM jobs")
+        .fetch_one(&interactive)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+    let _held = batch.acquire().await.unwrap();
+    assert!(batch.try_acquire().is_none());
+    assert_eq!(batch.size(), 1);
+    interactive.acquire().await.unwrap();
+}
+
+#[tokio::test]
+async fn owned_pool_executor() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = std::sync::Arc::new(sqlx_tracing::Pool::from(pool));
+
+    // A clone of the pool is an executor, so its statements can outlive the
+    // handle they were started from.
+    let task =
+        tokio::spawn(sqlx::query_scalar("SELECT 1").fetch_one(sqlx_tracing::Pool::clone(&pool)));
+    let one: i32 = task.await.unwrap().unwrap();
+    assert_eq!(one, 1);
+    let rows = sqlx::query("SELECT 1 UNION ALL SELECT 2")
+        .fetch_all(pool.as_ref().clone())
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+}
+
+#[tokio::test]
+async fn cardinality_limit() {
+    use std::sync::{Arc, Mutex};
+
+    assert_eq!(
+        sqlx_tracing::names::CARDINALITY_LIMITED,
+        "sqlx.cardinality.limited"
+    );
+
+    // Pools built with the same attributes share the limit, as with a pool
+    // per tenant.
+    let attributes = sqlx_tracing::Attributes::builder()
+        .with_cardinality_limit(2, 16)
+        .build();
+    let records = Arc::new(Mutex::new(Vec::new()));
+    for name in [
+        "tenant-1",
+        "tenant-2",
+        "tenant-3",
+        "tenant-1",
+        "tenant-with-a-long-name",
+    ] {
+        let sink = records.clone();
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        let pool = sqlx_tracing::PoolBuilder::from(pool)
+            .with_attributes(attributes.clone())
+            .with_pool_name(name)
+            .on_span_complete(move |record| sink.lock().unwrap().push(record.clone()))
+            .build();
+        sqlx::query("SELECT 1").execute(&pool).await.unwrap();
+    }
+
+    let names = records
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|record| record.pool_name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(names[..4], ["tenant-1", "tenant-2", "(other)", "tenant-1"]);
+    // Long values are hashed, then limited like the others.
+    assert_eq!(names[4], "(other)");
+
+    let sink = records.clone();
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_pool_name("tenant-with-a-long-name")
+        .with_cardinality_limit(10, 16)
+        .on_span_complete(move |record| sink.lock().unwrap().push(record.clone()))
+        .build();
+    sqlx::query("SELECT 1").execute(&pool).await.unwrap();
+    let name = records.lock().unwrap().last().unwrap().pool_name.clone();
+    assert_eq!(name.len(), 16);
+    assert_ne!(name, "tenant-with-a-lo");
+}
+
+#[cfg(feature = "migrate")]
+#[tokio::test]
+async fn database_helpers() {
+    use sqlx_tracing::Operation;
+
+    assert_eq!(
+        Operation::from_span_name(sqlx_tracing::names::DATABASE_CREATE),
+        Some(Operation::CreateDatabase)
+    );
+
+    let path = std::env::temp_dir().join(format!("sqlx-tracing-{}.db", std::process::id()));
+    let url = format!("sqlite://{}", path.display());
+    assert!(!sqlx_tracing::database_exists::<Sqlite>(&url).await.unwrap());
+    sqlx_tracing::create_database::<Sqlite>(&url).await.unwrap();
+    assert!(sqlx_tracing::database_exists::<Sqlite>(&url).await.unwrap());
+    sqlx_tracing::drop_database::<Sqlite>(&url).await.unwrap();
+    assert!(!sqlx_tracing::database_exists::<Sqlite>(&url).await.unwrap());
+}
+
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn test_database() {
+    use sqlx_tracing::testing::TestDatabase;
+
+    let db = TestDatabase::sqlite().await.unwrap();
+    let other = TestDatabase::sqlite().await.unwrap();
+    assert_ne!(db.name(), other.name());
+    assert_eq!(db.pool().attributes().pool_name(), db.name());
+    let path = std::env::temp_dir().join(format!("{}.db", db.name()));
+    assert!(path.exists());
+
+    sqlx::query("CREATE TABLE users (name TEXT)")
+        .execute(db.pool())
+        .await
+        .unwrap();
+    db.teardown().await.unwrap();
+    assert!(!path.exists());
+
+    // Dropping the fixture removes its file as well.
+    let path = std::env::temp_dir().join(format!("{}.db", other.name()));
+    other.pool().close().await;
+    drop(other);
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn raw_handle() {
+    use sqlx_tracing::Operation;
+
+    assert_eq!(
+        Operation::from_span_name(sqlx_tracing::names::SQLITE_RAW),
+        Some(Operation::SqliteRaw)
+    );
+
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::Pool::from(pool);
+    let mut conn = pool.acquire().await.unwrap();
+    conn.with_raw_handle(|handle| handle.create_collation("reversed", |a, b| b.cmp(a)))
+        .await
+        .unwrap()
+        .unwrap();
+    let names: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM (SELECT 'a' AS name UNION ALL SELECT 'b') ORDER BY name COLLATE reversed",
+    )
+    .fetch_all(&mut conn)
+    .await
+    .unwrap();
+    assert_eq!(names, ["b", "a"]);
+
+    // Within a transaction, the handle is the one of its connection.
+    let mut tx = pool.begin().await.unwrap();
+    tx.with_raw_handle(|handle| handle.create_collation("ordered", |a, b| a.cmp(b)))
+        .await
+        .unwrap()
+        .unwrap();
+    let names: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM (SELECT 'b' AS name UNION ALL SELECT 'a') ORDER BY name COLLATE ordered",
+    )
+    .fetch_all(&mut tx.executor())
+    .await
+    .unwrap();
+    assert_eq!(names, ["a", "b"]);
+    tx.rollback().await.unwrap();
+}
+
+#[derive(Default)]
+struct Total(i64);
+
+impl sqlx_tracing::sqlite::SqliteAggregate for Total {
+    fn step(&mut self, args: &[SqliteValue]) -> Result<(), String> {
+        match args[0] {
+            SqliteValue::Integer(value) => self.0 += value,
+            SqliteValue::Null => {}
+            _ => return Err("total() takes integers".into()),
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<SqliteValue, String> {
+        Ok(SqliteValue::Integer(self.0))
+    }
+}
+
+#[tokio::test]
+async fn custom_functions() {
+    use sqlx_tracing::Operation;
+
+    assert_eq!(
+        Operation::from_span_name(sqlx_tracing::names::SQLITE_FUNCTION),
+        Some(Operation::SqliteFunction)
+    );
+
+    let pool = sqlx_tracing::PoolOptions::<sqlx::Sqlite>::new()
+        .after_connect(|conn, _| {
+            Box::pin(async move {
+                sqlx_tracing::sqlite::create_function(conn, "double", 1, |args| match &args[0] {
+                    SqliteValue::Integer(value) => Ok(SqliteValue::Integer(value * 2)),
+                    SqliteValue::Text(text) => Ok(SqliteValue::Text(text.repeat(2))),
+                    _ => Err("double() takes an integer or a text".into()),
+                })
+                .await?;
+                sqlx_tracing::sqlite::create_aggregate::<Total>(conn, "total_of", 1).await
+            })
+        })
+        .connect(":memory:")
+        .await
+        .unwrap()
+        .build();
+
+    let (number, text): (i64, String) = sqlx::query_as("SELECT double(21), double('ab')")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!((number, text), (42, "abab".to_owned()));
+
+    let total: i64 = sqlx::query_scalar(
+        "SELECT total_of(value) FROM (SELECT 1 AS value UNION ALL SELECT 2 UNION ALL SELECT NULL)",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(total, 3);
+
+    // An aggregation over no rows finishes with the default value.
+    let total: i64 = sqlx::query_scalar("SELECT total_of(1) WHERE 0")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(total, 0);
+
+    // Errors of the functions fail their statement.
+    let err = sqlx::query("SELECT double(1.5)")
+        .execute(&pool)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("double() takes an integer or a text")
+    );
+    let err = sqlx::query("SELECT total_of('a')")
+        .execute(&pool)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("total() takes integers"));
+
+    let mut conn = pool.acquire().await.unwrap();
+    let err = sqlx_tracing::sqlite::create_function(conn.as_mut(), "bad\0name", 0, |_| {
+        Ok(SqliteValue::Null)
+    })
+    .await
+    .unwrap_err();
+    assert!(matches!(err, sqlx::Error::InvalidArgument(_)));
+}
+
+/// Sets its flag when dropped.
+struct DropFlag(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[derive(Default)]
+struct Exploding;
+
+impl sqlx_tracing::sqlite::SqliteAggregate for Exploding {
+    fn step(&mut self, _args: &[SqliteValue]) -> Result<(), String> {
+        panic!("step exploded");
+    }
+
+    fn finish(self) -> Result<SqliteValue, String> {
+        Ok(SqliteValue::Null)
+    }
+}
+
+#[tokio::test]
+async fn custom_function_panics_and_drops() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use sqlx::Connection;
+
+    let mut conn = sqlx::SqliteConnection::connect(":memory:").await.unwrap();
+    let first = Arc::new(AtomicBool::new(false));
+    let flag = DropFlag(first.clone());
+    sqlx_tracing::sqlite::create_function(&mut conn, "explode", 0, move |_| {
+        let _flag = &flag;
+        panic!("function exploded")
+    })
+    .await
+    .unwrap();
+    sqlx_tracing::sqlite::create_aggregate::<Exploding>(&mut conn, "explode_all", 1)
+        .await
+        .unwrap();
+
+    // Panics fail the statement instead of unwinding into SQLite, and leave
+    // the connection usable.
+    let err = sqlx::query("SELECT explode()")
+        .execute(&mut conn)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("custom function panicked"));
+    let err = sqlx::query("SELECT explode_all(1)")
+        .execute(&mut conn)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("custom function panicked"));
+    let value: i64 = sqlx::query_scalar("SELECT 1")
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(value, 1);
+
+    // The user data of a function is dropped once the function is replaced,
+    // and that of its replacement when the connection closes.
+    assert!(!first.load(Ordering::SeqCst));
+    let second = Arc::new(AtomicBool::new(false));
+    let flag = DropFlag(second.clone());
+    sqlx_tracing::sqlite::create_function(&mut conn, "explode", 0, move |_| {
+        let _flag = &flag;
+        Ok(SqliteValue::Null)
+    })
+    .await
+    .unwrap();
+    assert!(first.load(Ordering::SeqCst));
+    assert!(!second.load(Ordering::SeqCst));
+    conn.close().await.unwrap();
+    assert!(second.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn settings_recording() {
+    let pool = sqlx_tracing::PoolOptions::<Sqlite>::new()
+        .with_settings_recording(true)
+        .after_connect(|conn, _meta| {
+            Box::pin(async move {
+                sqlx::query("PRAGMA cache_size = -4000")
+                    .execute(conn)
+                    .await
+                    .map(drop)
+            })
+        })
+        .connect(":memory:")
+        .await
+        .unwrap()
+        .build();
+
+    // The user callback still runs, before the settings are queried.
+    let cache_size: i64 = sqlx::query_scalar("PRAGMA cache_size")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(cache_size, -4000);
+}
+
+#[tokio::test]
+async fn statement_cache_capacity() {
+    assert_eq!(
+        sqlx_tracing::names::STATEMENT_CACHE_EXCEEDED,
+        "sqlx.statement_cache.exceeded"
+    );
+
+    // A disabled cache is not watched.
+    let attributes = sqlx_tracing::Attributes::builder()
+        .with_statement_cache_capacity(0)
+        .build();
+    assert_eq!(attributes.statement_cache_capacity(), None);
+
+    let attributes = sqlx_tracing::Attributes::builder()
+        .with_statement_cache_capacity(2)
+        .build();
+    assert_eq!(attributes.statement_cache_capacity(), Some(2));
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_attributes(attributes)
+        .build();
+    assert_eq!(pool.attributes().statement_cache_capacity(), Some(2));
+
+    // Exceeding the capacity only warns.
+    for value in 1..=4 {
+        let sql = format!("SELECT {value}");
+        let got: i64 = sqlx::query_scalar(&sql).fetch_one(&pool).await.unwrap();
+        assert_eq!(got, value);
+    }
+}
+
+#[tokio::test]
+async fn error_status() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use sqlx_tracing::ErrorStatus;
+
+    assert_eq!(sqlx_tracing::names::ERROR_EXPECTED, "sqlx.error.expected");
+    assert_eq!(ErrorStatus::default(), ErrorStatus::Error);
+
+    let expected = Arc::new(AtomicUsize::new(0));
+    let counter = expected.clone();
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .with_error_status(move |err| match err.as_database_error() {
+            Some(db_err) if db_err.is_unique_violation() => {
+                counter.fetch_add(1, Ordering::Relaxed);
+                ErrorStatus::Ok
+            }
+            _ => ErrorStatus::Error,
+        })
+        .build();
+    sqlx::query("CREATE TABLE users (name TEXT PRIMARY KEY)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO users VALUES ('alice')")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // The operation still fails, only its span is successful.
+    let err = sqlx::query("INSERT INTO users VALUES ('alice')")
+        .execute(&pool)
+        .await
+        .unwrap_err();
+    assert!(err.as_database_error().unwrap().is_unique_violation());
+    assert_eq!(expected.load(Ordering::Relaxed), 1);
+
+    // Other errors are mapped too, and keep failing their span.
+    sqlx::query("SELECT * FROM missing")
+        .execute(&pool)
+        .await
+        .unwrap_err();
+    assert_eq!(expected.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn prelude() {
+    use sqlx_tracing::prelude::*;
+
+    #[derive(FromRow)]
+    struct User {
+        name: String,
+    }
+
+    let pool: Pool<Sqlite> = sqlx::SqlitePool::connect(":memory:").await.unwrap().into();
+    let mut tx: Transaction<'_, Sqlite> = pool.begin().await.unwrap();
+    query("CREATE TABLE users (name TEXT)")
+        .execute(&mut tx.executor())
+        .await
+        .unwrap();
+    query("INSERT INTO users VALUES ('alice')")
+        .execute(&mut tx.executor())
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let users: Vec<User> = query_as("SELECT name FROM users")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].name, "alice");
+    let count: i64 = query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+    let mut conn: PoolConnection<Sqlite> = pool.acquire().await.unwrap();
+    let row = query("SELECT name FROM users")
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(row.get::<String, _>("name"), "alice");
+}
+
+/// Inserts a user through any executor borrowed for the call only.
+async fn insert_user<'c, E>(executor: E, name: &str) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'c, Database = Sqlite>,
+{
+    sqlx::query("INSERT INTO users VALUES (?)")
+        .bind(name)
+        .execute(executor)
+        .await
+        .map(drop)
+}
+
+#[tokio::test]
+async fn reborrowed_executors() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::Pool::from(pool);
+    let mut tx = pool.begin().await.unwrap();
+
+    // The same executor is reused across awaits, loops and helper calls.
+    let mut executor = tx.executor();
+    sqlx::query("CREATE TABLE users (name TEXT)")
+        .execute(&mut executor)
+        .await
+        .unwrap();
+    for name in ["alice", "bob"] {
+        insert_user(&mut executor, name).await.unwrap();
+    }
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(&mut executor)
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+
+    // So is the transaction itself.
+    for name in ["carol", "dave"] {
+        insert_user(&mut tx, name).await.unwrap();
+    }
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(&mut tx)
+        .await
+        .unwrap();
+    assert_eq!(count, 4);
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn pool_query_shorthands() {
+    #[derive(sqlx::FromRow)]
+    struct User {
+        name: String,
+    }
+
+    let records = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = records.clone();
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::PoolBuilder::from(pool)
+        .on_span_complete(move |record| sink.lock().unwrap().push(record.operation))
+        .build();
+
+    let result = pool
+        .execute_sql("CREATE TABLE users (name TEXT)")
+        .await
+        .unwrap();
+    assert_eq!(result.rows_affected(), 0);
+    let result = pool
+        .execute_sql("INSERT INTO users VALUES ('alice'), ('bob')")
+        .await
+        .unwrap();
+    assert_eq!(result.rows_affected(), 2);
+
+    let (count,): (i64,) = pool
+        .fetch_one_as("SELECT COUNT(*) FROM users")
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+    let users: Vec<User> = pool
+        .fetch_all_as("SELECT name FROM users ORDER BY name")
+        .await
+        .unwrap();
+    let names: Vec<_> = users.into_iter().map(|user| user.name).collect();
+    assert_eq!(names, ["alice", "bob"]);
+    let err = pool
+        .fetch_one_as::<(String,)>("SELECT name FROM users WHERE 0")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, sqlx::Error::RowNotFound));
+
+    // They are traced like the executor methods they stand for.
+    use sqlx_tracing::Operation;
+    assert_eq!(
+        *records.lock().unwrap(),
+        [
+            Operation::Execute,
+            Operation::Execute,
+            Operation::FetchOne,
+            Operation::FetchAll,
+            Operation::FetchOne,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn transaction_reports() {
+    let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+    let pool = sqlx_tracing::Pool::from(pool);
+    sqlx::query("CREATE TABLE users (name TEXT)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    for name in ["alice", "bob"] {
+        sqlx::query("INSERT INTO users VALUES (?)")
+            .bind(name)
+            .execute(&mut tx.executor())
+            .await
+            .unwrap();
+    }
+    let savepoint = tx.begin().await.unwrap();
+    savepoint.commit().await.unwrap();
+    let names: Vec<String> = sqlx::query_scalar("SELECT name FROM users")
+        .fetch_all(&mut tx)
+        .await
+        .unwrap();
+    assert_eq!(names.len(), 2);
+    let report = tx.commit_with_report().await.unwrap();
+    assert_eq!(report.statements, 3);
+    assert_eq!(report.savepoints, 1);
+    assert!(report.database_time > std::time::Duration::ZERO);
+    assert!(report.duration >= report.database_time + report.idle_time);
+
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query("DELETE FROM users")
+        .execute(&mut tx.executor())
+        .await
+        .unwrap();
+    let report = tx.rollback_with_report().await.unwrap();
+    assert_eq!(report.statements, 1);
+    assert_eq!(report.savepoints, 0);
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+}